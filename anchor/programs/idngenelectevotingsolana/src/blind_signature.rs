@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+use curve25519_dalek::scalar::Scalar;
+use crate::elgamal::{self, BASEPOINT, IDENTITY};
+use crate::errors::ErrorCode;
+
+// Blind Schnorr signatures over Ristretto for unlinkable ballot tokens.
+// The ballot issuer (commission signing service) holds x with public key
+// X = xG. A voter obtains a signature on a random nullifier without the
+// issuer learning it:
+//   issuer:  R' = kG                          (sent off-chain)
+//   voter:   R = R' + aG + bX, c = H(X, R, n), c' = c + b   (posted with the request)
+//   issuer:  s' = k + c'x                     (posted and checked on-chain)
+//   voter:   s = s' + a                       (signature (R, s) on n)
+// The final signature satisfies sG == R + cX and shares no values with the
+// request, so the ballot cannot be matched to the credential that asked for it.
+// Plain blind Schnorr can be forged with many concurrent sessions (the ROS
+// attack), so issuance uses the clause variant: the voter opens two sessions
+// (R'_0, c'_0) and (R'_1, c'_1) at once, and the issuer answers only one,
+// chosen at random, discarding the other nonce. A forger can no longer pick
+// which sessions get answered, which defeats ROS.
+// Tokens are bound to the voter's polling station (TPS): X above is the
+// station key X_p = X + t_p G (secret x + t_p), so a ballot counts at the
+// right station and region while staying anonymous among that station's voters.
+// The nullifier is itself a one-time public key N = nG whose secret n only
+// the voter holds. A ballot carries the voter's Schnorr signature under N on
+// its contents (choice, ballot kind), so whoever sees or relays the token
+// cannot cast it for another choice.
+
+const BALLOT_TOKEN_DOMAIN: &[u8] = b"IDN-EVOTE-BALLOT-TOKEN";
+const STATION_KEY_DOMAIN: &[u8] = b"IDN-EVOTE-STATION-KEY";
+const BALLOT_CONTENTS_DOMAIN: &[u8] = b"IDN-EVOTE-BALLOT-CONTENTS";
+
+/// Schnorr signature (R, s): the unblinded token on a nullifier, or the
+/// voter's signature on ballot contents
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BlindSignature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+/// An issuer key must be a valid, non-identity point (the identity would
+/// let anyone forge signatures)
+pub fn validate_issuer_key(issuer_key: &[u8; 32]) -> Result<()> {
+    elgamal::validate_point(issuer_key)?;
+    require!(*issuer_key != IDENTITY, ErrorCode::InvalidCurvePoint);
+    Ok(())
+}
+
+/// Issuer key for tokens bound to a polling station: X + t_p G,
+/// t_p = H(election, polling_station, X)
+pub fn station_issuer_key(
+    issuer_key: &[u8; 32],
+    election: &Pubkey,
+    polling_station: &Pubkey,
+) -> Result<[u8; 32]> {
+    let tweak = elgamal::challenge_scalar(
+        STATION_KEY_DOMAIN,
+        &[election.as_ref(), polling_station.as_ref()],
+        &[issuer_key],
+    );
+    elgamal::point_add(issuer_key, &elgamal::point_mul(&tweak, &BASEPOINT.0)?)
+}
+
+/// Check the issuer's response to a blinded challenge: s'G - c'X == R'
+pub fn verify_blind_response(
+    issuer_key: &[u8; 32],
+    issuer_commitment: &[u8; 32],
+    blinded_challenge: &[u8; 32],
+    blind_response: &[u8; 32],
+) -> Result<()> {
+    let c = scalar(blinded_challenge)?;
+    let s = scalar(blind_response)?;
+    let commitment = elgamal::commitment(&s, &BASEPOINT.0, &c, issuer_key)?;
+    require!(commitment == *issuer_commitment, ErrorCode::InvalidBlindSignature);
+    Ok(())
+}
+
+/// Verify an unblinded ballot token signature on `nullifier`: sG - cX == R
+pub fn verify_ballot_token(
+    issuer_key: &[u8; 32],
+    election: &Pubkey,
+    nullifier: &[u8; 32],
+    signature: &BlindSignature,
+) -> Result<()> {
+    let c = elgamal::challenge_scalar(
+        BALLOT_TOKEN_DOMAIN,
+        &[election.as_ref()],
+        &[issuer_key, &signature.r, nullifier],
+    );
+    let s = scalar(&signature.s)?;
+    let commitment = elgamal::commitment(&s, &BASEPOINT.0, &c, issuer_key)?;
+    require!(commitment == signature.r, ErrorCode::InvalidBlindSignature);
+    Ok(())
+}
+
+/// Verify the voter's signature on a ballot's contents under the nullifier
+/// key N: sG - cN == R, c = H(election, contents, N, R)
+/// Each part of `contents` must have a fixed width so the parts cannot be
+/// shifted into one another
+pub fn verify_ballot_contents(
+    election: &Pubkey,
+    nullifier: &[u8; 32],
+    contents: &[&[u8]],
+    signature: &BlindSignature,
+) -> Result<()> {
+    // With the identity as N anyone could sign for the ballot
+    require!(
+        elgamal::validate_point(nullifier).is_ok() && *nullifier != IDENTITY,
+        ErrorCode::InvalidBallotSignature
+    );
+    let mut context: Vec<&[u8]> = Vec::with_capacity(1 + contents.len());
+    context.push(election.as_ref());
+    context.extend_from_slice(contents);
+    let c = elgamal::challenge_scalar(BALLOT_CONTENTS_DOMAIN, &context, &[nullifier, &signature.r]);
+    let s = elgamal::canonical_scalar(&signature.s)
+        .map_err(|_| error!(ErrorCode::InvalidBallotSignature))?;
+    let commitment = elgamal::commitment(&s, &BASEPOINT.0, &c, nullifier)?;
+    require!(commitment == signature.r, ErrorCode::InvalidBallotSignature);
+    Ok(())
+}
+
+fn scalar(bytes: &[u8; 32]) -> Result<Scalar> {
+    elgamal::canonical_scalar(bytes).map_err(|_| error!(ErrorCode::InvalidBlindSignature))
+}
+
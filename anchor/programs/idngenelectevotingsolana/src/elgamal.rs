@@ -0,0 +1,590 @@
+use anchor_lang::prelude::*;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_COMPRESSED;
+use curve25519_dalek::scalar::Scalar;
+use solana_curve25519::ristretto::{
+    add_ristretto, multiscalar_multiply_ristretto, multiply_ristretto, subtract_ristretto,
+    validate_ristretto, PodRistrettoPoint,
+};
+use solana_curve25519::scalar::PodScalar;
+use crate::errors::ErrorCode;
+
+// Exponential ElGamal over Ristretto for encrypted tallies.
+// A vote m in {0, 1} for one candidate is encrypted under the joint
+// commission key Y as (C1, C2) = (rG, mG + rY). Ciphertexts add
+// homomorphically, so the per-candidate sums encrypt the vote counts.
+// Point arithmetic goes through the curve25519 syscalls; only scalar
+// arithmetic (challenges, Lagrange checks) is done in-program.
+
+/// Ristretto base point G
+pub const BASEPOINT: PodRistrettoPoint = PodRistrettoPoint(RISTRETTO_BASEPOINT_COMPRESSED.0);
+
+/// Compressed Ristretto identity (encryption of 0 with r = 0)
+pub const IDENTITY: [u8; 32] = [0u8; 32];
+
+const BALLOT_PROOF_DOMAIN: &[u8] = b"IDN-EVOTE-BALLOT";
+const BALLOT_SUM_DOMAIN: &[u8] = b"IDN-EVOTE-BALLOT-SUM";
+const DECRYPTION_PROOF_DOMAIN: &[u8] = b"IDN-EVOTE-DECRYPT";
+
+/// ElGamal ciphertext (C1, C2) = (rG, mG + rY)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct ElGamalCiphertext {
+    pub c1: [u8; 32],
+    pub c2: [u8; 32],
+}
+
+impl Default for ElGamalCiphertext {
+    fn default() -> Self {
+        Self { c1: IDENTITY, c2: IDENTITY }
+    }
+}
+
+impl ElGamalCiphertext {
+    /// Homomorphic addition: encrypts the sum of both plaintexts
+    pub fn add(&self, other: &ElGamalCiphertext) -> Result<ElGamalCiphertext> {
+        Ok(ElGamalCiphertext {
+            c1: point_add(&self.c1, &other.c1)?,
+            c2: point_add(&self.c2, &other.c2)?,
+        })
+    }
+}
+
+/// Disjunctive Chaum-Pedersen proof that a ciphertext encrypts 0 or 1
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct ZeroOneProof {
+    pub challenge_0: [u8; 32],
+    pub challenge_1: [u8; 32],
+    pub response_0: [u8; 32],
+    pub response_1: [u8; 32],
+}
+
+/// Chaum-Pedersen proof that log_A(X) == log_B(Y)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct EqualityProof {
+    pub challenge: [u8; 32],
+    pub response: [u8; 32],
+}
+
+/// Encrypted one-hot ballot: one ciphertext per candidate, each proven to
+/// encrypt 0 or 1, plus a proof that they sum to exactly 1
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EncryptedBallot {
+    pub ciphertexts: Vec<ElGamalCiphertext>,
+    pub validity_proofs: Vec<ZeroOneProof>,
+    pub sum_proof: EqualityProof,
+}
+
+/// Verify an encrypted one-hot ballot under `public_key`.
+/// `context` binds the proofs to the election and voter so a ballot
+/// cannot be copied by another voter.
+pub fn verify_ballot(
+    ballot: &EncryptedBallot,
+    public_key: &[u8; 32],
+    candidate_count: usize,
+    context: &[&[u8]],
+) -> Result<()> {
+    require!(
+        ballot.ciphertexts.len() == candidate_count
+            && ballot.validity_proofs.len() == candidate_count,
+        ErrorCode::InvalidBallotProof
+    );
+
+    let mut sum = ElGamalCiphertext::default();
+    for (ciphertext, proof) in ballot.ciphertexts.iter().zip(ballot.validity_proofs.iter()) {
+        verify_zero_one(ciphertext, proof, public_key, context)?;
+        sum = sum.add(ciphertext)?;
+    }
+
+    // The sum encrypts 1: log_G(sum.c1) == log_Y(sum.c2 - G)
+    let sum_c2_minus_one = point_sub(&sum.c2, &BASEPOINT.0)?;
+    verify_equality(
+        &ballot.sum_proof,
+        &BASEPOINT.0,
+        &sum.c1,
+        public_key,
+        &sum_c2_minus_one,
+        BALLOT_SUM_DOMAIN,
+        context,
+    )
+    .map_err(|_| error!(ErrorCode::InvalidBallotProof))
+}
+
+/// Verify a trustee's decryption share D = x_i * C1 against its public
+/// key share Y_i = x_i * G
+pub fn verify_decryption_share(
+    proof: &EqualityProof,
+    public_share: &[u8; 32],
+    c1: &[u8; 32],
+    share: &[u8; 32],
+    context: &[&[u8]],
+) -> Result<()> {
+    verify_equality(
+        proof,
+        &BASEPOINT.0,
+        public_share,
+        c1,
+        share,
+        DECRYPTION_PROOF_DOMAIN,
+        context,
+    )
+    .map_err(|_| error!(ErrorCode::InvalidDecryptionProof))
+}
+
+/// Public key share of trustee `index` from Feldman commitments:
+/// Y_i = sum_j index^j * A_j
+pub fn trustee_public_share(commitments: &[[u8; 32]], index: u8) -> Result<[u8; 32]> {
+    let index = Scalar::from(index as u64);
+    let mut power = Scalar::ONE;
+    let mut scalars = Vec::with_capacity(commitments.len());
+    let mut points = Vec::with_capacity(commitments.len());
+    for commitment in commitments {
+        scalars.push(PodScalar(power.to_bytes()));
+        points.push(PodRistrettoPoint(*commitment));
+        power *= index;
+    }
+
+    multiscalar_multiply_ristretto(&scalars, &points)
+        .map(|point| point.0)
+        .ok_or(error!(ErrorCode::InvalidCurvePoint))
+}
+
+/// Check a claimed Lagrange coefficient at zero for trustee `index` over
+/// the set `indices`: lambda * prod(j - i) == prod(j) for j != i.
+/// Verifying by multiplication avoids an in-program scalar inversion.
+pub fn verify_lagrange_coefficient(lambda: &[u8; 32], index: u8, indices: &[u8]) -> Result<Scalar> {
+    let lambda = canonical_scalar(lambda)
+        .map_err(|_| error!(ErrorCode::InvalidLagrangeCoefficient))?;
+    let i = Scalar::from(index as u64);
+    let mut numerator = Scalar::ONE;
+    let mut denominator = Scalar::ONE;
+    for &other in indices.iter().filter(|&&other| other != index) {
+        let j = Scalar::from(other as u64);
+        numerator *= j;
+        denominator *= j - i;
+    }
+
+    require!(
+        lambda * denominator == numerator,
+        ErrorCode::InvalidLagrangeCoefficient
+    );
+
+    Ok(lambda)
+}
+
+/// Combine decryption shares and check C2 - sum(lambda_i * D_i) == count * G
+pub fn verify_decrypted_count(
+    c2: &[u8; 32],
+    lambdas: &[Scalar],
+    shares: &[[u8; 32]],
+    count: u64,
+) -> Result<()> {
+    let scalars: Vec<PodScalar> = lambdas.iter().map(|l| PodScalar(l.to_bytes())).collect();
+    let points: Vec<PodRistrettoPoint> = shares.iter().map(|s| PodRistrettoPoint(*s)).collect();
+    let combined = multiscalar_multiply_ristretto(&scalars, &points)
+        .ok_or(error!(ErrorCode::InvalidCurvePoint))?;
+    let message_point = point_sub(c2, &combined.0)?;
+
+    let expected = if count == 0 {
+        IDENTITY
+    } else {
+        point_mul(&Scalar::from(count), &BASEPOINT.0)?
+    };
+    require!(message_point == expected, ErrorCode::TallyMismatch);
+
+    Ok(())
+}
+
+pub fn validate_point(point: &[u8; 32]) -> Result<()> {
+    require!(
+        validate_ristretto(&PodRistrettoPoint(*point)),
+        ErrorCode::InvalidCurvePoint
+    );
+    Ok(())
+}
+
+fn verify_zero_one(
+    ciphertext: &ElGamalCiphertext,
+    proof: &ZeroOneProof,
+    public_key: &[u8; 32],
+    context: &[&[u8]],
+) -> Result<()> {
+    let c0 = canonical_scalar(&proof.challenge_0)?;
+    let c1 = canonical_scalar(&proof.challenge_1)?;
+    let z0 = canonical_scalar(&proof.response_0)?;
+    let z1 = canonical_scalar(&proof.response_1)?;
+
+    // Branch m = 0: C1 = rG, C2 = rY
+    let a0 = commitment(&z0, &BASEPOINT.0, &c0, &ciphertext.c1)?;
+    let b0 = commitment(&z0, public_key, &c0, &ciphertext.c2)?;
+    // Branch m = 1: C1 = rG, C2 - G = rY
+    let c2_minus_one = point_sub(&ciphertext.c2, &BASEPOINT.0)?;
+    let a1 = commitment(&z1, &BASEPOINT.0, &c1, &ciphertext.c1)?;
+    let b1 = commitment(&z1, public_key, &c1, &c2_minus_one)?;
+
+    let challenge = challenge_scalar(
+        BALLOT_PROOF_DOMAIN,
+        context,
+        &[public_key, &ciphertext.c1, &ciphertext.c2, &a0, &b0, &a1, &b1],
+    );
+    require!(challenge == c0 + c1, ErrorCode::InvalidBallotProof);
+
+    Ok(())
+}
+
+/// Verify log_g1(h1) == log_g2(h2)
+fn verify_equality(
+    proof: &EqualityProof,
+    g1: &[u8; 32],
+    h1: &[u8; 32],
+    g2: &[u8; 32],
+    h2: &[u8; 32],
+    domain: &[u8],
+    context: &[&[u8]],
+) -> Result<()> {
+    let c = canonical_scalar(&proof.challenge)?;
+    let z = canonical_scalar(&proof.response)?;
+
+    let a = commitment(&z, g1, &c, h1)?;
+    let b = commitment(&z, g2, &c, h2)?;
+
+    let challenge = challenge_scalar(domain, context, &[g1, h1, g2, h2, &a, &b]);
+    require!(challenge == c, ErrorCode::InvalidDecryptionProof);
+
+    Ok(())
+}
+
+/// Sigma-protocol commitment z*g - c*h
+pub(crate) fn commitment(z: &Scalar, g: &[u8; 32], c: &Scalar, h: &[u8; 32]) -> Result<[u8; 32]> {
+    multiscalar_multiply_ristretto(
+        &[PodScalar(z.to_bytes()), PodScalar((-c).to_bytes())],
+        &[PodRistrettoPoint(*g), PodRistrettoPoint(*h)],
+    )
+    .map(|point| point.0)
+    .ok_or(error!(ErrorCode::InvalidCurvePoint))
+}
+
+/// Fiat-Shamir challenge: two domain-separated SHA-256 digests reduced mod l
+pub(crate) fn challenge_scalar(domain: &[u8], context: &[&[u8]], points: &[&[u8; 32]]) -> Scalar {
+    let mut wide = [0u8; 64];
+    for (half, chunk) in wide.chunks_mut(32).enumerate() {
+        let mut parts: Vec<&[u8]> = Vec::with_capacity(2 + context.len() + points.len());
+        parts.push(domain);
+        let counter = [half as u8];
+        parts.push(&counter);
+        parts.extend_from_slice(context);
+        parts.extend(points.iter().map(|point| point.as_slice()));
+        chunk.copy_from_slice(&solana_program::hash::hashv(&parts).to_bytes());
+    }
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+pub(crate) fn canonical_scalar(bytes: &[u8; 32]) -> Result<Scalar> {
+    Option::from(Scalar::from_canonical_bytes(*bytes)).ok_or(error!(ErrorCode::InvalidBallotProof))
+}
+
+pub(crate) fn point_add(a: &[u8; 32], b: &[u8; 32]) -> Result<[u8; 32]> {
+    add_ristretto(&PodRistrettoPoint(*a), &PodRistrettoPoint(*b))
+        .map(|point| point.0)
+        .ok_or(error!(ErrorCode::InvalidCurvePoint))
+}
+
+fn point_sub(a: &[u8; 32], b: &[u8; 32]) -> Result<[u8; 32]> {
+    subtract_ristretto(&PodRistrettoPoint(*a), &PodRistrettoPoint(*b))
+        .map(|point| point.0)
+        .ok_or(error!(ErrorCode::InvalidCurvePoint))
+}
+
+pub(crate) fn point_mul(scalar: &Scalar, point: &[u8; 32]) -> Result<[u8; 32]> {
+    multiply_ristretto(&PodScalar(scalar.to_bytes()), &PodRistrettoPoint(*point))
+        .map(|point| point.0)
+        .ok_or(error!(ErrorCode::InvalidCurvePoint))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTEXT: &[&[u8]] = &[b"election", b"voter"];
+
+    /// Deterministic nonce for the prover side of the tests
+    fn nonce(label: &[u8]) -> Scalar {
+        challenge_scalar(b"IDN-EVOTE-TEST-NONCE", &[label], &[])
+    }
+
+    fn mul_base(scalar: &Scalar) -> [u8; 32] {
+        point_mul(scalar, &BASEPOINT.0).unwrap()
+    }
+
+    /// Prove log_g1(h1) == log_g2(h2) == x
+    fn prove_equality(
+        x: &Scalar,
+        (g1, h1): (&[u8; 32], &[u8; 32]),
+        (g2, h2): (&[u8; 32], &[u8; 32]),
+        domain: &[u8],
+        context: &[&[u8]],
+    ) -> EqualityProof {
+        let w = nonce(h2);
+        let a = point_mul(&w, g1).unwrap();
+        let b = point_mul(&w, g2).unwrap();
+        let c = challenge_scalar(domain, context, &[g1, h1, g2, h2, &a, &b]);
+        EqualityProof {
+            challenge: c.to_bytes(),
+            response: (w + c * x).to_bytes(),
+        }
+    }
+
+    /// Encrypt m in {0, 1} with a proof, simulating the other branch
+    fn encrypt_bit(
+        m: u8,
+        r: &Scalar,
+        public_key: &[u8; 32],
+        label: &[u8],
+    ) -> (ElGamalCiphertext, ZeroOneProof) {
+        let c1 = mul_base(r);
+        let mut c2 = point_mul(r, public_key).unwrap();
+        if m == 1 {
+            c2 = point_add(&c2, &BASEPOINT.0).unwrap();
+        }
+        let ciphertext = ElGamalCiphertext { c1, c2 };
+        let c2_minus_one = point_sub(&c2, &BASEPOINT.0).unwrap();
+
+        let w = nonce(&[label, b"w"].concat());
+        let c_sim = nonce(&[label, b"c"].concat());
+        let z_sim = nonce(&[label, b"z"].concat());
+        let sim_h2 = if m == 0 { &c2_minus_one } else { &c2 };
+        let real_a = mul_base(&w);
+        let real_b = point_mul(&w, public_key).unwrap();
+        let sim_a = commitment(&z_sim, &BASEPOINT.0, &c_sim, &c1).unwrap();
+        let sim_b = commitment(&z_sim, public_key, &c_sim, sim_h2).unwrap();
+
+        let (a0, b0, a1, b1) = if m == 0 {
+            (real_a, real_b, sim_a, sim_b)
+        } else {
+            (sim_a, sim_b, real_a, real_b)
+        };
+        let challenge = challenge_scalar(
+            BALLOT_PROOF_DOMAIN,
+            CONTEXT,
+            &[public_key, &c1, &c2, &a0, &b0, &a1, &b1],
+        );
+        let c_real = challenge - c_sim;
+        let z_real = w + c_real * r;
+        let proof = if m == 0 {
+            ZeroOneProof {
+                challenge_0: c_real.to_bytes(),
+                challenge_1: c_sim.to_bytes(),
+                response_0: z_real.to_bytes(),
+                response_1: z_sim.to_bytes(),
+            }
+        } else {
+            ZeroOneProof {
+                challenge_0: c_sim.to_bytes(),
+                challenge_1: c_real.to_bytes(),
+                response_0: z_sim.to_bytes(),
+                response_1: z_real.to_bytes(),
+            }
+        };
+        (ciphertext, proof)
+    }
+
+    /// One-hot ballot marking `bits` (not necessarily one-hot, to test rejection)
+    fn encrypt_ballot(bits: &[u8], public_key: &[u8; 32], label: &[u8]) -> EncryptedBallot {
+        let mut ciphertexts = Vec::new();
+        let mut validity_proofs = Vec::new();
+        let mut r_sum = Scalar::ZERO;
+        let mut sum = ElGamalCiphertext::default();
+        for (k, bit) in bits.iter().enumerate() {
+            let r = nonce(&[label, &[k as u8]].concat());
+            let (ciphertext, proof) =
+                encrypt_bit(*bit, &r, public_key, &[label, &[k as u8]].concat());
+            sum = sum.add(&ciphertext).unwrap();
+            r_sum += r;
+            ciphertexts.push(ciphertext);
+            validity_proofs.push(proof);
+        }
+        let sum_c2_minus_one = point_sub(&sum.c2, &BASEPOINT.0).unwrap();
+        let sum_proof = prove_equality(
+            &r_sum,
+            (&BASEPOINT.0, &sum.c1),
+            (public_key, &sum_c2_minus_one),
+            BALLOT_SUM_DOMAIN,
+            CONTEXT,
+        );
+        EncryptedBallot {
+            ciphertexts,
+            validity_proofs,
+            sum_proof,
+        }
+    }
+
+    /// 2-of-3 Shamir sharing of the commission key x(t) = a0 + a1 t
+    struct Commission {
+        commitments: Vec<[u8; 32]>,
+        coefficients: [Scalar; 2],
+    }
+
+    impl Commission {
+        fn new() -> Self {
+            let coefficients = [nonce(b"a0"), nonce(b"a1")];
+            Self {
+                commitments: coefficients.iter().map(mul_base).collect(),
+                coefficients,
+            }
+        }
+
+        fn public_key(&self) -> [u8; 32] {
+            self.commitments[0]
+        }
+
+        fn secret_share(&self, index: u8) -> Scalar {
+            self.coefficients[0] + self.coefficients[1] * Scalar::from(index as u64)
+        }
+
+        /// Shares x_i * C1 for every tally, each with its proof
+        fn decrypt(
+            &self,
+            index: u8,
+            tallies: &[ElGamalCiphertext],
+        ) -> Vec<([u8; 32], EqualityProof)> {
+            let x = self.secret_share(index);
+            let public_share = mul_base(&x);
+            tallies
+                .iter()
+                .map(|tally| {
+                    let share = point_mul(&x, &tally.c1).unwrap();
+                    let proof = prove_equality(
+                        &x,
+                        (&BASEPOINT.0, &public_share),
+                        (&tally.c1, &share),
+                        DECRYPTION_PROOF_DOMAIN,
+                        &[b"election", &[index]],
+                    );
+                    (share, proof)
+                })
+                .collect()
+        }
+    }
+
+    fn cast(
+        votes: &[usize],
+        candidate_count: usize,
+        public_key: &[u8; 32],
+    ) -> Vec<ElGamalCiphertext> {
+        let mut tallies = vec![ElGamalCiphertext::default(); candidate_count];
+        for (v, choice) in votes.iter().enumerate() {
+            let bits: Vec<u8> = (0..candidate_count).map(|k| (k == *choice) as u8).collect();
+            let ballot = encrypt_ballot(
+                &bits,
+                public_key,
+                &[b"ballot".as_slice(), &[v as u8]].concat(),
+            );
+            verify_ballot(&ballot, public_key, candidate_count, CONTEXT).unwrap();
+            for (sum, ciphertext) in tallies.iter_mut().zip(ballot.ciphertexts.iter()) {
+                *sum = sum.add(ciphertext).unwrap();
+            }
+        }
+        tallies
+    }
+
+    #[test]
+    fn threshold_decryption_opens_known_counts() {
+        let commission = Commission::new();
+        let public_key = commission.public_key();
+        let tallies = cast(&[0, 2, 0, 1, 2, 2], 3, &public_key);
+
+        // Trustees 1 and 3 decrypt; trustee 2 stays offline
+        let indices = [1u8, 3];
+        let mut shares = Vec::new();
+        for &index in &indices {
+            let public_share = trustee_public_share(&commission.commitments, index).unwrap();
+            assert_eq!(public_share, mul_base(&commission.secret_share(index)));
+            let decryption = commission.decrypt(index, &tallies);
+            for ((share, proof), tally) in decryption.iter().zip(tallies.iter()) {
+                verify_decryption_share(
+                    proof,
+                    &public_share,
+                    &tally.c1,
+                    share,
+                    &[b"election", &[index]],
+                )
+                .unwrap();
+            }
+            shares.push(
+                decryption
+                    .into_iter()
+                    .map(|(share, _)| share)
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        // lambda_1 = 3 / (3 - 1), lambda_3 = 1 / (1 - 3)
+        let lambda_1 = Scalar::from(3u64) * Scalar::from(2u64).invert();
+        let lambda_3 = -Scalar::from(2u64).invert();
+        let lambdas = [
+            verify_lagrange_coefficient(&lambda_1.to_bytes(), 1, &indices).unwrap(),
+            verify_lagrange_coefficient(&lambda_3.to_bytes(), 3, &indices).unwrap(),
+        ];
+        assert!(verify_lagrange_coefficient(&lambda_3.to_bytes(), 1, &indices).is_err());
+
+        for (k, (tally, count)) in tallies.iter().zip([2u64, 1, 3]).enumerate() {
+            let candidate_shares = [shares[0][k], shares[1][k]];
+            verify_decrypted_count(&tally.c2, &lambdas, &candidate_shares, count).unwrap();
+            assert_eq!(
+                verify_decrypted_count(&tally.c2, &lambdas, &candidate_shares, count + 1)
+                    .unwrap_err(),
+                ErrorCode::TallyMismatch.into()
+            );
+        }
+    }
+
+    #[test]
+    fn forged_decryption_share_is_rejected() {
+        let commission = Commission::new();
+        let tallies = cast(&[1, 0], 2, &commission.public_key());
+        let public_share = trustee_public_share(&commission.commitments, 2).unwrap();
+
+        // Shifting a valid share by G would shift the published count
+        let (share, proof) = commission.decrypt(2, &tallies)[0];
+        let forged = point_add(&share, &BASEPOINT.0).unwrap();
+        assert_eq!(
+            verify_decryption_share(
+                &proof,
+                &public_share,
+                &tallies[0].c1,
+                &forged,
+                &[b"election", &[2]]
+            )
+            .unwrap_err(),
+            ErrorCode::InvalidDecryptionProof.into()
+        );
+
+        // A consistent proof under another trustee's key does not pass either
+        let (share, proof) = commission.decrypt(1, &tallies)[0];
+        assert!(verify_decryption_share(
+            &proof,
+            &public_share,
+            &tallies[0].c1,
+            &share,
+            &[b"election", &[2]]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn ballot_must_mark_exactly_one_candidate() {
+        let public_key = Commission::new().public_key();
+        let ballot = encrypt_ballot(&[0, 1, 0], &public_key, b"valid");
+        verify_ballot(&ballot, &public_key, 3, CONTEXT).unwrap();
+
+        // Copied to another voter, the proofs no longer verify
+        assert_eq!(
+            verify_ballot(&ballot, &public_key, 3, &[b"election", b"other"]).unwrap_err(),
+            ErrorCode::InvalidBallotProof.into()
+        );
+
+        let double = encrypt_ballot(&[1, 1, 0], &public_key, b"double");
+        assert_eq!(
+            verify_ballot(&double, &public_key, 3, CONTEXT).unwrap_err(),
+            ErrorCode::InvalidBallotProof.into()
+        );
+    }
+}
@@ -32,4 +32,196 @@ pub enum ErrorCode {
     VotingPeriodInvalid,
     #[msg("Arithmetic overflow")]
     Overflow,
+    #[msg("Signer is not an election commissioner")]
+    NotCommissioner,
+    #[msg("Commissioner has already approved this proposal")]
+    DuplicateApproval,
+    #[msg("Proposal does not have enough commissioner approvals")]
+    InsufficientApprovals,
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Proposal does not authorize this action")]
+    ProposalActionMismatch,
+    #[msg("Signer is not an election registrar")]
+    RegistrarNotAuthorized,
+    #[msg("Voter verification requires a registrar attestation")]
+    MissingRegistrarAttestation,
+    #[msg("Registrar attestation is invalid")]
+    InvalidRegistrarAttestation,
+    #[msg("Registrar is already configured")]
+    RegistrarAlreadyAdded,
+    #[msg("Registrar is not configured")]
+    RegistrarNotFound,
+    #[msg("Too many registrars")]
+    TooManyRegistrars,
+    #[msg("Election has been cancelled")]
+    ElectionCancelled,
+    #[msg("Election cannot return to draft")]
+    CannotReturnToDraft,
+    #[msg("Registration can only be opened from draft")]
+    CannotOpenRegistration,
+    #[msg("Voting can only be opened from registration")]
+    CannotOpenVoting,
+    #[msg("Only an election in voting can be closed")]
+    CannotCloseVoting,
+    #[msg("Only a closed election can be tallied")]
+    CannotTally,
+    #[msg("Only a tallied election can be certified")]
+    CannotCertify,
+    #[msg("A certified election cannot be cancelled")]
+    CannotCancel,
+    #[msg("Registration period is invalid")]
+    InvalidRegistrationPeriod,
+    #[msg("Voter registration has not opened yet")]
+    RegistrationNotOpen,
+    #[msg("NIK is already registered to another wallet")]
+    NikAlreadyRegistered,
+    #[msg("NIK can only be rebound during registration or voting")]
+    NikRebindClosed,
+    #[msg("Instruction does not match the election voting mode")]
+    VotingModeMismatch,
+    #[msg("Tally configuration is invalid")]
+    InvalidTallyConfig,
+    #[msg("Encrypted ballot proof is invalid")]
+    InvalidBallotProof,
+    #[msg("Decryption share proof is invalid")]
+    InvalidDecryptionProof,
+    #[msg("Decryption share already submitted")]
+    DecryptionShareAlreadySubmitted,
+    #[msg("Decryption shares are only accepted while the election is closed")]
+    DecryptionClosed,
+    #[msg("Not enough decryption shares to open the tally")]
+    NotEnoughDecryptionShares,
+    #[msg("Lagrange coefficient is invalid")]
+    InvalidLagrangeCoefficient,
+    #[msg("Published counts do not match the encrypted tally")]
+    TallyMismatch,
+    #[msg("Encrypted elections are tallied by publishing decrypted counts")]
+    TallyRequiresDecryption,
+    #[msg("Invalid curve point")]
+    InvalidCurvePoint,
+    #[msg("Ballot token issuer has not been set")]
+    BallotIssuerNotSet,
+    #[msg("Ballot token was already issued for this request")]
+    BallotTokenAlreadyIssued,
+    #[msg("Ballot token signature is invalid")]
+    InvalidBlindSignature,
+    #[msg("More ballots than issued ballot tokens")]
+    BallotTokensExhausted,
+    #[msg("Membership proofs are not configured for this election")]
+    MembershipProofsNotConfigured,
+    #[msg("Membership proof is invalid")]
+    InvalidMembershipProof,
+    #[msg("Identity commitment is not a valid field element")]
+    InvalidIdentityCommitment,
+    #[msg("Identity commitment already registered for this credential")]
+    IdentityAlreadyRegistered,
+    #[msg("Membership tree is full")]
+    MembershipTreeFull,
+    #[msg("Candidate account must be omitted exactly for commitment ballots")]
+    CandidateAccountMismatch,
+    #[msg("Votes can only be revealed while the election is closed")]
+    RevealWindowClosed,
+    #[msg("Ballot has already been revealed")]
+    BallotAlreadyRevealed,
+    #[msg("Revealed vote does not match the ballot commitment")]
+    InvalidReveal,
+    #[msg("Ballot number must be at least 1")]
+    InvalidBallotNumber,
+    #[msg("Ballot number is already taken by another ticket")]
+    DuplicateBallotNumber,
+    #[msg("Coalition has too many or duplicate parties")]
+    InvalidCoalition,
+    #[msg("Recapitulation can only start once voting has closed")]
+    RecapitulationClosed,
+    #[msg("Province has already been counted")]
+    ProvinceAlreadyCounted,
+    #[msg("Provinces counted do not add up to the national count")]
+    ProvincesIncomplete,
+    #[msg("Results can only be computed for a tallied election")]
+    ResultNotReady,
+    #[msg("Election result has already been computed")]
+    ResultAlreadyComputed,
+    #[msg("A result needs at least two candidates")]
+    NotEnoughCandidates,
+    #[msg("Election result does not require a runoff")]
+    RunoffNotRequired,
+    #[msg("Runoff has already been created for this election")]
+    RunoffAlreadyCreated,
+    #[msg("Candidate accounts do not match the runoff tickets")]
+    RunoffCandidateMismatch,
+    #[msg("Election is not a runoff of the given parent election")]
+    NotARunoff,
+    #[msg("Region code does not match a province, regency, district or village")]
+    InvalidRegionCode,
+    #[msg("Region is not a village of this election")]
+    InvalidRegion,
+    #[msg("Region accounts do not form the chain from the village to its province")]
+    InvalidRegionChain,
+    #[msg("Region tally has no room for another candidate")]
+    RegionTallyFull,
+    #[msg("Polling station is invalid for this election or region")]
+    InvalidPollingStation,
+    #[msg("Polling station has reached its capacity")]
+    PollingStationFull,
+    #[msg("Voter is not assigned to this polling station")]
+    WrongPollingStation,
+    #[msg("Signer is not a commissioner or registrar")]
+    NotTransferApprover,
+    #[msg("Transfers are closed for this election")]
+    TransferWindowClosed,
+    #[msg("Transfer has already been approved")]
+    TransferAlreadyApproved,
+    #[msg("Electoral district is invalid or does not cover the voter's region")]
+    InvalidElectoralDistrict,
+    #[msg("Candidate is not on this party's list for the electoral district")]
+    PartyListMismatch,
+    #[msg("Party tally has no room for another electoral district")]
+    PartyTallyFull,
+    #[msg("Parliamentary threshold must be at most 10000 basis points")]
+    InvalidParliamentaryThreshold,
+    #[msg("Party accounts must be every registered party, in party order")]
+    PartiesIncomplete,
+    #[msg("List candidates must be from this dapil, each once, in party and list order")]
+    ListCandidateOrder,
+    #[msg("Seats in this electoral district have already been assigned")]
+    SeatsAlreadyAssigned,
+    #[msg("Election is part of an election event")]
+    ElectionInEvent,
+    #[msg("Election is not a contest of this election event")]
+    NotEventContest,
+    #[msg("Contest must share the host's commission and polling window")]
+    EventContestMismatch,
+    #[msg("Election event has no room for another contest")]
+    EventFull,
+    #[msg("Ranking must list distinct candidates of the ranked-choice tally")]
+    InvalidRanking,
+    #[msg("Ballots must be passed in sequence order, each once per round")]
+    IrvBallotOrder,
+    #[msg("Ranked-choice tally already has a winner")]
+    IrvTallyComplete,
+    #[msg("Selections must be 1 to 8, and above 1 only for Plurality or ZkMembership")]
+    InvalidMaxSelections,
+    #[msg("Ballot marks more candidates than the election allows")]
+    TooManySelections,
+    #[msg("Candidate marked twice on one ballot")]
+    DuplicateSelection,
+    #[msg("Candidates must be passed in ascending ID order, each once")]
+    CandidateOrder,
+    #[msg("Region tallies must be one per candidate and region, in chain order")]
+    InvalidRegionTally,
+    #[msg("Ballot contents are not signed with the nullifier key")]
+    InvalidBallotSignature,
+    #[msg("Signer is not the ballot token issuer")]
+    NotBallotIssuer,
+    #[msg("Region is not a province of this election")]
+    NotAProvince,
+    #[msg("Multi-seat result must cover every candidate before certification")]
+    MultiSeatResultPending,
+    #[msg("Presidential results need regional counts, kept only in Plurality and CommitReveal modes")]
+    NoRegionalCounts,
+    #[msg("Region already belongs to, contains or lies within another electoral district")]
+    DistrictOverlap,
+    #[msg("No ballot counts for a continuing candidate, so the round elects no one")]
+    NoContinuingVotes,
 }
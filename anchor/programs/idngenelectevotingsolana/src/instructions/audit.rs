@@ -1,44 +1,75 @@
 use anchor_lang::prelude::*;
-use crate::state::{Election, VoterCredential, Ballot, AuditData, ReceiptVerification};
+use crate::state::{
+    Election, VoterCredential, Ballot, Nullifier, AuditData, ReceiptVerification, TallySummary,
+};
+use crate::errors::ErrorCode;
+use crate::utils::generate_anonymous_ballot_receipt;
 
 /// Admin function to audit AI verification integrity (for testing)
 /// Only accessible by election commissioners
 pub fn audit_verification(
     ctx: Context<AuditVerification>,
 ) -> Result<AuditData> {
+    let election = &ctx.accounts.election;
     let voter_credential = &ctx.accounts.voter_credential;
 
     Ok(AuditData {
-        voter_nik_hash: voter_credential.voter_nik_hash,
+        nik_salt: election.nik_salt,
+        voter_nik_commitment: voter_credential.voter_nik_commitment,
         biometric_hash: voter_credential.biometric_hash,
         ai_confidence_score: voter_credential.ai_confidence_score,
         verification_timestamp: voter_credential.verification_timestamp,
         has_voted: voter_credential.has_voted,
         is_verified: voter_credential.is_verified,
+        blank_votes: election.blank_votes,
+        spoiled_votes: election.spoiled_votes,
     })
 }
 
-/// Voter can verify their vote was counted using their receipt
+/// Voter can verify their vote was counted from the nullifier they kept
+/// Finds the ballot through the Nullifier PDA and recomputes its receipt;
+/// no credential or wallet is involved, so the check does not link the
+/// ballot to the voter
 pub fn verify_ballot_receipt(
     ctx: Context<VerifyReceipt>,
+    nullifier: [u8; 32],
 ) -> Result<ReceiptVerification> {
     let ballot = &ctx.accounts.ballot;
-    let voter_credential = &ctx.accounts.voter_credential;
+    let receipt = generate_anonymous_ballot_receipt(&nullifier, ballot.timestamp);
 
     Ok(ReceiptVerification {
-        is_valid: true,
+        is_valid: receipt == ballot.verification_receipt,
         ballot_sequence: ballot.ballot_sequence,
         timestamp: ballot.timestamp,
-        verification_code: voter_credential.verification_code.clone(),
+        receipt,
+    })
+}
+
+/// Vote totals for any observer; blank, spoiled and unopened commit-reveal
+/// ballots are reported separately rather than counted
+pub fn tally_summary(ctx: Context<ElectionView>) -> Result<TallySummary> {
+    let election = &ctx.accounts.election;
+    let counted_votes = election.counted_votes();
+
+    Ok(TallySummary {
+        status: election.status,
+        total_votes_cast: election.total_votes_cast,
+        counted_votes,
+        unrevealed_votes: election.unrevealed_votes(),
+        blank_votes: election.blank_votes,
+        spoiled_votes: election.spoiled_votes,
     })
 }
 
 #[derive(Accounts)]
 pub struct AuditVerification<'info> {
+    #[account(
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
     pub commissioner: Signer<'info>,
 
     #[account(
-        seeds = [b"election", election.election_name.as_bytes()],
+        seeds = [b"election", &election.election_id.to_le_bytes()],
         bump = election.bump
     )]
     pub election: Account<'info, Election>,
@@ -52,21 +83,21 @@ pub struct AuditVerification<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
 pub struct VerifyReceipt<'info> {
-    pub voter: Signer<'info>,
-
     #[account(
-        seeds = [b"election", election.election_name.as_bytes()],
+        seeds = [b"election", &election.election_id.to_le_bytes()],
         bump = election.bump
     )]
     pub election: Account<'info, Election>,
 
     #[account(
-        seeds = [b"voter_credential", election.key().as_ref(), voter.key().as_ref()],
-        bump = voter_credential.bump,
-        has_one = election
+        seeds = [b"nullifier", election.key().as_ref(), nullifier.as_ref()],
+        bump = nullifier_record.bump,
+        has_one = election,
+        has_one = ballot
     )]
-    pub voter_credential: Account<'info, VoterCredential>,
+    pub nullifier_record: Account<'info, Nullifier>,
 
     #[account(
         seeds = [b"ballot", election.key().as_ref(), &ballot.ballot_sequence.to_le_bytes()],
@@ -75,3 +106,12 @@ pub struct VerifyReceipt<'info> {
     )]
     pub ballot: Account<'info, Ballot>,
 }
+
+#[derive(Accounts)]
+pub struct ElectionView<'info> {
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+}
@@ -0,0 +1,214 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Mint, Burn, ThawAccount};
+use crate::blind_signature::{station_issuer_key, verify_blind_response};
+use crate::elgamal::validate_point;
+use crate::state::{BallotTokenRequest, Election, PollingStation, VoterCredential};
+use crate::errors::ErrorCode;
+
+/// Phase 1 of anonymous voting: spend the voting token and ask the issuer
+/// to blind-sign a ballot token
+/// The voter's wallet appears here but no candidate does; the ballot itself
+/// is cast later by any relayer with the unblinded token (cast_vote)
+/// The token is bound to the voter's polling station (the destination TPS
+/// once a transfer is approved)
+pub fn request_ballot_token(
+    ctx: Context<RequestBallotToken>,
+    issuer_commitments: [[u8; 32]; 2],
+    blinded_challenges: [[u8; 32]; 2],
+) -> Result<()> {
+    let election = &ctx.accounts.election;
+    let clock = Clock::get()?;
+
+    require!(election.status.allows_voting(), ErrorCode::ElectionNotActive);
+    require!(
+        election.voting_mode.uses_ballot_tokens(),
+        ErrorCode::VotingModeMismatch
+    );
+    // Event contests are voted through request_contest_ballot_token
+    require!(election.event.is_none(), ErrorCode::ElectionInEvent);
+    require!(
+        clock.unix_timestamp >= election.start_time
+            && clock.unix_timestamp <= election.end_time,
+        ErrorCode::VotingPeriodInvalid
+    );
+    require!(election.ballot_issuer != [0u8; 32], ErrorCode::BallotIssuerNotSet);
+    validate_clause_commitments(&issuer_commitments)?;
+
+    let voter_credential = &mut ctx.accounts.voter_credential;
+    require!(!voter_credential.has_voted, ErrorCode::AlreadyVoted);
+    require!(voter_credential.is_verified, ErrorCode::VoterNotVerified);
+    require!(
+        voter_credential.polling_station == ctx.accounts.polling_station.key(),
+        ErrorCode::WrongPollingStation
+    );
+
+    redeem_voting_token(
+        election,
+        &ctx.accounts.voting_token_mint,
+        &ctx.accounts.voter_token_account,
+        &ctx.accounts.voter,
+        &ctx.accounts.token_program,
+    )?;
+
+    // The credential is spent here; the ballot is not linked to it
+    voter_credential.has_voted = true;
+    voter_credential.vote_timestamp = Some(clock.unix_timestamp);
+
+    let request = &mut ctx.accounts.ballot_token_request;
+    request.election = election.key();
+    request.issuer_commitments = issuer_commitments;
+    request.blinded_challenges = blinded_challenges;
+    request.blind_response = None;
+    request.clause = 0;
+    request.polling_station = ctx.accounts.polling_station.key();
+    request.bump = ctx.bumps.ballot_token_request;
+
+    Ok(())
+}
+
+/// Post the issuer's blind signature response for a ballot-token request
+/// Only the issuer's wallet may submit it: the clause is the issuer's random
+/// pick, and a requester choosing it could grind the two sessions (ROS).
+/// The response only verifies if it was produced with the issuer's secret
+/// key, and each request is answered at most once
+pub fn issue_ballot_token(
+    ctx: Context<IssueBallotToken>,
+    clause: u8,
+    blind_response: [u8; 32],
+) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+    let request = &mut ctx.accounts.ballot_token_request;
+
+    require!(request.blind_response.is_none(), ErrorCode::BallotTokenAlreadyIssued);
+    require!(clause < 2, ErrorCode::InvalidBlindSignature);
+    // Signed with the station's key so the token only counts there
+    let issuer_key =
+        station_issuer_key(&election.ballot_issuer, &election.key(), &request.polling_station)?;
+    verify_blind_response(
+        &issuer_key,
+        &request.issuer_commitments[clause as usize],
+        &request.blinded_challenges[clause as usize],
+        &blind_response,
+    )?;
+
+    request.blind_response = Some(blind_response);
+    request.clause = clause;
+    election.ballot_tokens_issued = election
+        .ballot_tokens_issued
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+/// Both clause commitments must be valid points, and distinct so the issuer
+/// never reuses a nonce across the two sessions
+pub(crate) fn validate_clause_commitments(issuer_commitments: &[[u8; 32]; 2]) -> Result<()> {
+    validate_point(&issuer_commitments[0])?;
+    validate_point(&issuer_commitments[1])?;
+    require!(
+        issuer_commitments[0] != issuer_commitments[1],
+        ErrorCode::InvalidBlindSignature
+    );
+    Ok(())
+}
+
+/// Thaw and burn the voter's frozen voting token (prevents double voting)
+pub(crate) fn redeem_voting_token<'info>(
+    election: &Account<'info, Election>,
+    voting_token_mint: &Account<'info, Mint>,
+    voter_token_account: &Account<'info, TokenAccount>,
+    voter: &Signer<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    // Voting tokens are held frozen (non-transferable); thaw so it can be burned
+    let cpi_accounts = ThawAccount {
+        account: voter_token_account.to_account_info(),
+        mint: voting_token_mint.to_account_info(),
+        authority: election.to_account_info(),
+    };
+    let election_id = election.election_id.to_le_bytes();
+    let election_seeds = &[b"election".as_ref(), &election_id, &[election.bump]];
+    let signer = &[&election_seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token::thaw_account(cpi_ctx)?;
+
+    let cpi_accounts = Burn {
+        mint: voting_token_mint.to_account_info(),
+        from: voter_token_account.to_account_info(),
+        authority: voter.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+    token::burn(cpi_ctx, 1)
+}
+
+#[derive(Accounts)]
+pub struct RequestBallotToken<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"voter_credential", election.key().as_ref(), voter.key().as_ref()],
+        bump = voter_credential.bump,
+        has_one = election
+    )]
+    pub voter_credential: Account<'info, VoterCredential>,
+
+    /// Station the voter will vote at
+    #[account(has_one = election)]
+    pub polling_station: Account<'info, PollingStation>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + BallotTokenRequest::INIT_SPACE,
+        seeds = [b"ballot_token", election.key().as_ref(), voter_credential.key().as_ref()],
+        bump
+    )]
+    pub ballot_token_request: Account<'info, BallotTokenRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"voting_token_mint", election.key().as_ref()],
+        bump,
+    )]
+    pub voting_token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = voting_token_mint,
+        associated_token::authority = voter
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IssueBallotToken<'info> {
+    #[account(
+        constraint = issuer.key() == election.ballot_issuer_authority @ ErrorCode::NotBallotIssuer
+    )]
+    pub issuer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        has_one = election
+    )]
+    pub ballot_token_request: Account<'info, BallotTokenRequest>,
+}
@@ -1,68 +1,211 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, Burn};
-use crate::state::{Election, VoterCredential, Candidate, Ballot};
+use crate::blind_signature::{
+    station_issuer_key, verify_ballot_contents, verify_ballot_token, BlindSignature,
+};
+use crate::instructions::region::{load_region_chain, record_region_votes};
+use crate::membership::{hash_to_field, verify_groth16, Groth16Proof};
+use crate::state::{
+    Election, Candidate, Ballot, BallotKind, Nullifier, PollingStation, Region, VotingMode,
+    ZkVerifyingKey,
+};
 use crate::errors::ErrorCode;
-use crate::utils::generate_ballot_receipt;
+use crate::utils::generate_anonymous_ballot_receipt;
+
+/// Proof that the (anonymous) caster is entitled to one ballot
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum BallotAuthorization {
+    /// Unblinded ballot token signed by the issuer (Plurality and CommitReveal
+    /// modes), with the voter's signature on the ballot under the nullifier key
+    BlindToken {
+        signature: BlindSignature,
+        contents_signature: BlindSignature,
+    },
+    /// Groth16 proof of membership in the identity tree (ZkMembership mode);
+    /// the nullifier is the circuit's nullifier hash
+    MembershipProof { proof: Groth16Proof },
+}
 
 /// Cast an anonymous vote
-/// Separates voter identity from vote choice for ballot secrecy
-pub fn cast_vote(
-    ctx: Context<CastVote>,
+/// Submitted by any relayer with a blind ballot token or a membership proof,
+/// so the ballot carries no link to the voter or credential.
+/// In CommitReveal mode no candidate is passed and `encrypted_vote_data` is
+/// the commitment sha256(election || nullifier || candidate_id LE || salt),
+/// opened later by reveal_vote
+/// Blind-token ballots are signed under the nullifier key over the ballot
+/// kind, `encrypted_vote_data` and the marked candidates' PDAs in order
+/// Blind-token ballots name the polling station the token was issued for and
+/// pass its region chain (village first, up to the province) as remaining
+/// accounts; a token presented at any other station does not verify
+/// The chain is followed by the region tallies of each marked candidate, one
+/// per region in chain order (see record_region_votes)
+/// Blank and spoiled ballots pass no candidate in any mode
+/// When the election has several seats (max_selections > 1), further
+/// candidates marked on the ballot lead the remaining accounts, ahead of the
+/// region chain; each is counted once
+pub fn cast_vote<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CastVote<'info>>,
+    nullifier: [u8; 32],
+    authorization: BallotAuthorization,
+    kind: BallotKind,
     encrypted_vote_data: [u8; 32],
 ) -> Result<()> {
     let election = &ctx.accounts.election;
     let clock = Clock::get()?;
 
     // Verify election is active and within voting period
-    require!(election.is_active, ErrorCode::ElectionNotActive);
+    require!(election.status.allows_voting(), ErrorCode::ElectionNotActive);
     require!(
         clock.unix_timestamp >= election.start_time
             && clock.unix_timestamp <= election.end_time,
         ErrorCode::VotingPeriodInvalid
     );
 
-    // Verify voter hasn't voted yet
-    let voter_credential = &mut ctx.accounts.voter_credential;
-    require!(!voter_credential.has_voted, ErrorCode::AlreadyVoted);
-    require!(voter_credential.is_verified, ErrorCode::VoterNotVerified);
-
-    // Burn the voting token (prevents double voting)
-    let cpi_accounts = Burn {
-        mint: ctx.accounts.voting_token_mint.to_account_info(),
-        from: ctx.accounts.voter_token_account.to_account_info(),
-        authority: ctx.accounts.voter.to_account_info(),
-    };
-    let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-    token::burn(cpi_ctx, 1)?;
-
-    // Mark voter as having voted (without revealing vote choice)
-    voter_credential.has_voted = true;
-    voter_credential.vote_timestamp = Some(clock.unix_timestamp);
+    // Commitment, blank and spoiled ballots must not touch a candidate;
+    // all others need one
+    let needs_candidate =
+        kind == BallotKind::Candidate && election.voting_mode != VotingMode::CommitReveal;
+    let candidate_key = ctx.accounts.candidate.as_ref().map(|candidate| candidate.key());
+    require!(
+        candidate_key.is_some() == needs_candidate,
+        ErrorCode::CandidateAccountMismatch
+    );
+    let candidate_key = candidate_key.unwrap_or_default();
+
+    let (selection_accounts, chain_accounts) = split_selections(ctx.remaining_accounts);
+    let mut further_candidates =
+        load_further_selections(election, &candidate_key, selection_accounts)?;
+    require!(
+        needs_candidate || further_candidates.is_empty(),
+        ErrorCode::CandidateAccountMismatch
+    );
+    require!(
+        further_candidates.len() < usize::from(election.max_selections),
+        ErrorCode::TooManySelections
+    );
+    let mut selected_keys = vec![candidate_key];
+    selected_keys.extend(further_candidates.iter().map(|candidate| candidate.key()));
+
+    // The nullifier PDA (init) stops a token or identity being used twice
+    let mut regions = Vec::new();
+    match (election.voting_mode, &authorization) {
+        (
+            VotingMode::Plurality | VotingMode::CommitReveal,
+            BallotAuthorization::BlindToken { signature, contents_signature },
+        ) => {
+            let polling_station = ctx
+                .accounts
+                .polling_station
+                .as_ref()
+                .ok_or(ErrorCode::InvalidPollingStation)?;
+            let kind_byte = [kind as u8];
+            let mut contents: Vec<&[u8]> = vec![&kind_byte, &encrypted_vote_data];
+            contents.extend(selected_keys.iter().map(|key| key.as_ref()));
+            regions = verify_station_token(
+                election,
+                polling_station,
+                chain_accounts,
+                &nullifier,
+                signature,
+                &contents,
+                contents_signature,
+                clock.unix_timestamp,
+            )?;
+        }
+        (VotingMode::ZkMembership, BallotAuthorization::MembershipProof { proof }) => {
+            // Membership proofs are not bound to a station, so a station or
+            // region named by the relayer would prove nothing: these ballots
+            // keep no regional tally and are taken only while polls are open
+            // in every zone
+            require!(
+                ctx.accounts.polling_station.is_none() && chain_accounts.is_empty(),
+                ErrorCode::InvalidPollingStation
+            );
+            let (opens, closes) = election.common_voting_window();
+            require!(
+                clock.unix_timestamp >= opens && clock.unix_timestamp <= closes,
+                ErrorCode::VotingPeriodInvalid
+            );
+            let zk_verifying_key = ctx
+                .accounts
+                .zk_verifying_key
+                .as_ref()
+                .ok_or(ErrorCode::MembershipProofsNotConfigured)?;
+            // The signal binds the proof to the chosen candidates (or to a
+            // blank or spoiled ballot), so a relayer cannot redirect the vote
+            let public_inputs = [
+                election.membership_root,
+                nullifier,
+                ballot_signal(kind, &selected_keys),
+                hash_to_field(&[election.key().as_ref()]),
+            ];
+            verify_groth16(&zk_verifying_key.verifying_key, proof, &public_inputs)?;
+        }
+        _ => return err!(ErrorCode::VotingModeMismatch),
+    }
 
     // Record anonymous ballot
     let ballot = &mut ctx.accounts.ballot;
     ballot.election = ctx.accounts.election.key();
-    ballot.candidate = ctx.accounts.candidate.key();
+    ballot.candidate = candidate_key;
     ballot.encrypted_vote_data = encrypted_vote_data;
+    ballot.is_revealed = false;
     ballot.timestamp = clock.unix_timestamp;
     ballot.ballot_sequence = election.total_votes_cast;
-    ballot.verification_receipt = generate_ballot_receipt(
-        *ctx.accounts.voter.key,
-        &voter_credential.verification_code,
-        clock.unix_timestamp,
-    );
+    ballot.verification_receipt = generate_anonymous_ballot_receipt(&nullifier, clock.unix_timestamp);
+    ballot.region = regions.first().map(|village| village.key());
+    ballot.polling_station = ctx.accounts.polling_station.as_ref().map(|station| station.key());
+    ballot.kind = kind;
+    ballot.ranking = Vec::new();
+    ballot.selections = Vec::new();
     ballot.bump = ctx.bumps.ballot;
 
-    // Increment candidate vote count
-    let candidate = &mut ctx.accounts.candidate;
-    candidate.vote_count = candidate
-        .vote_count
-        .checked_add(1)
-        .ok_or(ErrorCode::Overflow)?;
+    let nullifier_record = &mut ctx.accounts.nullifier_record;
+    nullifier_record.election = ballot.election;
+    nullifier_record.ballot = ballot.key();
+    nullifier_record.bump = ctx.bumps.nullifier_record;
+
+    // Increment candidate vote counts
+    let mut candidate_ids = Vec::with_capacity(selected_keys.len());
+    if let Some(candidate) = ctx.accounts.candidate.as_mut() {
+        candidate.vote_count = candidate
+            .vote_count
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        candidate_ids.push(candidate.candidate_id);
+    }
+    for candidate in further_candidates.iter_mut() {
+        candidate.vote_count = candidate
+            .vote_count
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        candidate_ids.push(candidate.candidate_id);
+        candidate.exit(&crate::ID)?;
+    }
+    if ctx.accounts.election.max_selections > 1 {
+        ctx.accounts.ballot.selections = candidate_ids.clone();
+    }
+
+    // Commitment ballots are attributed to a candidate when revealed
+    let relayer = ctx.accounts.relayer.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+    if let Some(polling_station) = ctx.accounts.polling_station.as_mut() {
+        record_station_ballot(polling_station, &mut regions, kind)?;
+        record_region_votes(
+            &chain_accounts[regions.len()..],
+            &regions,
+            &candidate_ids,
+            &relayer,
+            &system_program,
+        )?;
+    }
 
     // Increment total votes cast
     let election_mut = &mut ctx.accounts.election;
+    election_mut.record_ballot_kind(kind)?;
+    election_mut.total_selections = election_mut
+        .total_selections
+        .checked_add(candidate_ids.len() as u64)
+        .ok_or(ErrorCode::Overflow)?;
     election_mut.total_votes_cast = election_mut
         .total_votes_cast
         .checked_add(1)
@@ -71,37 +214,161 @@ pub fn cast_vote(
     Ok(())
 }
 
+/// Check a blind ballot token presented at `polling_station` against the
+/// station's key and local polling hours, and the voter's signature on the
+/// ballot `contents` under the nullifier key, and load the station's region
+/// chain from the remaining accounts
+pub(crate) fn verify_station_token<'info>(
+    election: &Account<'info, Election>,
+    polling_station: &Account<'info, PollingStation>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    nullifier: &[u8; 32],
+    signature: &BlindSignature,
+    contents: &[&[u8]],
+    contents_signature: &BlindSignature,
+    now: i64,
+) -> Result<Vec<Account<'info, Region>>> {
+    let regions = load_region_chain(remaining_accounts, &election.key(), &polling_station.region)?;
+
+    // Polls keep local hours in the station's time zone
+    let (opens, closes) = election.voting_window(regions[0].time_zone);
+    require!(now >= opens && now <= closes, ErrorCode::VotingPeriodInvalid);
+
+    let issuer_key = station_issuer_key(
+        &election.ballot_issuer,
+        &election.key(),
+        &polling_station.key(),
+    )?;
+    verify_ballot_token(&issuer_key, &election.key(), nullifier, signature)?;
+    // Only the holder of the nullifier secret decides what the token votes for
+    verify_ballot_contents(&election.key(), nullifier, contents, contents_signature)?;
+    require!(
+        election.total_votes_cast < election.ballot_tokens_issued,
+        ErrorCode::BallotTokensExhausted
+    );
+
+    Ok(regions)
+}
+
+/// Split off the candidate accounts leading the remaining accounts
+fn split_selections<'info>(
+    accounts: &'info [AccountInfo<'info>],
+) -> (&'info [AccountInfo<'info>], &'info [AccountInfo<'info>]) {
+    let selections = accounts
+        .iter()
+        .take_while(|info| {
+            info.owner == &crate::ID
+                && info
+                    .try_borrow_data()
+                    .is_ok_and(|data| data.starts_with(Candidate::DISCRIMINATOR))
+        })
+        .count();
+    accounts.split_at(selections)
+}
+
+/// Load the candidates marked after the first, each once
+fn load_further_selections<'info>(
+    election: &Account<'info, Election>,
+    first: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+) -> Result<Vec<Account<'info, Candidate>>> {
+    let mut candidates: Vec<Account<'info, Candidate>> = Vec::with_capacity(accounts.len());
+    for info in accounts.iter() {
+        let candidate = Account::<Candidate>::try_from(info)?;
+        require!(
+            candidate.election == election.key(),
+            ErrorCode::CandidateAccountMismatch
+        );
+        require!(
+            info.key() != *first && !candidates.iter().any(|c| c.key() == info.key()),
+            ErrorCode::DuplicateSelection
+        );
+        candidates.push(candidate);
+    }
+    Ok(candidates)
+}
+
+/// ZkMembership signal: the PDAs of the candidates marked, in ballot order,
+/// or the ballot kind for blank and spoiled ballots
+fn ballot_signal(kind: BallotKind, candidates: &[Pubkey]) -> [u8; 32] {
+    match kind {
+        BallotKind::Candidate => {
+            let keys: Vec<&[u8]> = candidates.iter().map(|key| key.as_ref()).collect();
+            hash_to_field(&keys)
+        }
+        BallotKind::Blank => hash_to_field(&[b"blank".as_ref()]),
+        BallotKind::Spoiled => hash_to_field(&[b"spoiled".as_ref()]),
+    }
+}
+
+/// Count a ballot at its station and in every region of its chain
+/// Candidate votes per region go in the region tallies (record_region_votes)
+pub(crate) fn record_station_ballot(
+    polling_station: &mut PollingStation,
+    regions: &mut [Account<Region>],
+    kind: BallotKind,
+) -> Result<()> {
+    polling_station.votes_cast = polling_station
+        .votes_cast
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    for region in regions.iter_mut() {
+        region.votes_cast = region.votes_cast.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        match kind {
+            BallotKind::Candidate => {}
+            BallotKind::Blank => {
+                region.blank_votes = region.blank_votes.checked_add(1).ok_or(ErrorCode::Overflow)?
+            }
+            BallotKind::Spoiled => {
+                region.spoiled_votes =
+                    region.spoiled_votes.checked_add(1).ok_or(ErrorCode::Overflow)?
+            }
+        }
+        region.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
 #[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
 pub struct CastVote<'info> {
+    /// Any fee payer; should not be the voter's registered wallet
     #[account(mut)]
-    pub voter: Signer<'info>,
+    pub relayer: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"election", election.election_name.as_bytes()],
+        seeds = [b"election", &election.election_id.to_le_bytes()],
         bump = election.bump
     )]
     pub election: Account<'info, Election>,
 
+    /// Omitted in CommitReveal mode and for blank and spoiled ballots
     #[account(
         mut,
-        seeds = [b"voter_credential", election.key().as_ref(), voter.key().as_ref()],
-        bump = voter_credential.bump,
+        seeds = [b"candidate", election.key().as_ref(), &candidate.candidate_id.to_le_bytes()],
+        bump = candidate.bump,
         has_one = election
     )]
-    pub voter_credential: Account<'info, VoterCredential>,
+    pub candidate: Option<Account<'info, Candidate>>,
+
+    /// Required for blind-token ballots
+    #[account(mut, has_one = election)]
+    pub polling_station: Option<Account<'info, PollingStation>>,
 
     #[account(
-        mut,
-        seeds = [b"candidate", election.key().as_ref(), &candidate.candidate_id.to_le_bytes()],
-        bump = candidate.bump,
-        has_one = election
+        init,
+        payer = relayer,
+        space = 8 + Nullifier::INIT_SPACE,
+        seeds = [b"nullifier", election.key().as_ref(), nullifier.as_ref()],
+        bump
     )]
-    pub candidate: Account<'info, Candidate>,
+    pub nullifier_record: Account<'info, Nullifier>,
 
     #[account(
         init,
-        payer = voter,
+        payer = relayer,
         space = 8 + Ballot::INIT_SPACE,
         seeds = [
             b"ballot",
@@ -112,20 +379,13 @@ pub struct CastVote<'info> {
     )]
     pub ballot: Account<'info, Ballot>,
 
+    /// Required in ZkMembership mode
     #[account(
-        mut,
-        seeds = [b"voting_token_mint", election.key().as_ref()],
-        bump,
-    )]
-    pub voting_token_mint: Account<'info, Mint>,
-
-    #[account(
-        mut,
-        associated_token::mint = voting_token_mint,
-        associated_token::authority = voter
+        seeds = [b"zk_verifying_key", election.key().as_ref()],
+        bump = zk_verifying_key.bump,
+        has_one = election
     )]
-    pub voter_token_account: Account<'info, TokenAccount>,
+    pub zk_verifying_key: Option<Account<'info, ZkVerifyingKey>>,
 
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
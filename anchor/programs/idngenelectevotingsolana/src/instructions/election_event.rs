@@ -0,0 +1,307 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount, Mint};
+use crate::instructions::ballot_token::{redeem_voting_token, validate_clause_commitments};
+use crate::state::{
+    BallotTokenRequest, Election, ElectionEvent, ElectionStatus, PollingStation, Proposal,
+    ProposalAction, VoterCredential, MAX_EVENT_CONTESTS,
+};
+use crate::errors::ErrorCode;
+
+/// Turn the election into the host of an election event (pemilu serentak)
+/// (requires an approved commissioner proposal naming the same event)
+/// The host's voter register, credentials and voting tokens serve every
+/// contest; the host is the event's first contest
+pub fn create_election_event(ctx: Context<CreateElectionEvent>, name: String) -> Result<()> {
+    validate_event_name(&name)?;
+
+    let election = &mut ctx.accounts.election;
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::CreateElectionEvent { name: name.clone() },
+    )?;
+
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+    require!(
+        election.voting_mode.uses_ballot_tokens(),
+        ErrorCode::VotingModeMismatch
+    );
+    require!(election.event.is_none(), ErrorCode::ElectionInEvent);
+
+    let election_event = &mut ctx.accounts.election_event;
+    election_event.host = election.key();
+    election_event.name = name;
+    election_event.contests = vec![election.key()];
+    election_event.bump = ctx.bumps.election_event;
+
+    election.event = Some(election_event.key());
+
+    Ok(())
+}
+
+/// Add a Draft election as a contest of the host's event
+/// (requires an approved host proposal naming the contest)
+/// The contest must be run by the same commission over the same polling
+/// window; its regions and stations are created in it, by the host's codes
+pub fn add_event_contest(ctx: Context<AddEventContest>) -> Result<()> {
+    let election = &ctx.accounts.election;
+    let contest = &mut ctx.accounts.contest;
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::AddEventContest {
+            contest: contest.key(),
+        },
+    )?;
+
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+    require!(
+        contest.status == ElectionStatus::Draft,
+        ErrorCode::ElectionAlreadyActive
+    );
+    require!(
+        contest.voting_mode.uses_ballot_tokens(),
+        ErrorCode::VotingModeMismatch
+    );
+    require!(contest.event.is_none(), ErrorCode::ElectionInEvent);
+    require!(
+        contest.commissioners == election.commissioners
+            && contest.required_signatures == election.required_signatures
+            && contest.start_time == election.start_time
+            && contest.end_time == election.end_time,
+        ErrorCode::EventContestMismatch
+    );
+
+    let election_event = &mut ctx.accounts.election_event;
+    require!(
+        election_event.contests.len() < MAX_EVENT_CONTESTS,
+        ErrorCode::EventFull
+    );
+    election_event.contests.push(contest.key());
+
+    contest.event = Some(election_event.key());
+
+    Ok(())
+}
+
+/// Phase 1 of anonymous voting in one contest of an election event
+/// Like request_ballot_token, but against the host credential: each contest
+/// can be requested once, in any order. The voting token is spent with the
+/// first contest; the ballot token is bound to the contest's station with
+/// the voter's TPS code, and the ballot is cast in the contest as usual
+pub fn request_contest_ballot_token(
+    ctx: Context<RequestContestBallotToken>,
+    issuer_commitments: [[u8; 32]; 2],
+    blinded_challenges: [[u8; 32]; 2],
+) -> Result<()> {
+    let contest = &ctx.accounts.contest;
+    let clock = Clock::get()?;
+
+    require!(contest.status.allows_voting(), ErrorCode::ElectionNotActive);
+    require!(
+        clock.unix_timestamp >= contest.start_time && clock.unix_timestamp <= contest.end_time,
+        ErrorCode::VotingPeriodInvalid
+    );
+    require!(contest.ballot_issuer != [0u8; 32], ErrorCode::BallotIssuerNotSet);
+    validate_clause_commitments(&issuer_commitments)?;
+
+    let contest_index = ctx
+        .accounts
+        .election_event
+        .contests
+        .iter()
+        .position(|key| *key == contest.key())
+        .ok_or(ErrorCode::NotEventContest)?;
+    let contest_bit = 1u8 << contest_index;
+
+    let voter_credential = &mut ctx.accounts.voter_credential;
+    require!(voter_credential.is_verified, ErrorCode::VoterNotVerified);
+    require!(
+        voter_credential.contests_voted & contest_bit == 0,
+        ErrorCode::AlreadyVoted
+    );
+
+    if voter_credential.contests_voted == 0 {
+        redeem_voting_token(
+            &ctx.accounts.election,
+            &ctx.accounts.voting_token_mint,
+            &ctx.accounts.voter_token_account,
+            &ctx.accounts.voter,
+            &ctx.accounts.token_program,
+        )?;
+        voter_credential.has_voted = true;
+        voter_credential.vote_timestamp = Some(clock.unix_timestamp);
+    }
+    voter_credential.contests_voted |= contest_bit;
+
+    let request = &mut ctx.accounts.ballot_token_request;
+    request.election = contest.key();
+    request.issuer_commitments = issuer_commitments;
+    request.blinded_challenges = blinded_challenges;
+    request.blind_response = None;
+    request.clause = 0;
+    request.polling_station = ctx.accounts.polling_station.key();
+    request.bump = ctx.bumps.ballot_token_request;
+
+    Ok(())
+}
+
+/// Shared by create_proposal and create_election_event
+pub(crate) fn validate_event_name(name: &str) -> Result<()> {
+    require!(name.len() <= 100, ErrorCode::NameTooLong);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateElectionEvent<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    /// Host election
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + ElectionEvent::INIT_SPACE,
+        seeds = [b"election_event", election.key().as_ref()],
+        bump
+    )]
+    pub election_event: Account<'info, ElectionEvent>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddEventContest<'info> {
+    #[account(
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    /// Host election
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"election_event", election.key().as_ref()],
+        bump = election_event.bump
+    )]
+    pub election_event: Account<'info, ElectionEvent>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &contest.election_id.to_le_bytes()],
+        bump = contest.bump
+    )]
+    pub contest: Box<Account<'info, Election>>,
+}
+
+#[derive(Accounts)]
+pub struct RequestContestBallotToken<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    /// Host election, holding the voter's registration
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        seeds = [b"election_event", election.key().as_ref()],
+        bump = election_event.bump
+    )]
+    pub election_event: Account<'info, ElectionEvent>,
+
+    #[account(
+        seeds = [b"election", &contest.election_id.to_le_bytes()],
+        bump = contest.bump,
+        constraint = contest.event == Some(election_event.key()) @ ErrorCode::NotEventContest
+    )]
+    pub contest: Box<Account<'info, Election>>,
+
+    #[account(
+        mut,
+        seeds = [b"voter_credential", election.key().as_ref(), voter.key().as_ref()],
+        bump = voter_credential.bump,
+        has_one = election
+    )]
+    pub voter_credential: Account<'info, VoterCredential>,
+
+    /// The voter's station in the host
+    #[account(address = voter_credential.polling_station @ ErrorCode::WrongPollingStation)]
+    pub home_station: Account<'info, PollingStation>,
+
+    /// The station with the same region code and TPS number in the contest
+    #[account(
+        seeds = [
+            b"polling_station",
+            contest.key().as_ref(),
+            &home_station.region_code.to_le_bytes(),
+            &home_station.tps_number.to_le_bytes()
+        ],
+        bump = polling_station.bump
+    )]
+    pub polling_station: Account<'info, PollingStation>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + BallotTokenRequest::INIT_SPACE,
+        seeds = [b"ballot_token", contest.key().as_ref(), voter_credential.key().as_ref()],
+        bump
+    )]
+    pub ballot_token_request: Account<'info, BallotTokenRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"voting_token_mint", election.key().as_ref()],
+        bump,
+    )]
+    pub voting_token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = voting_token_mint,
+        associated_token::authority = voter
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
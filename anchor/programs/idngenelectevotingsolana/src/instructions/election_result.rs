@@ -0,0 +1,304 @@
+use anchor_lang::prelude::*;
+use crate::instructions::encrypted_tally::load_candidates;
+use crate::instructions::region::region_tally_votes;
+use crate::state::{
+    Candidate, Election, ElectionOutcome, ElectionResult, MultiSeatResult, Region, RegionLevel,
+    VotingMode, MAX_RESULT_CANDIDATES, PROVINCE_CODES, PROVINCE_COUNT,
+};
+use crate::errors::ErrorCode;
+
+/// Fold provinces into the result from their on-chain regional counts
+/// Each province Region is passed (remaining accounts) followed by its
+/// RegionTally for every ticket in `candidate_ids` order; a ticket no ballot
+/// in the province has marked has no tally yet, and its PDA is passed
+/// uncreated. The first call fixes the ticket order, and provinces can be
+/// folded over several transactions, each once
+pub fn tally_provinces<'info>(
+    ctx: Context<'_, '_, 'info, 'info, TallyProvinces<'info>>,
+    candidate_ids: Vec<u32>,
+) -> Result<()> {
+    validate_result_candidates(&candidate_ids)?;
+
+    let election = &ctx.accounts.election;
+    // Only station-bound ballots keep regional counts: ZkMembership and
+    // Encrypted ballots are counted nationally only, so the provincial
+    // spread (and with it the Article 6A result) cannot be established
+    require!(
+        matches!(
+            election.voting_mode,
+            VotingMode::Plurality | VotingMode::CommitReveal
+        ),
+        ErrorCode::NoRegionalCounts
+    );
+    // Counts only stop changing once the election is tallied (commit-reveal
+    // ballots are still being opened while it is closed)
+    require!(
+        election.status.allows_result_computation(),
+        ErrorCode::ResultNotReady
+    );
+
+    let election_key = election.key();
+    let result = &mut ctx.accounts.election_result;
+    require!(
+        result.outcome == ElectionOutcome::Pending,
+        ErrorCode::ResultAlreadyComputed
+    );
+
+    if result.election == Pubkey::default() {
+        result.election = election_key;
+        result.outcome = ElectionOutcome::Pending;
+        result.runoff_election = None;
+        result.provincial_vote_sums = vec![0; candidate_ids.len()];
+        result.provinces_with_spread = vec![0; candidate_ids.len()];
+        result.candidate_ids = candidate_ids.clone();
+        result.bump = ctx.bumps.election_result;
+    }
+    require!(result.candidate_ids == candidate_ids, ErrorCode::TallyMismatch);
+
+    let group = 1 + candidate_ids.len();
+    require!(
+        ctx.remaining_accounts.len().is_multiple_of(group),
+        ErrorCode::InvalidRegionTally
+    );
+    for accounts in ctx.remaining_accounts.chunks(group) {
+        let province = Account::<Region>::try_from(&accounts[0])?;
+        require!(
+            province.election == election_key && province.level == RegionLevel::Province,
+            ErrorCode::NotAProvince
+        );
+        let province_code = u8::try_from(province.region_code)
+            .ok()
+            .filter(|code| PROVINCE_CODES.contains(code))
+            .ok_or(ErrorCode::InvalidRegionCode)?;
+        require!(
+            !result.provinces_counted.contains(&province_code),
+            ErrorCode::ProvinceAlreadyCounted
+        );
+
+        let vote_counts = candidate_ids
+            .iter()
+            .zip(accounts[1..].iter())
+            .map(|(candidate_id, info)| region_tally_votes(info, &province.key(), *candidate_id))
+            .collect::<Result<Vec<u64>>>()?;
+        let province_total: u128 = vote_counts.iter().map(|count| u128::from(*count)).sum();
+        for (k, count) in vote_counts.iter().enumerate() {
+            result.provincial_vote_sums[k] = result.provincial_vote_sums[k]
+                .checked_add(*count)
+                .ok_or(ErrorCode::Overflow)?;
+            // At least 20% of the province's votes
+            if province_total > 0 && u128::from(*count) * 5 >= province_total {
+                result.provinces_with_spread[k] += 1;
+            }
+        }
+        result.provinces_counted.push(province_code);
+    }
+
+    Ok(())
+}
+
+/// Apply the Article 6A rule once every province has been counted
+/// Candidate accounts are passed as remaining accounts in result order
+pub fn compute_result<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ComputeResult<'info>>,
+) -> Result<()> {
+    let election = &ctx.accounts.election;
+    let result = &mut ctx.accounts.election_result;
+
+    require!(
+        election.status.allows_result_computation(),
+        ErrorCode::ResultNotReady
+    );
+    // Multi-seat races are decided by tally_multi_seat
+    require!(election.max_selections == 1, ErrorCode::VotingModeMismatch);
+    require!(
+        result.outcome == ElectionOutcome::Pending,
+        ErrorCode::ResultAlreadyComputed
+    );
+    let candidates = load_candidates(
+        ctx.remaining_accounts,
+        &election.key(),
+        &result.candidate_ids,
+    )?;
+    let vote_counts: Vec<u64> = candidates.iter().map(|c| c.vote_count).collect();
+
+    // Every ballot for a ticket counts in its province, so the provinces
+    // folded in must add up to the national count: one left out would skew
+    // the spread. The listed tickets must account for every counted ballot
+    require!(
+        vote_counts == result.provincial_vote_sums,
+        ErrorCode::ProvincesIncomplete
+    );
+    let national_total = vote_counts
+        .iter()
+        .try_fold(0u64, |total, count| total.checked_add(*count))
+        .ok_or(ErrorCode::Overflow)?;
+    require!(
+        national_total == election.counted_votes(),
+        ErrorCode::TallyMismatch
+    );
+
+    // Rank by votes, then by provincial spread (UU 7/2017 Art. 416),
+    // then by candidate ID so the order is deterministic
+    let mut ranking: Vec<usize> = (0..vote_counts.len()).collect();
+    ranking.sort_by(|&a, &b| {
+        vote_counts[b]
+            .cmp(&vote_counts[a])
+            .then(result.provinces_with_spread[b].cmp(&result.provinces_with_spread[a]))
+            .then(result.candidate_ids[a].cmp(&result.candidate_ids[b]))
+    });
+    let (first, second) = (ranking[0], ranking[1]);
+
+    let majority = u128::from(vote_counts[first]) * 2 > u128::from(national_total);
+    let spread = result.provinces_with_spread[first] as usize * 2 > PROVINCE_COUNT;
+    result.outcome = if majority && spread {
+        ElectionOutcome::Winner {
+            candidate_id: result.candidate_ids[first],
+        }
+    } else {
+        ElectionOutcome::RunoffRequired {
+            first_candidate_id: result.candidate_ids[first],
+            second_candidate_id: result.candidate_ids[second],
+        }
+    };
+    result.vote_counts = vote_counts;
+    result.blank_votes = election.blank_votes;
+    result.spoiled_votes = election.spoiled_votes;
+    result.unrevealed_votes = election.unrevealed_votes();
+
+    Ok(())
+}
+
+/// Fold candidates of a multi-seat race into its result, keeping the
+/// max_selections leaders. Candidates are passed as remaining accounts in
+/// ascending ID order, over several transactions if needed; the result is
+/// final once their votes account for every candidate marked, and only then
+/// can the election be certified
+pub fn tally_multi_seat<'info>(
+    ctx: Context<'_, '_, 'info, 'info, TallyMultiSeat<'info>>,
+) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+    let result = &mut ctx.accounts.multi_seat_result;
+
+    require!(
+        election.status.allows_result_computation(),
+        ErrorCode::ResultNotReady
+    );
+    require!(election.max_selections > 1, ErrorCode::VotingModeMismatch);
+    require!(!result.complete, ErrorCode::ResultAlreadyComputed);
+
+    if result.election == Pubkey::default() {
+        result.election = election.key();
+        result.seats = election.max_selections;
+        result.blank_votes = election.blank_votes;
+        result.spoiled_votes = election.spoiled_votes;
+        result.bump = ctx.bumps.multi_seat_result;
+    }
+
+    for info in ctx.remaining_accounts.iter() {
+        let candidate = Account::<Candidate>::try_from(info)?;
+        require!(
+            candidate.election == election.key()
+                && (result.candidates_counted == 0
+                    || candidate.candidate_id > result.last_candidate_id),
+            ErrorCode::CandidateOrder
+        );
+
+        result.consider(candidate.candidate_id, candidate.vote_count);
+        result.votes_counted = result
+            .votes_counted
+            .checked_add(candidate.vote_count)
+            .ok_or(ErrorCode::Overflow)?;
+        result.candidates_counted = result
+            .candidates_counted
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        result.last_candidate_id = candidate.candidate_id;
+    }
+
+    result.complete = result.votes_counted == election.total_selections;
+    election.multi_seat_tallied = result.complete;
+
+    Ok(())
+}
+
+/// Tickets a presidential result ranks: two to eight, each once
+pub(crate) fn validate_result_candidates(candidate_ids: &[u32]) -> Result<()> {
+    require!(
+        candidate_ids.len() <= MAX_RESULT_CANDIDATES,
+        ErrorCode::InvalidTallyConfig
+    );
+    require!(candidate_ids.len() >= 2, ErrorCode::NotEnoughCandidates);
+    for (i, candidate_id) in candidate_ids.iter().enumerate() {
+        require!(
+            !candidate_ids[..i].contains(candidate_id),
+            ErrorCode::InvalidTallyConfig
+        );
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct TallyProvinces<'info> {
+    /// Anyone may fold in provinces; the payer funds the result account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ElectionResult::INIT_SPACE,
+        seeds = [b"election_result", election.key().as_ref()],
+        bump
+    )]
+    pub election_result: Account<'info, ElectionResult>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TallyMultiSeat<'info> {
+    /// Anyone may fold in candidates; the payer funds the result account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + MultiSeatResult::INIT_SPACE,
+        seeds = [b"multi_seat_result", election.key().as_ref()],
+        bump
+    )]
+    pub multi_seat_result: Account<'info, MultiSeatResult>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ComputeResult<'info> {
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"election_result", election.key().as_ref()],
+        bump = election_result.bump,
+        has_one = election
+    )]
+    pub election_result: Account<'info, ElectionResult>,
+}
@@ -0,0 +1,503 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount, Mint};
+use crate::elgamal::{self, ElGamalCiphertext, EncryptedBallot, EqualityProof};
+use crate::instructions::ballot_token::redeem_voting_token;
+use crate::instructions::cast_vote::record_station_ballot;
+use crate::instructions::region::load_region_chain;
+use crate::state::{
+    Ballot, BallotKind, Candidate, Election, ElectionStatus, EncryptedTally, Nullifier,
+    PollingStation, Proposal, ProposalAction, TrusteeDecryption, VoterCredential, VotingMode,
+    MAX_ENCRYPTED_CANDIDATES,
+};
+use crate::errors::ErrorCode;
+use crate::utils::generate_anonymous_ballot_receipt;
+
+/// Set up the encrypted tally (requires an approved commissioner proposal)
+/// `key_commitments` are the Feldman commitments from the commissioners' key
+/// generation; candidates must already be registered and are passed as
+/// remaining accounts in `candidate_ids` order
+pub fn configure_encrypted_tally<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ConfigureEncryptedTally<'info>>,
+    threshold: u8,
+    candidate_ids: Vec<u32>,
+    key_commitments: Vec<[u8; 32]>,
+) -> Result<()> {
+    let election = &ctx.accounts.election;
+
+    require!(
+        election.voting_mode == VotingMode::Encrypted,
+        ErrorCode::VotingModeMismatch
+    );
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::ConfigureEncryptedTally {
+            threshold,
+            candidate_ids: candidate_ids.clone(),
+            key_commitments: key_commitments.clone(),
+        },
+    )?;
+
+    require!(
+        threshold > 0
+            && threshold as usize <= election.commissioners.len()
+            && key_commitments.len() == threshold as usize,
+        ErrorCode::InvalidTallyConfig
+    );
+    require!(
+        !candidate_ids.is_empty() && candidate_ids.len() <= MAX_ENCRYPTED_CANDIDATES,
+        ErrorCode::InvalidTallyConfig
+    );
+    for (i, candidate_id) in candidate_ids.iter().enumerate() {
+        require!(
+            !candidate_ids[..i].contains(candidate_id),
+            ErrorCode::InvalidTallyConfig
+        );
+    }
+    for commitment in &key_commitments {
+        elgamal::validate_point(commitment)?;
+    }
+    load_candidates(ctx.remaining_accounts, &election.key(), &candidate_ids)?;
+
+    let tally = &mut ctx.accounts.encrypted_tally;
+    tally.election = election.key();
+    tally.threshold = threshold;
+    tally.key_commitments = key_commitments;
+    tally.tallies = vec![ElGamalCiphertext::default(); candidate_ids.len()];
+    tally.candidate_ids = candidate_ids;
+    tally.decryptions = Vec::new();
+    tally.results = Vec::new();
+    tally.blank_votes = 0;
+    tally.spoiled_votes = 0;
+    tally.is_published = false;
+    tally.bump = ctx.bumps.encrypted_tally;
+
+    Ok(())
+}
+
+/// Cast an encrypted one-hot ballot
+/// The choice never appears on-chain; only the homomorphic sums are updated
+/// The voter's polling station and its region chain (village first, passed
+/// as remaining accounts) set the local polling hours and count the ballot
+/// in votes_cast; the candidate counts stay encrypted
+/// Blank and spoiled ballots carry no ciphertexts (a one-hot ballot always
+/// marks a candidate), so their kind is public as on the C1 tally sheet
+/// `nullifier` is a random value the voter keeps; as for the other ballots,
+/// the receipt is derived from it so verify_ballot_receipt can find the ballot
+pub fn cast_encrypted_vote<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CastEncryptedVote<'info>>,
+    nullifier: [u8; 32],
+    kind: BallotKind,
+    ballot: Option<EncryptedBallot>,
+) -> Result<()> {
+    let election = &ctx.accounts.election;
+    let clock = Clock::get()?;
+
+    require!(election.status.allows_voting(), ErrorCode::ElectionNotActive);
+    require!(
+        election.voting_mode == VotingMode::Encrypted,
+        ErrorCode::VotingModeMismatch
+    );
+    require!(
+        clock.unix_timestamp >= election.start_time
+            && clock.unix_timestamp <= election.end_time,
+        ErrorCode::VotingPeriodInvalid
+    );
+
+    let voter_credential = &mut ctx.accounts.voter_credential;
+    require!(!voter_credential.has_voted, ErrorCode::AlreadyVoted);
+    require!(voter_credential.is_verified, ErrorCode::VoterNotVerified);
+    require!(
+        voter_credential.polling_station == ctx.accounts.polling_station.key(),
+        ErrorCode::WrongPollingStation
+    );
+
+    // Polls keep local hours in the station's time zone
+    let mut regions = load_region_chain(
+        ctx.remaining_accounts,
+        &election.key(),
+        &ctx.accounts.polling_station.region,
+    )?;
+    let (opens, closes) = election.voting_window(regions[0].time_zone);
+    require!(
+        clock.unix_timestamp >= opens && clock.unix_timestamp <= closes,
+        ErrorCode::VotingPeriodInvalid
+    );
+
+    require!(
+        ballot.is_some() == (kind == BallotKind::Candidate),
+        ErrorCode::InvalidBallotProof
+    );
+
+    // Proofs are bound to this election and voter so a ballot cannot be replayed
+    let tally = &mut ctx.accounts.encrypted_tally;
+    let election_key = election.key();
+    let voter_key = ctx.accounts.voter.key();
+    if let Some(ballot) = &ballot {
+        elgamal::verify_ballot(
+            ballot,
+            &tally.joint_public_key(),
+            tally.candidate_ids.len(),
+            &[election_key.as_ref(), voter_key.as_ref()],
+        )?;
+    }
+
+    redeem_voting_token(
+        election,
+        &ctx.accounts.voting_token_mint,
+        &ctx.accounts.voter_token_account,
+        &ctx.accounts.voter,
+        &ctx.accounts.token_program,
+    )?;
+
+    let mut encrypted_vote_data = [0u8; 32];
+    if let Some(ballot) = &ballot {
+        let mut ciphertext_bytes: Vec<&[u8]> = Vec::with_capacity(ballot.ciphertexts.len() * 2);
+        for (sum, ciphertext) in tally.tallies.iter_mut().zip(ballot.ciphertexts.iter()) {
+            *sum = sum.add(ciphertext)?;
+            ciphertext_bytes.push(&ciphertext.c1);
+            ciphertext_bytes.push(&ciphertext.c2);
+        }
+        encrypted_vote_data = solana_program::hash::hashv(&ciphertext_bytes).to_bytes();
+    }
+
+    voter_credential.has_voted = true;
+    voter_credential.vote_timestamp = Some(clock.unix_timestamp);
+
+    // Ballot records a digest of the ciphertexts instead of a candidate
+    let ballot_account = &mut ctx.accounts.ballot;
+    ballot_account.election = election_key;
+    ballot_account.candidate = Pubkey::default();
+    ballot_account.encrypted_vote_data = encrypted_vote_data;
+    ballot_account.timestamp = clock.unix_timestamp;
+    ballot_account.ballot_sequence = election.total_votes_cast;
+    ballot_account.verification_receipt =
+        generate_anonymous_ballot_receipt(&nullifier, clock.unix_timestamp);
+    ballot_account.is_revealed = false;
+    ballot_account.region = Some(regions[0].key());
+    ballot_account.polling_station = Some(ctx.accounts.polling_station.key());
+    ballot_account.kind = kind;
+    ballot_account.bump = ctx.bumps.ballot;
+
+    let nullifier_record = &mut ctx.accounts.nullifier_record;
+    nullifier_record.election = election_key;
+    nullifier_record.ballot = ballot_account.key();
+    nullifier_record.bump = ctx.bumps.nullifier_record;
+
+    // Counted as cast at the station and in every region; no candidate is
+    // known until the tally is decrypted
+    record_station_ballot(&mut ctx.accounts.polling_station, &mut regions, kind)?;
+
+    let election_mut = &mut ctx.accounts.election;
+    election_mut.record_ballot_kind(kind)?;
+    election_mut.total_votes_cast = election_mut
+        .total_votes_cast
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+/// Post a commissioner's decryption shares x_i * C1 for every candidate tally,
+/// each with a Chaum-Pedersen proof against the commissioner's public key share
+pub fn submit_decryption_share(
+    ctx: Context<SubmitDecryptionShare>,
+    shares: Vec<[u8; 32]>,
+    proofs: Vec<EqualityProof>,
+) -> Result<()> {
+    let election = &ctx.accounts.election;
+    let tally = &mut ctx.accounts.encrypted_tally;
+
+    require!(
+        election.status == ElectionStatus::Closed && !tally.is_published,
+        ErrorCode::DecryptionClosed
+    );
+    require!(
+        shares.len() == tally.tallies.len() && proofs.len() == tally.tallies.len(),
+        ErrorCode::InvalidDecryptionProof
+    );
+
+    let commissioner = ctx.accounts.commissioner.key();
+    let position = election
+        .commissioners
+        .iter()
+        .position(|key| *key == commissioner)
+        .ok_or(ErrorCode::NotCommissioner)?;
+    let trustee_index = (position + 1) as u8;
+    require!(
+        !tally
+            .decryptions
+            .iter()
+            .any(|decryption| decryption.trustee_index == trustee_index),
+        ErrorCode::DecryptionShareAlreadySubmitted
+    );
+
+    let public_share = elgamal::trustee_public_share(&tally.key_commitments, trustee_index)?;
+    let election_key = election.key();
+    for ((share, proof), ciphertext) in shares.iter().zip(proofs.iter()).zip(tally.tallies.iter()) {
+        elgamal::validate_point(share)?;
+        elgamal::verify_decryption_share(
+            proof,
+            &public_share,
+            &ciphertext.c1,
+            share,
+            &[election_key.as_ref(), &[trustee_index]],
+        )?;
+    }
+
+    tally.decryptions.push(TrusteeDecryption {
+        trustee_index,
+        shares,
+    });
+
+    Ok(())
+}
+
+/// Publish plaintext counts once `threshold` decryption shares are in
+/// The program checks every count against the combined shares, writes it to
+/// the candidate accounts (remaining accounts, in `candidate_ids` order) and
+/// moves the election to Tallied. Lagrange coefficients for the first
+/// `threshold` trustees are computed off-chain and verified here.
+pub fn publish_tally<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PublishTally<'info>>,
+    counts: Vec<u64>,
+    lagrange_coefficients: Vec<[u8; 32]>,
+) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+    let tally = &mut ctx.accounts.encrypted_tally;
+
+    election.status.check_transition(ElectionStatus::Tallied)?;
+    require!(
+        election.voting_mode == VotingMode::Encrypted,
+        ErrorCode::VotingModeMismatch
+    );
+
+    let threshold = tally.threshold as usize;
+    require!(
+        tally.decryptions.len() >= threshold,
+        ErrorCode::NotEnoughDecryptionShares
+    );
+    require!(
+        lagrange_coefficients.len() == threshold,
+        ErrorCode::InvalidLagrangeCoefficient
+    );
+    require!(counts.len() == tally.tallies.len(), ErrorCode::TallyMismatch);
+
+    let trustees = &tally.decryptions[..threshold];
+    let indices: Vec<u8> = trustees.iter().map(|d| d.trustee_index).collect();
+    let mut lambdas = Vec::with_capacity(threshold);
+    for (lambda, trustee) in lagrange_coefficients.iter().zip(trustees.iter()) {
+        lambdas.push(elgamal::verify_lagrange_coefficient(
+            lambda,
+            trustee.trustee_index,
+            &indices,
+        )?);
+    }
+
+    let mut total: u64 = 0;
+    for (k, (ciphertext, count)) in tally.tallies.iter().zip(counts.iter()).enumerate() {
+        let shares: Vec<[u8; 32]> = trustees.iter().map(|d| d.shares[k]).collect();
+        elgamal::verify_decrypted_count(&ciphertext.c2, &lambdas, &shares, *count)?;
+        total = total.checked_add(*count).ok_or(ErrorCode::Overflow)?;
+    }
+    // Blank and spoiled ballots were never encrypted
+    require!(total == election.counted_votes(), ErrorCode::TallyMismatch);
+
+    let mut candidates = load_candidates(
+        ctx.remaining_accounts,
+        &election.key(),
+        &tally.candidate_ids,
+    )?;
+    for (candidate, count) in candidates.iter_mut().zip(counts.iter()) {
+        candidate.vote_count = *count;
+        candidate.exit(&crate::ID)?;
+    }
+
+    tally.results = counts;
+    tally.blank_votes = election.blank_votes;
+    tally.spoiled_votes = election.spoiled_votes;
+    tally.is_published = true;
+    election.status = ElectionStatus::Tallied;
+
+    Ok(())
+}
+
+/// Load the election's candidate accounts in `candidate_ids` order
+pub(crate) fn load_candidates<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    election: &Pubkey,
+    candidate_ids: &[u32],
+) -> Result<Vec<Account<'info, Candidate>>> {
+    require!(
+        accounts.len() == candidate_ids.len(),
+        ErrorCode::InvalidTallyConfig
+    );
+
+    let mut candidates = Vec::with_capacity(accounts.len());
+    for (info, candidate_id) in accounts.iter().zip(candidate_ids.iter()) {
+        let candidate = Account::<Candidate>::try_from(info)?;
+        require!(
+            candidate.election == *election && candidate.candidate_id == *candidate_id,
+            ErrorCode::InvalidTallyConfig
+        );
+        candidates.push(candidate);
+    }
+
+    Ok(candidates)
+}
+
+#[derive(Accounts)]
+pub struct ConfigureEncryptedTally<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + EncryptedTally::INIT_SPACE,
+        seeds = [b"encrypted_tally", election.key().as_ref()],
+        bump
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct CastEncryptedVote<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"voter_credential", election.key().as_ref(), voter.key().as_ref()],
+        bump = voter_credential.bump,
+        has_one = election
+    )]
+    pub voter_credential: Account<'info, VoterCredential>,
+
+    /// Voter's polling station (the destination after an approved transfer)
+    #[account(mut, has_one = election)]
+    pub polling_station: Account<'info, PollingStation>,
+
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally", election.key().as_ref()],
+        bump = encrypted_tally.bump,
+        has_one = election
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + Nullifier::INIT_SPACE,
+        seeds = [b"nullifier", election.key().as_ref(), nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_record: Account<'info, Nullifier>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + Ballot::INIT_SPACE,
+        seeds = [
+            b"ballot",
+            election.key().as_ref(),
+            &election.total_votes_cast.to_le_bytes()
+        ],
+        bump
+    )]
+    pub ballot: Account<'info, Ballot>,
+
+    #[account(
+        mut,
+        seeds = [b"voting_token_mint", election.key().as_ref()],
+        bump,
+    )]
+    pub voting_token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = voting_token_mint,
+        associated_token::authority = voter
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitDecryptionShare<'info> {
+    #[account(
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally", election.key().as_ref()],
+        bump = encrypted_tally.bump,
+        has_one = election
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+}
+
+#[derive(Accounts)]
+pub struct PublishTally<'info> {
+    #[account(
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally", election.key().as_ref()],
+        bump = encrypted_tally.bump,
+        has_one = election
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+}
@@ -1,50 +1,163 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token};
-use crate::state::Election;
+use crate::state::{
+    Election, ElectionRegistry, ElectionStatus, TimeZone, VotingMode,
+    DEFAULT_PARLIAMENTARY_THRESHOLD_BPS, MAX_COMMISSIONERS,
+};
 use crate::errors::ErrorCode;
 
+/// Create the program-wide election registry (once per deployment)
+pub fn initialize_registry(ctx: Context<InitializeRegistry>) -> Result<()> {
+    let registry = &mut ctx.accounts.registry;
+    registry.election_count = 0;
+    registry.bump = ctx.bumps.registry;
+    Ok(())
+}
+
 /// Initialize a new election with multi-sig commissioners
+/// The election takes the registry's next ID; its PDA is derived from that ID
 pub fn initialize_election(
     ctx: Context<InitializeElection>,
     election_name: String,
+    registration_start: i64,
+    registration_end: i64,
     start_time: i64,
     end_time: i64,
     commissioners: Vec<Pubkey>,
     required_signatures: u8,
+    voting_mode: VotingMode,
 ) -> Result<()> {
     require!(
-        commissioners.len() >= required_signatures as usize,
+        required_signatures > 0
+            && commissioners.len() >= required_signatures as usize
+            && commissioners.len() <= MAX_COMMISSIONERS,
         ErrorCode::InvalidCommissionerCount
     );
-    require!(start_time < end_time, ErrorCode::InvalidElectionPeriod);
+    // Duplicate commissioners would let one key count as several approvals
+    for (i, commissioner) in commissioners.iter().enumerate() {
+        require!(
+            !commissioners[..i].contains(commissioner),
+            ErrorCode::InvalidCommissionerCount
+        );
+    }
+    validate_schedule(
+        registration_start,
+        registration_end,
+        start_time,
+        end_time,
+        voting_mode,
+    )?;
     require!(election_name.len() <= 100, ErrorCode::NameTooLong);
 
+    let clock = Clock::get()?;
+    let registry = &mut ctx.accounts.registry;
     let election = &mut ctx.accounts.election;
+    election.election_id = registry.election_count;
+    registry.election_count = registry
+        .election_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    election.nik_salt = solana_program::hash::hashv(&[
+        b"nik_salt",
+        election.key().as_ref(),
+        &clock.slot.to_le_bytes(),
+    ])
+    .to_bytes();
     election.authority = ctx.accounts.authority.key();
     election.election_name = election_name;
+    election.registration_start = registration_start;
+    election.registration_end = registration_end;
     election.start_time = start_time;
     election.end_time = end_time;
-    election.is_active = false;
+    election.status = ElectionStatus::Draft;
+    election.voting_mode = voting_mode;
     election.total_registered_voters = 0;
     election.total_votes_cast = 0;
+    election.total_votes_revealed = 0;
+    election.blank_votes = 0;
+    election.spoiled_votes = 0;
     election.commissioners = commissioners;
     election.required_signatures = required_signatures;
+    election.registrars = Vec::new();
+    election.proposal_count = 0;
+    election.ballot_issuer = [0u8; 32];
+    election.ballot_issuer_authority = Pubkey::default();
+    election.ballot_tokens_issued = 0;
+    election.membership_root = [0u8; 32];
+    election.parent_election = None;
+    election.parliamentary_threshold_bps = DEFAULT_PARLIAMENTARY_THRESHOLD_BPS;
+    election.party_count = 0;
+    election.event = None;
+    election.max_selections = 1;
+    election.total_selections = 0;
+    election.multi_seat_tallied = false;
     election.bump = ctx.bumps.election;
 
     Ok(())
 }
 
+/// registration_start < registration_end <= start_time < end_time, with
+/// polls open in every time zone: WIB opens two hours into the national
+/// window and WIT closes two hours before its end, and membership-proof
+/// ballots are only taken while polls are open in both
+pub(crate) fn validate_schedule(
+    registration_start: i64,
+    registration_end: i64,
+    start_time: i64,
+    end_time: i64,
+    voting_mode: VotingMode,
+) -> Result<()> {
+    let zone_spread =
+        (TimeZone::Wit.utc_offset_hours() - TimeZone::Wib.utc_offset_hours()) * 3600;
+    let min_length = match voting_mode {
+        VotingMode::ZkMembership => 2 * zone_spread,
+        _ => zone_spread,
+    };
+    require!(
+        end_time.saturating_sub(start_time) > min_length,
+        ErrorCode::InvalidElectionPeriod
+    );
+    require!(
+        registration_start < registration_end && registration_end <= start_time,
+        ErrorCode::InvalidRegistrationPeriod
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeRegistry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ElectionRegistry::INIT_SPACE,
+        seeds = [b"election_registry"],
+        bump
+    )]
+    pub registry: Account<'info, ElectionRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
-#[instruction(election_name: String)]
 pub struct InitializeElection<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
+    #[account(
+        mut,
+        seeds = [b"election_registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, ElectionRegistry>,
+
     #[account(
         init,
         payer = authority,
         space = 8 + Election::INIT_SPACE,
-        seeds = [b"election", election_name.as_bytes()],
+        seeds = [b"election", &registry.election_count.to_le_bytes()],
         bump
     )]
     pub election: Account<'info, Election>,
@@ -56,6 +169,7 @@ pub struct InitializeElection<'info> {
         bump,
         mint::decimals = 0,
         mint::authority = election,
+        mint::freeze_authority = election,
     )]
     pub voting_token_mint: Account<'info, Mint>,
 
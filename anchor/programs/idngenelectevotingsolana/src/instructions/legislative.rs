@@ -0,0 +1,575 @@
+use anchor_lang::prelude::*;
+use crate::blind_signature::BlindSignature;
+use crate::instructions::cast_vote::{record_station_ballot, verify_station_token};
+use crate::instructions::region::create_pda_account;
+use crate::state::{
+    Ballot, BallotKind, DistrictRegion, Election, ElectoralDistrict, Nullifier, Party,
+    PartyListCandidate, PollingStation, Proposal, ProposalAction, RegionLevel, VotingMode,
+    MAX_DISTRICT_REGIONS, MAX_DISTRICT_SEATS,
+};
+use crate::errors::ErrorCode;
+use crate::utils::generate_anonymous_ballot_receipt;
+
+/// Create a legislative electoral district (dapil) from whole regions
+/// (requires an approved commissioner proposal naming the same district)
+/// For each region, in `region_codes` order, its DistrictRegion PDA and then
+/// those of its parents up to the province are passed as remaining accounts,
+/// so no region ends up in two dapils
+pub fn create_electoral_district<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreateElectoralDistrict<'info>>,
+    district_number: u16,
+    name: String,
+    seats: u8,
+    region_codes: Vec<u64>,
+) -> Result<()> {
+    validate_electoral_district(district_number, &name, seats, &region_codes)?;
+
+    let election = &ctx.accounts.election;
+    check_legislative_registration(election)?;
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::CreateElectoralDistrict {
+            district_number,
+            name: name.clone(),
+            seats,
+            region_codes: region_codes.clone(),
+        },
+    )?;
+
+    claim_district_regions(
+        ctx.remaining_accounts,
+        &election.key(),
+        district_number,
+        &region_codes,
+        &ctx.accounts.commissioner.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    let electoral_district = &mut ctx.accounts.electoral_district;
+    electoral_district.election = election.key();
+    electoral_district.district_number = district_number;
+    electoral_district.name = name;
+    electoral_district.seats = seats;
+    electoral_district.region_codes = region_codes;
+    electoral_district.list_candidate_count = 0;
+    electoral_district.blank_votes = 0;
+    electoral_district.spoiled_votes = 0;
+    electoral_district.bump = ctx.bumps.electoral_district;
+
+    Ok(())
+}
+
+/// Register a party contesting the legislative election
+/// (requires an approved commissioner proposal naming the same party)
+pub fn register_party(ctx: Context<RegisterParty>, party_id: u16, name: String) -> Result<()> {
+    validate_party(party_id, &name)?;
+
+    let election = &ctx.accounts.election;
+    check_legislative_registration(election)?;
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::RegisterParty {
+            party_id,
+            name: name.clone(),
+        },
+    )?;
+
+    let party = &mut ctx.accounts.party;
+    party.election = election.key();
+    party.party_id = party_id;
+    party.name = name;
+    party.vote_count = 0;
+    party.district_votes = Vec::new();
+    party.bump = ctx.bumps.party;
+
+    let election = &mut ctx.accounts.election;
+    election.party_count = election.party_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+/// Put a candidate (caleg) on a party's list in one dapil
+/// (requires an approved commissioner proposal naming the same candidate)
+pub fn register_list_candidate(
+    ctx: Context<RegisterListCandidate>,
+    party_id: u16,
+    district_number: u16,
+    list_number: u8,
+    name: String,
+) -> Result<()> {
+    validate_list_candidate(list_number, &name)?;
+
+    let election = &ctx.accounts.election;
+    check_legislative_registration(election)?;
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::RegisterListCandidate {
+            party_id,
+            district_number,
+            list_number,
+            name: name.clone(),
+        },
+    )?;
+
+    let list_candidate = &mut ctx.accounts.list_candidate;
+    list_candidate.election = election.key();
+    list_candidate.party = ctx.accounts.party.key();
+    list_candidate.party_id = party_id;
+    list_candidate.district_number = district_number;
+    list_candidate.list_number = list_number;
+    list_candidate.name = name;
+    list_candidate.vote_count = 0;
+    list_candidate.bump = ctx.bumps.list_candidate;
+
+    let electoral_district = &mut ctx.accounts.electoral_district;
+    electoral_district.list_candidate_count = electoral_district
+        .list_candidate_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+/// Cast an open-list legislative ballot with a blind ballot token
+/// Marks either a party or one candidate on its list in the voter's dapil;
+/// the party is credited either way, and the candidate too when chosen.
+/// Blank and spoiled ballots pass neither and count in the dapil.
+/// Like cast_vote, the station's region chain is passed as remaining accounts
+/// `contents_signature` signs, under the nullifier key, the ballot kind and
+/// the PDAs of the dapil, party and list candidate (zero when not marked)
+pub fn cast_legislative_vote<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CastLegislativeVote<'info>>,
+    nullifier: [u8; 32],
+    signature: BlindSignature,
+    contents_signature: BlindSignature,
+    kind: BallotKind,
+) -> Result<()> {
+    let election = &ctx.accounts.election;
+    let clock = Clock::get()?;
+
+    require!(
+        election.voting_mode == VotingMode::Legislative,
+        ErrorCode::VotingModeMismatch
+    );
+    require!(election.status.allows_voting(), ErrorCode::ElectionNotActive);
+    require!(
+        clock.unix_timestamp >= election.start_time
+            && clock.unix_timestamp <= election.end_time,
+        ErrorCode::VotingPeriodInvalid
+    );
+
+    // Blank and spoiled ballots mark no party or candidate
+    let marks_party = kind == BallotKind::Candidate;
+    require!(
+        ctx.accounts.party.is_some() == marks_party
+            && (marks_party || ctx.accounts.list_candidate.is_none()),
+        ErrorCode::CandidateAccountMismatch
+    );
+    if let (Some(party), Some(list_candidate)) =
+        (&ctx.accounts.party, &ctx.accounts.list_candidate)
+    {
+        require!(
+            list_candidate.party == party.key(),
+            ErrorCode::PartyListMismatch
+        );
+    }
+
+    let kind_byte = [kind as u8];
+    let party_key = ctx.accounts.party.as_ref().map(|party| party.key()).unwrap_or_default();
+    let list_candidate_key = ctx
+        .accounts
+        .list_candidate
+        .as_ref()
+        .map(|list_candidate| list_candidate.key())
+        .unwrap_or_default();
+    let electoral_district_key = ctx.accounts.electoral_district.key();
+    let mut regions = verify_station_token(
+        election,
+        &ctx.accounts.polling_station,
+        ctx.remaining_accounts,
+        &nullifier,
+        &signature,
+        &[
+            &kind_byte,
+            electoral_district_key.as_ref(),
+            party_key.as_ref(),
+            list_candidate_key.as_ref(),
+        ],
+        &contents_signature,
+        clock.unix_timestamp,
+    )?;
+
+    // The ballot paper is the one for the station's dapil
+    let electoral_district = &ctx.accounts.electoral_district;
+    require!(
+        electoral_district.covers(&regions),
+        ErrorCode::InvalidElectoralDistrict
+    );
+
+    let ballot = &mut ctx.accounts.ballot;
+    ballot.election = election.key();
+    ballot.candidate = match (&ctx.accounts.list_candidate, &ctx.accounts.party) {
+        (Some(list_candidate), _) => list_candidate.key(),
+        (None, Some(party)) => party.key(),
+        (None, None) => Pubkey::default(),
+    };
+    ballot.encrypted_vote_data = [0u8; 32];
+    ballot.is_revealed = false;
+    ballot.timestamp = clock.unix_timestamp;
+    ballot.ballot_sequence = election.total_votes_cast;
+    ballot.verification_receipt = generate_anonymous_ballot_receipt(&nullifier, clock.unix_timestamp);
+    ballot.region = Some(regions[0].key());
+    ballot.polling_station = Some(ctx.accounts.polling_station.key());
+    ballot.kind = kind;
+    ballot.ranking = Vec::new();
+    ballot.selections = Vec::new();
+    ballot.bump = ctx.bumps.ballot;
+
+    let nullifier_record = &mut ctx.accounts.nullifier_record;
+    nullifier_record.election = ballot.election;
+    nullifier_record.ballot = ballot.key();
+    nullifier_record.bump = ctx.bumps.nullifier_record;
+
+    let district_number = electoral_district.district_number;
+    if let Some(party) = ctx.accounts.party.as_mut() {
+        party.record_vote(district_number)?;
+    }
+    if let Some(list_candidate) = ctx.accounts.list_candidate.as_mut() {
+        list_candidate.vote_count = list_candidate
+            .vote_count
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+    let electoral_district = &mut ctx.accounts.electoral_district;
+    match kind {
+        BallotKind::Candidate => {}
+        BallotKind::Blank => {
+            electoral_district.blank_votes = electoral_district
+                .blank_votes
+                .checked_add(1)
+                .ok_or(ErrorCode::Overflow)?
+        }
+        BallotKind::Spoiled => {
+            electoral_district.spoiled_votes = electoral_district
+                .spoiled_votes
+                .checked_add(1)
+                .ok_or(ErrorCode::Overflow)?
+        }
+    }
+
+    record_station_ballot(&mut ctx.accounts.polling_station, &mut regions, kind)?;
+
+    let election_mut = &mut ctx.accounts.election;
+    election_mut.record_ballot_kind(kind)?;
+    election_mut.total_votes_cast = election_mut
+        .total_votes_cast
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+/// Shared by create_proposal and create_electoral_district
+pub(crate) fn validate_electoral_district(
+    district_number: u16,
+    name: &str,
+    seats: u8,
+    region_codes: &[u64],
+) -> Result<()> {
+    require!(name.len() <= 100, ErrorCode::NameTooLong);
+    require!(
+        district_number > 0
+            && seats > 0
+            && usize::from(seats) <= MAX_DISTRICT_SEATS
+            && !region_codes.is_empty()
+            && region_codes.len() <= MAX_DISTRICT_REGIONS,
+        ErrorCode::InvalidElectoralDistrict
+    );
+    for (i, region_code) in region_codes.iter().enumerate() {
+        let (level, _) = RegionLevel::from_code(*region_code)?;
+        require!(
+            level != RegionLevel::Village && !region_codes[..i].contains(region_code),
+            ErrorCode::InvalidElectoralDistrict
+        );
+    }
+    Ok(())
+}
+
+/// Record each region of a new dapil, and mark its parents as having a
+/// dapil below them. A region already recorded (in a dapil, or above one)
+/// or with a parent in a dapil overlaps another dapil
+fn claim_district_regions<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    election: &Pubkey,
+    district_number: u16,
+    region_codes: &[u64],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let mut accounts = accounts.iter();
+    for region_code in region_codes {
+        let mut code = Some(*region_code);
+        let mut in_district = true;
+        while let Some(current) = code {
+            let info = accounts.next().ok_or(ErrorCode::InvalidElectoralDistrict)?;
+            let (mut record, existed) =
+                load_district_region(info, election, current, payer, system_program)?;
+            if in_district {
+                require!(!existed, ErrorCode::DistrictOverlap);
+                record.district_number = Some(district_number);
+            } else {
+                require!(record.district_number.is_none(), ErrorCode::DistrictOverlap);
+            }
+            record.exit(&crate::ID)?;
+
+            code = RegionLevel::from_code(current)?.1;
+            in_district = false;
+        }
+    }
+    require!(accounts.next().is_none(), ErrorCode::InvalidElectoralDistrict);
+    Ok(())
+}
+
+/// Load a region's DistrictRegion record, creating it if this is the first
+/// dapil to reach the region; also reports whether it already existed
+fn load_district_region<'info>(
+    info: &'info AccountInfo<'info>,
+    election: &Pubkey,
+    region_code: u64,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<(Account<'info, DistrictRegion>, bool)> {
+    let code_bytes = region_code.to_le_bytes();
+    let (address, bump) = Pubkey::find_program_address(
+        &[b"district_region", election.as_ref(), &code_bytes],
+        &crate::ID,
+    );
+    require_keys_eq!(info.key(), address, ErrorCode::InvalidElectoralDistrict);
+
+    if info.owner == &crate::ID {
+        return Ok((Account::<DistrictRegion>::try_from(info)?, true));
+    }
+
+    let seeds: &[&[u8]] = &[b"district_region", election.as_ref(), &code_bytes, &[bump]];
+    create_pda_account(info, payer, system_program, 8 + DistrictRegion::INIT_SPACE, seeds)?;
+
+    let mut record = Account::<DistrictRegion>::try_from_unchecked(info)?;
+    record.election = *election;
+    record.region_code = region_code;
+    record.district_number = None;
+    record.bump = bump;
+    Ok((record, false))
+}
+
+/// Shared by create_proposal and register_party
+pub(crate) fn validate_party(party_id: u16, name: &str) -> Result<()> {
+    require!(name.len() <= 100, ErrorCode::NameTooLong);
+    require!(party_id > 0, ErrorCode::InvalidBallotNumber);
+    Ok(())
+}
+
+/// Shared by create_proposal and register_list_candidate
+pub(crate) fn validate_list_candidate(list_number: u8, name: &str) -> Result<()> {
+    require!(name.len() <= 100, ErrorCode::NameTooLong);
+    require!(list_number > 0, ErrorCode::InvalidBallotNumber);
+    Ok(())
+}
+
+fn check_legislative_registration(election: &Election) -> Result<()> {
+    require!(
+        election.voting_mode == VotingMode::Legislative,
+        ErrorCode::VotingModeMismatch
+    );
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(district_number: u16)]
+pub struct CreateElectoralDistrict<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + ElectoralDistrict::INIT_SPACE,
+        seeds = [b"electoral_district", election.key().as_ref(), &district_number.to_le_bytes()],
+        bump
+    )]
+    pub electoral_district: Account<'info, ElectoralDistrict>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(party_id: u16)]
+pub struct RegisterParty<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + Party::INIT_SPACE,
+        seeds = [b"party", election.key().as_ref(), &party_id.to_le_bytes()],
+        bump
+    )]
+    pub party: Account<'info, Party>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(party_id: u16, district_number: u16, list_number: u8)]
+pub struct RegisterListCandidate<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        seeds = [b"party", election.key().as_ref(), &party_id.to_le_bytes()],
+        bump = party.bump
+    )]
+    pub party: Box<Account<'info, Party>>,
+
+    #[account(
+        mut,
+        seeds = [b"electoral_district", election.key().as_ref(), &district_number.to_le_bytes()],
+        bump = electoral_district.bump
+    )]
+    pub electoral_district: Account<'info, ElectoralDistrict>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + PartyListCandidate::INIT_SPACE,
+        seeds = [
+            b"list_candidate",
+            election.key().as_ref(),
+            &party_id.to_le_bytes(),
+            &district_number.to_le_bytes(),
+            &[list_number]
+        ],
+        bump
+    )]
+    pub list_candidate: Account<'info, PartyListCandidate>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct CastLegislativeVote<'info> {
+    /// Any fee payer; should not be the voter's registered wallet
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(mut, has_one = election)]
+    pub polling_station: Account<'info, PollingStation>,
+
+    #[account(mut, has_one = election)]
+    pub electoral_district: Account<'info, ElectoralDistrict>,
+
+    /// Omitted for a blank or spoiled ballot
+    #[account(mut, has_one = election)]
+    pub party: Option<Box<Account<'info, Party>>>,
+
+    /// Omitted for a party-only, blank or spoiled ballot; must be on the
+    /// party's list
+    #[account(
+        mut,
+        has_one = election,
+        constraint = list_candidate.district_number
+            == electoral_district.district_number @ ErrorCode::PartyListMismatch
+    )]
+    pub list_candidate: Option<Account<'info, PartyListCandidate>>,
+
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + Nullifier::INIT_SPACE,
+        seeds = [b"nullifier", election.key().as_ref(), nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_record: Account<'info, Nullifier>,
+
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + Ballot::INIT_SPACE,
+        seeds = [
+            b"ballot",
+            election.key().as_ref(),
+            &election.total_votes_cast.to_le_bytes()
+        ],
+        bump
+    )]
+    pub ballot: Box<Account<'info, Ballot>>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -1,47 +1,195 @@
 use anchor_lang::prelude::*;
-use crate::state::Election;
+use crate::state::{
+    Election, ElectionStatus, Proposal, ProposalAction, VotingMode, MAX_REGISTRARS,
+    MAX_SELECTIONS,
+};
 use crate::errors::ErrorCode;
+use crate::blind_signature::validate_issuer_key;
+use crate::instructions::seat_allocation::validate_parliamentary_threshold;
 
-/// Activate the election (requires commissioner authority)
-pub fn activate_election(ctx: Context<ManageElection>) -> Result<()> {
+/// Move the election to the next lifecycle phase (requires an approved commissioner proposal)
+pub fn transition_election(ctx: Context<ManageElection>, status: ElectionStatus) -> Result<()> {
     let election = &mut ctx.accounts.election;
     let clock = Clock::get()?;
 
+    ctx.accounts
+        .proposal
+        .execute(election, &ProposalAction::TransitionElection { status })?;
+
+    election.transition_to(status, clock.unix_timestamp)
+}
+
+/// Extend voter registration before it closes (requires an approved commissioner proposal)
+pub fn extend_registration(ctx: Context<ManageElection>, registration_end: i64) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+    let clock = Clock::get()?;
+
+    ctx.accounts
+        .proposal
+        .execute(election, &ProposalAction::ExtendRegistration { registration_end })?;
+
+    require!(
+        clock.unix_timestamp <= election.registration_end,
+        ErrorCode::RegistrationClosed
+    );
     require!(
-        clock.unix_timestamp >= election.start_time,
-        ErrorCode::ElectionNotStarted
+        registration_end > election.registration_end && registration_end <= election.start_time,
+        ErrorCode::InvalidRegistrationPeriod
     );
-    require!(!election.is_active, ErrorCode::ElectionAlreadyActive);
 
-    election.is_active = true;
+    election.registration_end = registration_end;
 
     Ok(())
 }
 
-/// Finalize election and close voting (requires commissioner authority)
-pub fn finalize_election(ctx: Context<ManageElection>) -> Result<()> {
+/// Allow a registrar to attest voter verifications (requires an approved commissioner proposal)
+pub fn add_registrar(ctx: Context<ManageElection>, registrar: Pubkey) -> Result<()> {
     let election = &mut ctx.accounts.election;
-    let clock = Clock::get()?;
 
-    require!(election.is_active, ErrorCode::ElectionNotActive);
+    ctx.accounts
+        .proposal
+        .execute(election, &ProposalAction::AddRegistrar { registrar })?;
+
+    require!(!election.is_registrar(&registrar), ErrorCode::RegistrarAlreadyAdded);
+    require!(
+        election.registrars.len() < MAX_REGISTRARS,
+        ErrorCode::TooManyRegistrars
+    );
+
+    election.registrars.push(registrar);
+
+    Ok(())
+}
+
+/// Revoke a registrar (requires an approved commissioner proposal)
+pub fn remove_registrar(ctx: Context<ManageElection>, registrar: Pubkey) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+
+    ctx.accounts
+        .proposal
+        .execute(election, &ProposalAction::RemoveRegistrar { registrar })?;
+
+    require!(election.is_registrar(&registrar), ErrorCode::RegistrarNotFound);
+
+    election.registrars.retain(|key| *key != registrar);
+
+    Ok(())
+}
+
+/// Set the blind ballot-token issuer key and the wallet that posts its
+/// responses (requires an approved commissioner proposal)
+pub fn set_ballot_issuer(
+    ctx: Context<ManageElection>,
+    issuer_key: [u8; 32],
+    issuer_authority: Pubkey,
+) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::SetBallotIssuer { issuer_key, issuer_authority },
+    )?;
+
+    // Voters blind against this key, so it cannot change once polling opens
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+    validate_issuer_key(&issuer_key)?;
+
+    election.ballot_issuer = issuer_key;
+    election.ballot_issuer_authority = issuer_authority;
+
+    Ok(())
+}
+
+/// Set the parliamentary threshold, in basis points of national party votes
+/// (requires an approved commissioner proposal)
+pub fn set_parliamentary_threshold(
+    ctx: Context<ManageElection>,
+    threshold_bps: u16,
+) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::SetParliamentaryThreshold { threshold_bps },
+    )?;
+
+    // Parties contest under a known rule, so it is fixed once polling opens
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+    require!(
+        election.voting_mode == VotingMode::Legislative,
+        ErrorCode::VotingModeMismatch
+    );
+    validate_parliamentary_threshold(threshold_bps)?;
+
+    election.parliamentary_threshold_bps = threshold_bps;
+
+    Ok(())
+}
+
+/// Let each ballot mark up to `max_selections` candidates, electing as many
+/// (requires an approved commissioner proposal)
+pub fn set_max_selections(ctx: Context<ManageElection>, max_selections: u8) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::SetMaxSelections { max_selections },
+    )?;
+
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+    validate_max_selections(max_selections)?;
+    // Commitments, ciphertexts and the other ballot types hold one choice
     require!(
-        clock.unix_timestamp > election.end_time,
-        ErrorCode::ElectionStillActive
+        max_selections == 1
+            || matches!(
+                election.voting_mode,
+                VotingMode::Plurality | VotingMode::ZkMembership
+            ),
+        ErrorCode::InvalidMaxSelections
     );
 
-    election.is_active = false;
+    election.max_selections = max_selections;
+
+    Ok(())
+}
 
+/// Shared by create_proposal and set_max_selections
+pub(crate) fn validate_max_selections(max_selections: u8) -> Result<()> {
+    require!(
+        max_selections >= 1 && usize::from(max_selections) <= MAX_SELECTIONS,
+        ErrorCode::InvalidMaxSelections
+    );
     Ok(())
 }
 
 #[derive(Accounts)]
 pub struct ManageElection<'info> {
+    #[account(
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
     pub commissioner: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"election", election.election_name.as_bytes()],
+        seeds = [b"election", &election.election_id.to_le_bytes()],
         bump = election.bump,
     )]
     pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
 }
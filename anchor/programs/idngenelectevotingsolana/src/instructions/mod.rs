@@ -4,6 +4,20 @@ pub mod verify_voter;
 pub mod cast_vote;
 pub mod manage_election;
 pub mod audit;
+pub mod proposal;
+pub mod rebind_nik;
+pub mod encrypted_tally;
+pub mod ballot_token;
+pub mod zk_membership;
+pub mod reveal_vote;
+pub mod election_result;
+pub mod runoff;
+pub mod region;
+pub mod polling_station;
+pub mod legislative;
+pub mod seat_allocation;
+pub mod election_event;
+pub mod ranked_choice;
 
 pub use initialize_election::*;
 pub use register_candidate::*;
@@ -11,3 +25,17 @@ pub use verify_voter::*;
 pub use cast_vote::*;
 pub use manage_election::*;
 pub use audit::*;
+pub use proposal::*;
+pub use rebind_nik::*;
+pub use encrypted_tally::*;
+pub use ballot_token::*;
+pub use zk_membership::*;
+pub use reveal_vote::*;
+pub use election_result::*;
+pub use runoff::*;
+pub use region::*;
+pub use polling_station::*;
+pub use legislative::*;
+pub use seat_allocation::*;
+pub use election_event::*;
+pub use ranked_choice::*;
@@ -0,0 +1,241 @@
+use anchor_lang::prelude::*;
+use crate::state::{
+    Election, PollingStation, Proposal, ProposalAction, Region, RegionLevel, VoterCredential,
+    VoterTransfer,
+};
+use crate::errors::ErrorCode;
+
+/// Create a polling station (TPS) in a village
+/// (requires an approved commissioner proposal naming the same station)
+pub fn create_polling_station(
+    ctx: Context<CreatePollingStation>,
+    region_code: u64,
+    tps_number: u16,
+    capacity: u32,
+) -> Result<()> {
+    validate_polling_station(tps_number, capacity)?;
+
+    let election = &ctx.accounts.election;
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::CreatePollingStation {
+            region_code,
+            tps_number,
+            capacity,
+        },
+    )?;
+
+    let polling_station = &mut ctx.accounts.polling_station;
+    polling_station.election = election.key();
+    polling_station.region = ctx.accounts.region.key();
+    polling_station.region_code = region_code;
+    polling_station.tps_number = tps_number;
+    polling_station.capacity = capacity;
+    polling_station.registered_voters = 0;
+    polling_station.votes_cast = 0;
+    polling_station.bump = ctx.bumps.polling_station;
+
+    Ok(())
+}
+
+/// Shared by create_proposal and create_polling_station
+pub(crate) fn validate_polling_station(tps_number: u16, capacity: u32) -> Result<()> {
+    require!(tps_number > 0 && capacity > 0, ErrorCode::InvalidPollingStation);
+    Ok(())
+}
+
+/// Add a voter to a station's register (DPT), within its capacity
+pub(crate) fn register_at_station(polling_station: &mut PollingStation) -> Result<()> {
+    require!(
+        polling_station.registered_voters < polling_station.capacity,
+        ErrorCode::PollingStationFull
+    );
+    polling_station.registered_voters += 1;
+    Ok(())
+}
+
+/// Apply to vote at another polling station (DPTb, form A5)
+/// Only while voter registration is open. A voter holds one transfer at a
+/// time: a new request replaces a pending one, or follows an approved one
+/// from the station it moved the voter to
+pub fn request_transfer(ctx: Context<RequestTransfer>) -> Result<()> {
+    check_transfer_window(&ctx.accounts.election)?;
+
+    let voter_credential = &ctx.accounts.voter_credential;
+    require!(voter_credential.is_verified, ErrorCode::VoterNotVerified);
+    require!(
+        ctx.accounts.to_station.key() != voter_credential.polling_station,
+        ErrorCode::InvalidPollingStation
+    );
+
+    let voter_transfer = &mut ctx.accounts.voter_transfer;
+    voter_transfer.election = ctx.accounts.election.key();
+    voter_transfer.voter_credential = voter_credential.key();
+    voter_transfer.from_station = voter_credential.polling_station;
+    voter_transfer.to_station = ctx.accounts.to_station.key();
+    voter_transfer.approved = false;
+    voter_transfer.bump = ctx.bumps.voter_transfer;
+
+    Ok(())
+}
+
+/// Approve a transfer as a commissioner or registrar: the voter moves from
+/// the home station's register to the destination's, and votes (and is
+/// counted) there
+pub fn approve_transfer(ctx: Context<ApproveTransfer>) -> Result<()> {
+    check_transfer_window(&ctx.accounts.election)?;
+    // A credential revoked by rebind_nik no longer holds a register entry
+    require!(ctx.accounts.voter_credential.is_verified, ErrorCode::VoterNotVerified);
+
+    let voter_transfer = &mut ctx.accounts.voter_transfer;
+    require!(!voter_transfer.approved, ErrorCode::TransferAlreadyApproved);
+    voter_transfer.approved = true;
+
+    let from_station = &mut ctx.accounts.from_station;
+    from_station.registered_voters = from_station
+        .registered_voters
+        .checked_sub(1)
+        .ok_or(ErrorCode::Overflow)?;
+    let to_station = &mut ctx.accounts.to_station;
+    register_at_station(to_station)?;
+
+    let voter_credential = &mut ctx.accounts.voter_credential;
+    voter_credential.polling_station = to_station.key();
+    voter_credential.region = to_station.region;
+
+    Ok(())
+}
+
+/// Transfers follow the voter register: Registration phase, until registration_end
+fn check_transfer_window(election: &Election) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(
+        election.status.allows_voter_registration()
+            && clock.unix_timestamp <= election.registration_end,
+        ErrorCode::TransferWindowClosed
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(region_code: u64, tps_number: u16)]
+pub struct CreatePollingStation<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        seeds = [b"region", election.key().as_ref(), &region_code.to_le_bytes()],
+        bump = region.bump,
+        constraint = region.level == RegionLevel::Village @ ErrorCode::InvalidRegion
+    )]
+    pub region: Account<'info, Region>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + PollingStation::INIT_SPACE,
+        seeds = [
+            b"polling_station",
+            election.key().as_ref(),
+            &region_code.to_le_bytes(),
+            &tps_number.to_le_bytes()
+        ],
+        bump
+    )]
+    pub polling_station: Account<'info, PollingStation>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestTransfer<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        seeds = [b"voter_credential", election.key().as_ref(), voter.key().as_ref()],
+        bump = voter_credential.bump,
+        has_one = election
+    )]
+    pub voter_credential: Account<'info, VoterCredential>,
+
+    #[account(has_one = election)]
+    pub to_station: Account<'info, PollingStation>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = 8 + VoterTransfer::INIT_SPACE,
+        seeds = [b"voter_transfer", election.key().as_ref(), voter_credential.key().as_ref()],
+        bump
+    )]
+    pub voter_transfer: Account<'info, VoterTransfer>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveTransfer<'info> {
+    #[account(
+        constraint = election.is_commissioner(&approver.key())
+            || election.is_registrar(&approver.key()) @ ErrorCode::NotTransferApprover
+    )]
+    pub approver: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"voter_transfer", election.key().as_ref(), voter_credential.key().as_ref()],
+        bump = voter_transfer.bump,
+        has_one = election,
+        has_one = voter_credential,
+        has_one = from_station,
+        has_one = to_station
+    )]
+    pub voter_transfer: Account<'info, VoterTransfer>,
+
+    #[account(
+        mut,
+        has_one = election
+    )]
+    pub voter_credential: Account<'info, VoterCredential>,
+
+    #[account(mut)]
+    pub from_station: Account<'info, PollingStation>,
+
+    #[account(mut)]
+    pub to_station: Account<'info, PollingStation>,
+}
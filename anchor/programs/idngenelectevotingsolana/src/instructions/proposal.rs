@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use crate::state::{
+    Election, Proposal, ProposalAction, MAX_COMMISSIONERS, MAX_ENCRYPTED_CANDIDATES,
+};
+use crate::errors::ErrorCode;
+use crate::instructions::region::validate_region;
+use crate::instructions::polling_station::validate_polling_station;
+use crate::instructions::legislative::{
+    validate_electoral_district, validate_list_candidate, validate_party,
+};
+use crate::instructions::seat_allocation::validate_parliamentary_threshold;
+use crate::instructions::election_event::validate_event_name;
+use crate::instructions::ranked_choice::validate_ranked_candidates;
+use crate::instructions::manage_election::validate_max_selections;
+
+/// Open a proposal for an election action (proposer's approval is recorded)
+pub fn create_proposal(ctx: Context<CreateProposal>, action: ProposalAction) -> Result<()> {
+    // Every variant is listed, so a new action cannot skip its validation
+    match &action {
+        ProposalAction::RegisterCandidate { ticket, .. } => ticket.validate()?,
+        ProposalAction::ConfigureEncryptedTally { candidate_ids, key_commitments, .. } => {
+            require!(
+                candidate_ids.len() <= MAX_ENCRYPTED_CANDIDATES
+                    && key_commitments.len() <= MAX_COMMISSIONERS,
+                ErrorCode::InvalidTallyConfig
+            );
+        }
+        ProposalAction::CreateRegion { region_code, name } => {
+            validate_region(*region_code, name)?;
+        }
+        ProposalAction::CreatePollingStation { tps_number, capacity, .. } => {
+            validate_polling_station(*tps_number, *capacity)?;
+        }
+        ProposalAction::CreateElectoralDistrict { district_number, name, seats, region_codes } => {
+            validate_electoral_district(*district_number, name, *seats, region_codes)?;
+        }
+        ProposalAction::RegisterParty { party_id, name } => validate_party(*party_id, name)?,
+        ProposalAction::RegisterListCandidate { list_number, name, .. } => {
+            validate_list_candidate(*list_number, name)?;
+        }
+        ProposalAction::SetParliamentaryThreshold { threshold_bps } => {
+            validate_parliamentary_threshold(*threshold_bps)?;
+        }
+        ProposalAction::CreateElectionEvent { name } => validate_event_name(name)?,
+        ProposalAction::ConfigureRankedChoice { candidate_ids } => {
+            validate_ranked_candidates(candidate_ids)?;
+        }
+        ProposalAction::SetMaxSelections { max_selections } => {
+            validate_max_selections(*max_selections)?;
+        }
+        // Checked against the election when the proposal executes
+        ProposalAction::TransitionElection { .. }
+        | ProposalAction::ExtendRegistration { .. }
+        | ProposalAction::RebindNik { .. }
+        | ProposalAction::AddRegistrar { .. }
+        | ProposalAction::RemoveRegistrar { .. }
+        | ProposalAction::SetBallotIssuer { .. }
+        | ProposalAction::ConfigureMembershipProofs { .. }
+        | ProposalAction::CreateRunoff { .. }
+        | ProposalAction::AddEventContest { .. } => {}
+    }
+
+    let clock = Clock::get()?;
+    let election = &mut ctx.accounts.election;
+
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.election = election.key();
+    proposal.proposal_id = election.proposal_count;
+    proposal.proposer = ctx.accounts.commissioner.key();
+    proposal.action = action;
+    proposal.approvals = vec![ctx.accounts.commissioner.key()];
+    proposal.executed = false;
+    proposal.created_at = clock.unix_timestamp;
+    proposal.bump = ctx.bumps.proposal;
+
+    election.proposal_count = election
+        .proposal_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+/// Approve an open proposal as another commissioner
+pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+    let commissioner = ctx.accounts.commissioner.key();
+    let proposal = &mut ctx.accounts.proposal;
+
+    require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+    require!(
+        !proposal.approvals.contains(&commissioner),
+        ErrorCode::DuplicateApproval
+    );
+
+    proposal.approvals.push(commissioner);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [b"proposal", election.key().as_ref(), &election.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    #[account(
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
@@ -0,0 +1,303 @@
+use anchor_lang::prelude::*;
+use crate::blind_signature::BlindSignature;
+use crate::instructions::cast_vote::{record_station_ballot, verify_station_token};
+use crate::instructions::encrypted_tally::load_candidates;
+use crate::instructions::region::record_region_votes;
+use crate::state::{
+    Ballot, BallotKind, Election, IrvTally, Nullifier, PollingStation, Proposal, ProposalAction,
+    VotingMode, MAX_RANKED_CANDIDATES,
+};
+use crate::errors::ErrorCode;
+use crate::utils::generate_anonymous_ballot_receipt;
+
+/// Fix the candidates of a ranked-choice election and create its tally
+/// (requires an approved commissioner proposal naming the same candidates)
+/// Candidates must already be registered and are passed as remaining
+/// accounts in `candidate_ids` order
+pub fn configure_ranked_choice<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ConfigureRankedChoice<'info>>,
+    candidate_ids: Vec<u32>,
+) -> Result<()> {
+    validate_ranked_candidates(&candidate_ids)?;
+
+    let election = &ctx.accounts.election;
+    require!(
+        election.voting_mode == VotingMode::RankedChoice,
+        ErrorCode::VotingModeMismatch
+    );
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::ConfigureRankedChoice {
+            candidate_ids: candidate_ids.clone(),
+        },
+    )?;
+
+    load_candidates(ctx.remaining_accounts, &election.key(), &candidate_ids)?;
+
+    let irv_tally = &mut ctx.accounts.irv_tally;
+    irv_tally.election = election.key();
+    irv_tally.continuing = vec![true; candidate_ids.len()];
+    irv_tally.counts = vec![0; candidate_ids.len()];
+    irv_tally.candidate_ids = candidate_ids;
+    irv_tally.rounds = Vec::new();
+    irv_tally.ballots_counted = 0;
+    irv_tally.exhausted = 0;
+    irv_tally.winner = None;
+    irv_tally.blank_votes = 0;
+    irv_tally.spoiled_votes = 0;
+    irv_tally.bump = ctx.bumps.irv_tally;
+
+    Ok(())
+}
+
+/// Cast a ranked ballot with a blind ballot token
+/// `ranking` lists distinct candidate IDs, most preferred first; it need not
+/// rank every candidate, and is empty for a blank or spoiled ballot. Like
+/// cast_vote, the station's region chain is passed as remaining accounts,
+/// followed by the first preference's region tallies
+/// `contents_signature` signs, under the nullifier key, the ballot kind and
+/// the ranked candidate IDs (u32 LE) in order
+pub fn cast_ranked_vote<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CastRankedVote<'info>>,
+    nullifier: [u8; 32],
+    signature: BlindSignature,
+    contents_signature: BlindSignature,
+    kind: BallotKind,
+    ranking: Vec<u32>,
+) -> Result<()> {
+    let election = &ctx.accounts.election;
+    let clock = Clock::get()?;
+
+    require!(
+        election.voting_mode == VotingMode::RankedChoice,
+        ErrorCode::VotingModeMismatch
+    );
+    require!(election.status.allows_voting(), ErrorCode::ElectionNotActive);
+    require!(
+        clock.unix_timestamp >= election.start_time
+            && clock.unix_timestamp <= election.end_time,
+        ErrorCode::VotingPeriodInvalid
+    );
+
+    let candidate_ids = &ctx.accounts.irv_tally.candidate_ids;
+    require!(
+        ranking.is_empty() == (kind != BallotKind::Candidate),
+        ErrorCode::InvalidRanking
+    );
+    for (i, candidate_id) in ranking.iter().enumerate() {
+        require!(
+            candidate_ids.contains(candidate_id) && !ranking[..i].contains(candidate_id),
+            ErrorCode::InvalidRanking
+        );
+    }
+
+    let kind_byte = [kind as u8];
+    let ranked_ids: Vec<[u8; 4]> = ranking.iter().map(|id| id.to_le_bytes()).collect();
+    let mut contents: Vec<&[u8]> = vec![&kind_byte];
+    contents.extend(ranked_ids.iter().map(|id| id.as_slice()));
+    let mut regions = verify_station_token(
+        election,
+        &ctx.accounts.polling_station,
+        ctx.remaining_accounts,
+        &nullifier,
+        &signature,
+        &contents,
+        &contents_signature,
+        clock.unix_timestamp,
+    )?;
+
+    let first_preference: Vec<u32> = ranking.first().copied().into_iter().collect();
+    let ballot = &mut ctx.accounts.ballot;
+    ballot.election = election.key();
+    ballot.candidate = Pubkey::default();
+    ballot.encrypted_vote_data = [0u8; 32];
+    ballot.is_revealed = false;
+    ballot.timestamp = clock.unix_timestamp;
+    ballot.ballot_sequence = election.total_votes_cast;
+    ballot.verification_receipt = generate_anonymous_ballot_receipt(&nullifier, clock.unix_timestamp);
+    ballot.region = Some(regions[0].key());
+    ballot.polling_station = Some(ctx.accounts.polling_station.key());
+    ballot.kind = kind;
+    ballot.ranking = ranking;
+    ballot.selections = Vec::new();
+    ballot.bump = ctx.bumps.ballot;
+
+    let nullifier_record = &mut ctx.accounts.nullifier_record;
+    nullifier_record.election = ballot.election;
+    nullifier_record.ballot = ballot.key();
+    nullifier_record.bump = ctx.bumps.nullifier_record;
+
+    record_station_ballot(&mut ctx.accounts.polling_station, &mut regions, kind)?;
+    record_region_votes(
+        &ctx.remaining_accounts[regions.len()..],
+        &regions,
+        &first_preference,
+        &ctx.accounts.relayer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    let election_mut = &mut ctx.accounts.election;
+    election_mut.record_ballot_kind(kind)?;
+    election_mut.total_votes_cast = election_mut
+        .total_votes_cast
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+/// Count ranked ballots, one instant-runoff round at a time
+/// Ballots are passed as remaining accounts in ballot_sequence order, as
+/// many per transaction as fit; once every ballot is counted the round
+/// closes, and the next round starts again from ballot 0
+/// Blank and spoiled ballots are passed too but count for no candidate;
+/// with no candidate votes at all the round cannot close (NoContinuingVotes)
+pub fn tally_irv<'info>(ctx: Context<'_, '_, 'info, 'info, TallyIrv<'info>>) -> Result<()> {
+    let election = &ctx.accounts.election;
+    let irv_tally = &mut ctx.accounts.irv_tally;
+
+    require!(
+        election.status.allows_recapitulation(),
+        ErrorCode::RecapitulationClosed
+    );
+    require!(irv_tally.winner.is_none(), ErrorCode::IrvTallyComplete);
+
+    for info in ctx.remaining_accounts.iter() {
+        let ballot = Account::<Ballot>::try_from(info)?;
+        require!(
+            ballot.election == election.key()
+                && ballot.ballot_sequence == irv_tally.ballots_counted,
+            ErrorCode::IrvBallotOrder
+        );
+        irv_tally.count_ballot(ballot.kind, &ballot.ranking)?;
+    }
+
+    if irv_tally.ballots_counted == election.total_votes_cast {
+        irv_tally.close_round()?;
+        irv_tally.blank_votes = election.blank_votes;
+        irv_tally.spoiled_votes = election.spoiled_votes;
+    }
+
+    Ok(())
+}
+
+/// Shared by create_proposal and configure_ranked_choice
+pub(crate) fn validate_ranked_candidates(candidate_ids: &[u32]) -> Result<()> {
+    require!(
+        candidate_ids.len() <= MAX_RANKED_CANDIDATES,
+        ErrorCode::InvalidTallyConfig
+    );
+    require!(candidate_ids.len() >= 2, ErrorCode::NotEnoughCandidates);
+    for (i, candidate_id) in candidate_ids.iter().enumerate() {
+        require!(
+            !candidate_ids[..i].contains(candidate_id),
+            ErrorCode::InvalidTallyConfig
+        );
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ConfigureRankedChoice<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + IrvTally::INIT_SPACE,
+        seeds = [b"irv_tally", election.key().as_ref()],
+        bump
+    )]
+    pub irv_tally: Box<Account<'info, IrvTally>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct CastRankedVote<'info> {
+    /// Any fee payer; should not be the voter's registered wallet
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(mut, has_one = election)]
+    pub polling_station: Account<'info, PollingStation>,
+
+    #[account(
+        seeds = [b"irv_tally", election.key().as_ref()],
+        bump = irv_tally.bump,
+        has_one = election
+    )]
+    pub irv_tally: Box<Account<'info, IrvTally>>,
+
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + Nullifier::INIT_SPACE,
+        seeds = [b"nullifier", election.key().as_ref(), nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_record: Account<'info, Nullifier>,
+
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + Ballot::INIT_SPACE,
+        seeds = [
+            b"ballot",
+            election.key().as_ref(),
+            &election.total_votes_cast.to_le_bytes()
+        ],
+        bump
+    )]
+    pub ballot: Box<Account<'info, Ballot>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TallyIrv<'info> {
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"irv_tally", election.key().as_ref()],
+        bump = irv_tally.bump,
+        has_one = election
+    )]
+    pub irv_tally: Box<Account<'info, IrvTally>>,
+}
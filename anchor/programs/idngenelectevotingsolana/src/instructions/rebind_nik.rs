@@ -0,0 +1,131 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount, Mint};
+use anchor_spl::associated_token::AssociatedToken;
+use crate::state::{Election, NikRecord, Proposal, ProposalAction, VoterCredential};
+use crate::errors::ErrorCode;
+use crate::instructions::verify_voter::issue_voting_token;
+
+/// Rebind a NIK to a new wallet after the citizen lost the old one
+/// (requires an approved commissioner proposal naming the NIK commitment and new wallet)
+/// The old credential is revoked and a copy is issued to the new wallet
+pub fn rebind_nik(ctx: Context<RebindNik>) -> Result<()> {
+    let election = &ctx.accounts.election;
+    let new_voter = ctx.accounts.new_voter.key();
+
+    require!(election.status.allows_nik_rebind(), ErrorCode::NikRebindClosed);
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::RebindNik {
+            voter_nik_commitment: ctx.accounts.nik_record.voter_nik_commitment,
+            new_voter,
+        },
+    )?;
+
+    // Revoke the old credential; a spent vote cannot be moved
+    let old_credential = &mut ctx.accounts.old_voter_credential;
+    require!(!old_credential.has_voted, ErrorCode::AlreadyVoted);
+    old_credential.is_verified = false;
+
+    let new_credential = &mut ctx.accounts.new_voter_credential;
+    new_credential.election = election.key();
+    new_credential.voter_authority = new_voter;
+    new_credential.voter_nik_commitment = old_credential.voter_nik_commitment;
+    new_credential.biometric_hash = old_credential.biometric_hash;
+    new_credential.photo_ipfs_hash = old_credential.photo_ipfs_hash.clone();
+    new_credential.is_verified = true;
+    new_credential.has_voted = false;
+    new_credential.verification_timestamp = old_credential.verification_timestamp;
+    new_credential.vote_timestamp = None;
+    new_credential.ai_confidence_score = old_credential.ai_confidence_score;
+    new_credential.verification_code = old_credential.verification_code.clone();
+    // The identity stays in the membership tree; the voter keeps its secrets
+    new_credential.identity_commitment = old_credential.identity_commitment;
+    new_credential.region = old_credential.region;
+    new_credential.polling_station = old_credential.polling_station;
+    new_credential.contests_voted = old_credential.contests_voted;
+    new_credential.bump = ctx.bumps.new_voter_credential;
+
+    let nik_record = &mut ctx.accounts.nik_record;
+    nik_record.voter_credential = new_credential.key();
+    nik_record.voter_authority = new_voter;
+
+    // Mint the (frozen, non-transferable) voting token to the new wallet
+    issue_voting_token(
+        &ctx.accounts.election,
+        &ctx.accounts.voting_token_mint,
+        &ctx.accounts.new_voter_token_account,
+        &ctx.accounts.token_program,
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RebindNik<'info> {
+    #[account(
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    /// New wallet must sign to prove the citizen controls it
+    #[account(mut)]
+    pub new_voter: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"nik_record", election.key().as_ref(), &nik_record.voter_nik_commitment],
+        bump = nik_record.bump,
+        has_one = election
+    )]
+    pub nik_record: Account<'info, NikRecord>,
+
+    #[account(
+        mut,
+        address = nik_record.voter_credential,
+        has_one = election
+    )]
+    pub old_voter_credential: Account<'info, VoterCredential>,
+
+    #[account(
+        init,
+        payer = new_voter,
+        space = 8 + VoterCredential::INIT_SPACE,
+        seeds = [b"voter_credential", election.key().as_ref(), new_voter.key().as_ref()],
+        bump
+    )]
+    pub new_voter_credential: Account<'info, VoterCredential>,
+
+    #[account(
+        mut,
+        seeds = [b"voting_token_mint", election.key().as_ref()],
+        bump,
+    )]
+    pub voting_token_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = new_voter,
+        associated_token::mint = voting_token_mint,
+        associated_token::authority = new_voter
+    )]
+    pub new_voter_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
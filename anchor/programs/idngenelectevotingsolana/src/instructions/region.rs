@@ -0,0 +1,262 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount, Transfer};
+use crate::state::{Election, Proposal, ProposalAction, Region, RegionLevel, RegionTally, TimeZone};
+use crate::errors::ErrorCode;
+
+/// Create an electoral region (province down to village) under the election
+/// (requires an approved commissioner proposal naming the same code and name)
+/// Every region below province level must be created under its parent
+pub fn create_region(ctx: Context<CreateRegion>, region_code: u64, name: String) -> Result<()> {
+    let (level, parent_code) = validate_region(region_code, &name)?;
+
+    let election = &ctx.accounts.election;
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::CreateRegion {
+            region_code,
+            name: name.clone(),
+        },
+    )?;
+
+    let (parent, time_zone) = match (parent_code, &ctx.accounts.parent) {
+        (None, None) => (None, TimeZone::for_province(region_code)),
+        (Some(code), Some(parent)) => {
+            require!(
+                parent.election == election.key() && parent.region_code == code,
+                ErrorCode::InvalidRegionCode
+            );
+            (Some(parent.key()), parent.time_zone)
+        }
+        _ => return err!(ErrorCode::InvalidRegionCode),
+    };
+
+    let region = &mut ctx.accounts.region;
+    region.election = election.key();
+    region.region_code = region_code;
+    region.level = level;
+    region.parent = parent;
+    region.name = name;
+    region.time_zone = time_zone;
+    region.votes_cast = 0;
+    region.blank_votes = 0;
+    region.spoiled_votes = 0;
+    region.bump = ctx.bumps.region;
+
+    Ok(())
+}
+
+/// Shared by create_proposal and create_region
+pub(crate) fn validate_region(region_code: u64, name: &str) -> Result<(RegionLevel, Option<u64>)> {
+    require!(name.len() <= 100, ErrorCode::NameTooLong);
+    RegionLevel::from_code(region_code)
+}
+
+/// Load a region chain from remaining accounts: the voter's village first,
+/// then each parent in turn, ending at its province
+/// Accounts after the province are left to the caller (region tallies)
+pub(crate) fn load_region_chain<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    election: &Pubkey,
+    village: &Pubkey,
+) -> Result<Vec<Account<'info, Region>>> {
+    let mut chain: Vec<Account<'info, Region>> = Vec::with_capacity(accounts.len());
+    for info in accounts.iter() {
+        let expected = match chain.last() {
+            None => Some(*village),
+            Some(child) if child.parent.is_none() => break,
+            Some(child) => child.parent,
+        };
+        require!(
+            expected == Some(info.key()),
+            ErrorCode::InvalidRegionChain
+        );
+        let region = Account::<Region>::try_from(info)?;
+        require!(region.election == *election, ErrorCode::InvalidRegionChain);
+        chain.push(region);
+    }
+
+    let complete = chain.first().is_some_and(|region| region.level == RegionLevel::Village)
+        && chain.last().is_some_and(|region| region.parent.is_none());
+    require!(complete, ErrorCode::InvalidRegionChain);
+
+    Ok(chain)
+}
+
+/// Add a vote for each candidate in every region of the chain
+/// `accounts` are the RegionTally PDAs: for each candidate in turn, one per
+/// region in chain order. A tally not yet created is created here, paid by
+/// `payer`
+pub(crate) fn record_region_votes<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    regions: &[Account<'info, Region>],
+    candidate_ids: &[u32],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    require!(
+        accounts.len() == regions.len() * candidate_ids.len(),
+        ErrorCode::InvalidRegionTally
+    );
+
+    let mut accounts = accounts.iter();
+    for candidate_id in candidate_ids {
+        for region in regions {
+            let info = accounts.next().ok_or(ErrorCode::InvalidRegionTally)?;
+            let mut region_tally =
+                load_region_tally(info, region, *candidate_id, payer, system_program)?;
+            region_tally.vote_count = region_tally
+                .vote_count
+                .checked_add(1)
+                .ok_or(ErrorCode::Overflow)?;
+            region_tally.exit(&crate::ID)?;
+        }
+    }
+    Ok(())
+}
+
+/// Votes a region's tally holds for a candidate; zero while no ballot has
+/// created the tally, in which case its PDA is passed uncreated
+pub(crate) fn region_tally_votes<'info>(
+    info: &'info AccountInfo<'info>,
+    region: &Pubkey,
+    candidate_id: u32,
+) -> Result<u64> {
+    if info.owner == &crate::ID {
+        let region_tally = Account::<RegionTally>::try_from(info)?;
+        require!(
+            region_tally.region == *region && region_tally.candidate_id == candidate_id,
+            ErrorCode::InvalidRegionTally
+        );
+        return Ok(region_tally.vote_count);
+    }
+
+    let (address, _) = Pubkey::find_program_address(
+        &[b"region_tally", region.as_ref(), &candidate_id.to_le_bytes()],
+        &crate::ID,
+    );
+    require_keys_eq!(info.key(), address, ErrorCode::InvalidRegionTally);
+    Ok(0)
+}
+
+/// Load a region's tally for a candidate, creating it with the first vote
+fn load_region_tally<'info>(
+    info: &'info AccountInfo<'info>,
+    region: &Account<'info, Region>,
+    candidate_id: u32,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<Account<'info, RegionTally>> {
+    // Tallies only exist at their PDA, so a matching one is the right one
+    if info.owner == &crate::ID {
+        let region_tally = Account::<RegionTally>::try_from(info)?;
+        require!(
+            region_tally.region == region.key() && region_tally.candidate_id == candidate_id,
+            ErrorCode::InvalidRegionTally
+        );
+        return Ok(region_tally);
+    }
+
+    let region_key = region.key();
+    let candidate_bytes = candidate_id.to_le_bytes();
+    let (address, bump) = Pubkey::find_program_address(
+        &[b"region_tally", region_key.as_ref(), &candidate_bytes],
+        &crate::ID,
+    );
+    require_keys_eq!(info.key(), address, ErrorCode::InvalidRegionTally);
+
+    let seeds: &[&[u8]] = &[b"region_tally", region_key.as_ref(), &candidate_bytes, &[bump]];
+    create_pda_account(info, payer, system_program, 8 + RegionTally::INIT_SPACE, seeds)?;
+
+    let mut region_tally = Account::<RegionTally>::try_from_unchecked(info)?;
+    region_tally.election = region.election;
+    region_tally.region = region_key;
+    region_tally.candidate_id = candidate_id;
+    region_tally.vote_count = 0;
+    region_tally.bump = bump;
+    Ok(region_tally)
+}
+
+/// Create a program-owned PDA as `init` would, including when someone has
+/// already sent lamports to the address
+pub(crate) fn create_pda_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let signer = &[seeds];
+    let rent = Rent::get()?.minimum_balance(space);
+    let lamports = info.lamports();
+
+    if lamports == 0 {
+        let cpi_accounts = CreateAccount {
+            from: payer.clone(),
+            to: info.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer);
+        return system_program::create_account(cpi_ctx, rent, space as u64, &crate::ID);
+    }
+
+    let shortfall = rent.saturating_sub(lamports);
+    if shortfall > 0 {
+        let cpi_accounts = Transfer {
+            from: payer.clone(),
+            to: info.clone(),
+        };
+        system_program::transfer(CpiContext::new(system_program.clone(), cpi_accounts), shortfall)?;
+    }
+    let cpi_accounts = Allocate {
+        account_to_allocate: info.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer);
+    system_program::allocate(cpi_ctx, space as u64)?;
+    let cpi_accounts = Assign {
+        account_to_assign: info.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer);
+    system_program::assign(cpi_ctx, &crate::ID)
+}
+
+#[derive(Accounts)]
+#[instruction(region_code: u64)]
+pub struct CreateRegion<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + Region::INIT_SPACE,
+        seeds = [b"region", election.key().as_ref(), &region_code.to_le_bytes()],
+        bump
+    )]
+    pub region: Account<'info, Region>,
+
+    /// Enclosing region; omitted for provinces
+    pub parent: Option<Account<'info, Region>>,
+
+    pub system_program: Program<'info, System>,
+}
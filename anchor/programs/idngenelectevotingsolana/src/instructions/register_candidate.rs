@@ -1,22 +1,49 @@
 use anchor_lang::prelude::*;
-use crate::state::{Election, Candidate};
+use crate::state::{BallotNumberRecord, Election, Candidate, CandidateTicket, Proposal, ProposalAction};
 use crate::errors::ErrorCode;
 
-/// Register a candidate for the election (requires commissioner authority)
+/// Register a candidate ticket (pasangan calon) for the election
+/// (requires an approved commissioner proposal naming the same ticket)
 pub fn register_candidate(
     ctx: Context<RegisterCandidate>,
-    candidate_name: String,
+    ticket: CandidateTicket,
     candidate_id: u32,
 ) -> Result<()> {
-    require!(candidate_name.len() <= 100, ErrorCode::NameTooLong);
+    ticket.validate()?;
 
     let election = &ctx.accounts.election;
-    require!(!election.is_active, ErrorCode::ElectionAlreadyActive);
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::RegisterCandidate {
+            candidate_id,
+            ticket: ticket.clone(),
+        },
+    )?;
+
+    // One ballot number, one ticket
+    let ballot_number_record = &mut ctx.accounts.ballot_number_record;
+    require!(
+        ballot_number_record.candidate == Pubkey::default(),
+        ErrorCode::DuplicateBallotNumber
+    );
+    ballot_number_record.election = election.key();
+    ballot_number_record.ballot_number = ticket.ballot_number;
+    ballot_number_record.candidate = ctx.accounts.candidate.key();
+    ballot_number_record.bump = ctx.bumps.ballot_number_record;
 
     let candidate = &mut ctx.accounts.candidate;
-    candidate.election = ctx.accounts.election.key();
+    candidate.election = election.key();
     candidate.candidate_id = candidate_id;
-    candidate.candidate_name = candidate_name;
+    candidate.candidate_name = ticket.candidate_name;
+    candidate.running_mate_name = ticket.running_mate_name;
+    candidate.ballot_number = ticket.ballot_number;
+    candidate.coalition_party_ids = ticket.coalition_party_ids;
+    candidate.vision_mission_ipfs_hash = ticket.vision_mission_ipfs_hash;
     candidate.vote_count = 0;
     candidate.bump = ctx.bumps.candidate;
 
@@ -24,27 +51,46 @@ pub fn register_candidate(
 }
 
 #[derive(Accounts)]
-#[instruction(candidate_name: String, candidate_id: u32)]
+#[instruction(ticket: CandidateTicket, candidate_id: u32)]
 pub struct RegisterCandidate<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"election", election.election_name.as_bytes()],
-        bump = election.bump,
-        has_one = authority
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
     )]
     pub election: Account<'info, Election>,
 
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
     #[account(
         init,
-        payer = authority,
+        payer = commissioner,
         space = 8 + Candidate::INIT_SPACE,
         seeds = [b"candidate", election.key().as_ref(), &candidate_id.to_le_bytes()],
         bump
     )]
     pub candidate: Account<'info, Candidate>,
 
+    #[account(
+        init_if_needed,
+        payer = commissioner,
+        space = 8 + BallotNumberRecord::INIT_SPACE,
+        seeds = [b"ballot_number", election.key().as_ref(), &[ticket.ballot_number]],
+        bump
+    )]
+    pub ballot_number_record: Account<'info, BallotNumberRecord>,
+
     pub system_program: Program<'info, System>,
 }
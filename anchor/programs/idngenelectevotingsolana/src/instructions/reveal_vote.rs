@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use crate::state::{Ballot, BallotKind, Candidate, Election, ElectionStatus, Nullifier, VotingMode};
+use crate::errors::ErrorCode;
+use crate::instructions::region::{load_region_chain, record_region_votes};
+
+/// Open a commit-reveal ballot after polling closes and count it
+/// Anyone holding the preimage may submit it, so voters can reveal through
+/// a relayer just as they cast
+/// The commitment covers the election and the ballot's nullifier, so it
+/// cannot be copied onto another ballot or into another election
+/// The ballot's region chain (village first) is passed as remaining accounts,
+/// followed by the candidate's tally in each of those regions
+pub fn reveal_vote<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RevealVote<'info>>,
+    candidate_id: u32,
+    nullifier: [u8; 32],
+    salt: [u8; 32],
+) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+    let ballot = &mut ctx.accounts.ballot;
+
+    require!(
+        election.voting_mode == VotingMode::CommitReveal,
+        ErrorCode::VotingModeMismatch
+    );
+    require!(
+        election.status == ElectionStatus::Closed,
+        ErrorCode::RevealWindowClosed
+    );
+    require!(!ballot.is_revealed, ErrorCode::BallotAlreadyRevealed);
+    // Blank and spoiled ballots carry no commitment
+    require!(ballot.kind == BallotKind::Candidate, ErrorCode::InvalidReveal);
+
+    require!(
+        ctx.accounts.nullifier_record.ballot == ballot.key(),
+        ErrorCode::InvalidReveal
+    );
+
+    let commitment = solana_program::hash::hashv(&[
+        election.key().as_ref(),
+        &nullifier,
+        &candidate_id.to_le_bytes(),
+        &salt,
+    ]);
+    require!(
+        commitment.to_bytes() == ballot.encrypted_vote_data,
+        ErrorCode::InvalidReveal
+    );
+
+    if let Some(village) = ballot.region {
+        let regions = load_region_chain(ctx.remaining_accounts, &election.key(), &village)?;
+        record_region_votes(
+            &ctx.remaining_accounts[regions.len()..],
+            &regions,
+            &[candidate_id],
+            &ctx.accounts.revealer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+    }
+
+    let candidate = &mut ctx.accounts.candidate;
+    ballot.candidate = candidate.key();
+    ballot.is_revealed = true;
+
+    candidate.vote_count = candidate
+        .vote_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    election.total_votes_revealed = election
+        .total_votes_revealed
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(candidate_id: u32, nullifier: [u8; 32])]
+pub struct RevealVote<'info> {
+    /// Pays for any region tally the candidate did not have yet
+    #[account(mut)]
+    pub revealer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"ballot", election.key().as_ref(), &ballot.ballot_sequence.to_le_bytes()],
+        bump = ballot.bump,
+        has_one = election
+    )]
+    pub ballot: Account<'info, Ballot>,
+
+    /// Nullifier the ballot was cast with; already public, as it names the ballot
+    #[account(
+        seeds = [b"nullifier", election.key().as_ref(), nullifier.as_ref()],
+        bump = nullifier_record.bump,
+        has_one = election
+    )]
+    pub nullifier_record: Account<'info, Nullifier>,
+
+    #[account(
+        mut,
+        seeds = [b"candidate", election.key().as_ref(), &candidate_id.to_le_bytes()],
+        bump = candidate.bump,
+        has_one = election
+    )]
+    pub candidate: Account<'info, Candidate>,
+
+    pub system_program: Program<'info, System>,
+}
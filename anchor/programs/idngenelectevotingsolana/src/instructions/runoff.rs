@@ -0,0 +1,427 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::associated_token::AssociatedToken;
+use crate::state::{
+    BallotNumberRecord, Candidate, Election, ElectionOutcome, ElectionRegistry, ElectionResult,
+    ElectionStatus, NikRecord, PollingStation, Proposal, ProposalAction, VoterCredential,
+};
+use crate::errors::ErrorCode;
+use crate::instructions::initialize_election::validate_schedule;
+use crate::instructions::verify_voter::issue_voting_token;
+use crate::instructions::polling_station::register_at_station;
+
+/// Create the second-round election between the top two tickets of a
+/// first round whose result requires a runoff
+/// (requires an approved commissioner proposal on the first-round election)
+/// The runoff copies the commission, registrars, voting mode and NIK salt,
+/// so first-round voters can carry their credentials over
+pub fn create_runoff(
+    ctx: Context<CreateRunoff>,
+    election_name: String,
+    registration_start: i64,
+    registration_end: i64,
+    start_time: i64,
+    end_time: i64,
+) -> Result<()> {
+    require!(election_name.len() <= 100, ErrorCode::NameTooLong);
+    let parent = &ctx.accounts.election;
+    validate_schedule(
+        registration_start,
+        registration_end,
+        start_time,
+        end_time,
+        parent.voting_mode,
+    )?;
+    ctx.accounts.proposal.execute(
+        parent,
+        &ProposalAction::CreateRunoff {
+            election_name: election_name.clone(),
+            registration_start,
+            registration_end,
+            start_time,
+            end_time,
+        },
+    )?;
+
+    let result = &mut ctx.accounts.election_result;
+    let ElectionOutcome::RunoffRequired { first_candidate_id, second_candidate_id } = result.outcome
+    else {
+        return err!(ErrorCode::RunoffNotRequired);
+    };
+    require!(
+        result.runoff_election.is_none(),
+        ErrorCode::RunoffAlreadyCreated
+    );
+    require!(
+        ctx.accounts.first_candidate.candidate_id == first_candidate_id
+            && ctx.accounts.second_candidate.candidate_id == second_candidate_id,
+        ErrorCode::RunoffCandidateMismatch
+    );
+
+    let registry = &mut ctx.accounts.registry;
+    let runoff = &mut ctx.accounts.runoff_election;
+    runoff.election_id = registry.election_count;
+    registry.election_count = registry
+        .election_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    runoff.authority = ctx.accounts.commissioner.key();
+    runoff.election_name = election_name;
+    runoff.nik_salt = parent.nik_salt;
+    runoff.registration_start = registration_start;
+    runoff.registration_end = registration_end;
+    runoff.start_time = start_time;
+    runoff.end_time = end_time;
+    runoff.status = ElectionStatus::Draft;
+    runoff.voting_mode = parent.voting_mode;
+    runoff.total_registered_voters = 0;
+    runoff.total_votes_cast = 0;
+    runoff.total_votes_revealed = 0;
+    runoff.blank_votes = 0;
+    runoff.spoiled_votes = 0;
+    runoff.commissioners = parent.commissioners.clone();
+    runoff.required_signatures = parent.required_signatures;
+    runoff.registrars = parent.registrars.clone();
+    runoff.proposal_count = 0;
+    runoff.ballot_issuer = [0u8; 32];
+    runoff.ballot_issuer_authority = Pubkey::default();
+    runoff.ballot_tokens_issued = 0;
+    runoff.membership_root = [0u8; 32];
+    runoff.parent_election = Some(parent.key());
+    runoff.parliamentary_threshold_bps = parent.parliamentary_threshold_bps;
+    runoff.party_count = 0;
+    runoff.event = None;
+    runoff.max_selections = 1;
+    runoff.total_selections = 0;
+    runoff.multi_seat_tallied = false;
+    runoff.bump = ctx.bumps.runoff_election;
+    result.runoff_election = Some(runoff.key());
+
+    // Tickets keep their candidate IDs and ballot numbers in the second round
+    let runoff_key = runoff.key();
+    copy_ticket(
+        &ctx.accounts.first_candidate,
+        &mut ctx.accounts.runoff_first_candidate,
+        ctx.bumps.runoff_first_candidate,
+        &mut ctx.accounts.first_ballot_number_record,
+        ctx.bumps.first_ballot_number_record,
+        runoff_key,
+    );
+    copy_ticket(
+        &ctx.accounts.second_candidate,
+        &mut ctx.accounts.runoff_second_candidate,
+        ctx.bumps.runoff_second_candidate,
+        &mut ctx.accounts.second_ballot_number_record,
+        ctx.bumps.second_ballot_number_record,
+        runoff_key,
+    );
+
+    Ok(())
+}
+
+/// Carry a verified first-round credential over to the runoff, without
+/// repeating biometric verification
+/// The voter's village and polling station must first be created in the runoff
+pub fn carry_over_voter(ctx: Context<CarryOverVoter>) -> Result<()> {
+    let election = &ctx.accounts.election;
+    let clock = Clock::get()?;
+
+    require!(
+        election.status.allows_voter_registration(),
+        ErrorCode::RegistrationClosed
+    );
+    require!(
+        clock.unix_timestamp >= election.registration_start,
+        ErrorCode::RegistrationNotOpen
+    );
+    require!(
+        clock.unix_timestamp <= election.registration_end,
+        ErrorCode::RegistrationClosed
+    );
+
+    // Revoked (rebound) first-round credentials cannot be carried over
+    let parent_credential = &ctx.accounts.parent_voter_credential;
+    require!(parent_credential.is_verified, ErrorCode::VoterNotVerified);
+
+    let nik_record = &mut ctx.accounts.nik_record;
+    require!(
+        nik_record.voter_credential == Pubkey::default(),
+        ErrorCode::NikAlreadyRegistered
+    );
+    nik_record.election = election.key();
+    nik_record.voter_nik_commitment = parent_credential.voter_nik_commitment;
+    nik_record.voter_credential = ctx.accounts.voter_credential.key();
+    nik_record.voter_authority = ctx.accounts.voter.key();
+    nik_record.bump = ctx.bumps.nik_record;
+
+    let voter_credential = &mut ctx.accounts.voter_credential;
+    voter_credential.election = election.key();
+    voter_credential.voter_authority = ctx.accounts.voter.key();
+    voter_credential.voter_nik_commitment = parent_credential.voter_nik_commitment;
+    voter_credential.biometric_hash = parent_credential.biometric_hash;
+    voter_credential.photo_ipfs_hash = parent_credential.photo_ipfs_hash.clone();
+    voter_credential.is_verified = true;
+    voter_credential.has_voted = false;
+    voter_credential.verification_timestamp = parent_credential.verification_timestamp;
+    voter_credential.vote_timestamp = None;
+    voter_credential.ai_confidence_score = parent_credential.ai_confidence_score;
+    voter_credential.verification_code = parent_credential.verification_code.clone();
+    // The runoff has its own membership tree; identities are registered anew
+    voter_credential.identity_commitment = None;
+    voter_credential.region = ctx.accounts.polling_station.region;
+    voter_credential.polling_station = ctx.accounts.polling_station.key();
+    voter_credential.contests_voted = 0;
+    voter_credential.bump = ctx.bumps.voter_credential;
+
+    register_at_station(&mut ctx.accounts.polling_station)?;
+
+    issue_voting_token(
+        &ctx.accounts.election,
+        &ctx.accounts.voting_token_mint,
+        &ctx.accounts.voter_token_account,
+        &ctx.accounts.token_program,
+    )?;
+
+    let election_mut = &mut ctx.accounts.election;
+    election_mut.total_registered_voters = election_mut
+        .total_registered_voters
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+fn copy_ticket(
+    source: &Candidate,
+    candidate: &mut Account<Candidate>,
+    candidate_bump: u8,
+    ballot_number_record: &mut BallotNumberRecord,
+    record_bump: u8,
+    runoff: Pubkey,
+) {
+    candidate.election = runoff;
+    candidate.candidate_id = source.candidate_id;
+    candidate.candidate_name = source.candidate_name.clone();
+    candidate.running_mate_name = source.running_mate_name.clone();
+    candidate.ballot_number = source.ballot_number;
+    candidate.coalition_party_ids = source.coalition_party_ids.clone();
+    candidate.vision_mission_ipfs_hash = source.vision_mission_ipfs_hash.clone();
+    candidate.vote_count = 0;
+    candidate.bump = candidate_bump;
+
+    ballot_number_record.election = runoff;
+    ballot_number_record.ballot_number = source.ballot_number;
+    ballot_number_record.candidate = candidate.key();
+    ballot_number_record.bump = record_bump;
+}
+
+#[derive(Accounts)]
+pub struct CreateRunoff<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election_registry"],
+        bump = registry.bump
+    )]
+    pub registry: Box<Account<'info, ElectionRegistry>>,
+
+    /// First-round election
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Box<Account<'info, Election>>,
+
+    #[account(
+        mut,
+        seeds = [b"election_result", election.key().as_ref()],
+        bump = election_result.bump,
+        has_one = election
+    )]
+    pub election_result: Box<Account<'info, ElectionResult>>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + Election::INIT_SPACE,
+        seeds = [b"election", &registry.election_count.to_le_bytes()],
+        bump
+    )]
+    pub runoff_election: Box<Account<'info, Election>>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        seeds = [b"voting_token_mint", runoff_election.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = runoff_election,
+        mint::freeze_authority = runoff_election,
+    )]
+    pub runoff_voting_token_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        seeds = [b"candidate", election.key().as_ref(), &first_candidate.candidate_id.to_le_bytes()],
+        bump = first_candidate.bump,
+        has_one = election
+    )]
+    pub first_candidate: Box<Account<'info, Candidate>>,
+
+    #[account(
+        seeds = [b"candidate", election.key().as_ref(), &second_candidate.candidate_id.to_le_bytes()],
+        bump = second_candidate.bump,
+        has_one = election
+    )]
+    pub second_candidate: Box<Account<'info, Candidate>>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + Candidate::INIT_SPACE,
+        seeds = [
+            b"candidate",
+            runoff_election.key().as_ref(),
+            &first_candidate.candidate_id.to_le_bytes()
+        ],
+        bump
+    )]
+    pub runoff_first_candidate: Box<Account<'info, Candidate>>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + Candidate::INIT_SPACE,
+        seeds = [
+            b"candidate",
+            runoff_election.key().as_ref(),
+            &second_candidate.candidate_id.to_le_bytes()
+        ],
+        bump
+    )]
+    pub runoff_second_candidate: Box<Account<'info, Candidate>>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + BallotNumberRecord::INIT_SPACE,
+        seeds = [
+            b"ballot_number",
+            runoff_election.key().as_ref(),
+            &[first_candidate.ballot_number]
+        ],
+        bump
+    )]
+    pub first_ballot_number_record: Box<Account<'info, BallotNumberRecord>>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + BallotNumberRecord::INIT_SPACE,
+        seeds = [
+            b"ballot_number",
+            runoff_election.key().as_ref(),
+            &[second_candidate.ballot_number]
+        ],
+        bump
+    )]
+    pub second_ballot_number_record: Box<Account<'info, BallotNumberRecord>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CarryOverVoter<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    /// Runoff election
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump,
+        constraint = election.parent_election == Some(parent_election.key()) @ ErrorCode::NotARunoff
+    )]
+    pub election: Box<Account<'info, Election>>,
+
+    pub parent_election: Box<Account<'info, Election>>,
+
+    #[account(
+        seeds = [b"voter_credential", parent_election.key().as_ref(), voter.key().as_ref()],
+        bump = parent_voter_credential.bump
+    )]
+    pub parent_voter_credential: Box<Account<'info, VoterCredential>>,
+
+    #[account(address = parent_voter_credential.polling_station)]
+    pub parent_polling_station: Box<Account<'info, PollingStation>>,
+
+    /// The runoff's copy of the voter's polling station
+    #[account(
+        mut,
+        seeds = [
+            b"polling_station",
+            election.key().as_ref(),
+            &parent_polling_station.region_code.to_le_bytes(),
+            &parent_polling_station.tps_number.to_le_bytes()
+        ],
+        bump = polling_station.bump,
+        has_one = election
+    )]
+    pub polling_station: Box<Account<'info, PollingStation>>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + VoterCredential::INIT_SPACE,
+        seeds = [b"voter_credential", election.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub voter_credential: Box<Account<'info, VoterCredential>>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = 8 + NikRecord::INIT_SPACE,
+        seeds = [
+            b"nik_record",
+            election.key().as_ref(),
+            &parent_voter_credential.voter_nik_commitment
+        ],
+        bump
+    )]
+    pub nik_record: Box<Account<'info, NikRecord>>,
+
+    #[account(
+        mut,
+        seeds = [b"voting_token_mint", election.key().as_ref()],
+        bump,
+    )]
+    pub voting_token_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        associated_token::mint = voting_token_mint,
+        associated_token::authority = voter
+    )]
+    pub voter_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
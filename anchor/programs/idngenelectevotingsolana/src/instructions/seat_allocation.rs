@@ -0,0 +1,231 @@
+use anchor_lang::prelude::*;
+use crate::state::{
+    Election, ElectoralDistrict, Party, PartyListCandidate, PartySeats, SeatAllocation,
+    VotingMode,
+};
+use crate::errors::ErrorCode;
+
+/// Divide a dapil's seats among parties by Sainte-Laguë
+/// Every registered party is passed as remaining accounts in party order:
+/// the national total for the threshold needs them all
+pub fn allocate_seats<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AllocateSeats<'info>>,
+    district_number: u16,
+) -> Result<()> {
+    let election = &ctx.accounts.election;
+    require!(
+        election.voting_mode == VotingMode::Legislative,
+        ErrorCode::VotingModeMismatch
+    );
+    require!(
+        election.status.allows_result_computation(),
+        ErrorCode::ResultNotReady
+    );
+
+    let parties = load_parties(ctx.remaining_accounts, election)?;
+    let national_votes = parties
+        .iter()
+        .try_fold(0u64, |total, party| total.checked_add(party.vote_count))
+        .ok_or(ErrorCode::Overflow)?;
+
+    // Only parties over the threshold, with votes in this dapil, take part
+    let threshold_bps = election.parliamentary_threshold_bps;
+    let contenders: Vec<(u16, u64)> = parties
+        .iter()
+        .filter(|party| {
+            u128::from(party.vote_count) * 10_000
+                >= u128::from(threshold_bps) * u128::from(national_votes)
+        })
+        .map(|party| (party.party_id, party.district_votes(district_number)))
+        .filter(|(_, vote_count)| *vote_count > 0)
+        .collect();
+
+    let seats = ctx.accounts.electoral_district.seats;
+    let votes: Vec<u64> = contenders.iter().map(|(_, vote_count)| *vote_count).collect();
+    let won = sainte_lague(&votes, seats);
+
+    let seat_allocation = &mut ctx.accounts.seat_allocation;
+    seat_allocation.election = election.key();
+    seat_allocation.district_number = district_number;
+    seat_allocation.seats = seats;
+    seat_allocation.threshold_bps = threshold_bps;
+    seat_allocation.party_seats = contenders
+        .iter()
+        .zip(won)
+        .filter(|(_, seats)| *seats > 0)
+        .map(|((party_id, vote_count), seats)| PartySeats {
+            party_id: *party_id,
+            vote_count: *vote_count,
+            seats,
+        })
+        .collect();
+    seat_allocation.candidates_counted = 0;
+    seat_allocation.last_party_id = 0;
+    seat_allocation.last_list_number = 0;
+    seat_allocation.elected = Vec::new();
+    seat_allocation.complete = ctx.accounts.electoral_district.list_candidate_count == 0;
+    seat_allocation.blank_votes = ctx.accounts.electoral_district.blank_votes;
+    seat_allocation.spoiled_votes = ctx.accounts.electoral_district.spoiled_votes;
+    seat_allocation.bump = ctx.bumps.seat_allocation;
+
+    Ok(())
+}
+
+/// Fill each party's seats with its list candidates by individual votes
+/// The dapil's candidates are passed as remaining accounts in (party, list
+/// number) order, over several transactions if needed; each counts once
+pub fn assign_seats<'info>(ctx: Context<'_, '_, 'info, 'info, AssignSeats<'info>>) -> Result<()> {
+    let election_key = ctx.accounts.election.key();
+    let list_candidate_count = ctx.accounts.electoral_district.list_candidate_count;
+    let seat_allocation = &mut ctx.accounts.seat_allocation;
+    require!(!seat_allocation.complete, ErrorCode::SeatsAlreadyAssigned);
+
+    for info in ctx.remaining_accounts.iter() {
+        let candidate = Account::<PartyListCandidate>::try_from(info)?;
+        let position = (candidate.party_id, candidate.list_number);
+        require!(
+            candidate.election == election_key
+                && candidate.district_number == seat_allocation.district_number
+                && (seat_allocation.candidates_counted == 0
+                    || position
+                        > (seat_allocation.last_party_id, seat_allocation.last_list_number)),
+            ErrorCode::ListCandidateOrder
+        );
+
+        seat_allocation.consider(&candidate);
+        seat_allocation.candidates_counted = seat_allocation
+            .candidates_counted
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        (seat_allocation.last_party_id, seat_allocation.last_list_number) = position;
+    }
+
+    seat_allocation.complete = seat_allocation.candidates_counted == list_candidate_count;
+
+    Ok(())
+}
+
+/// Shared by create_proposal and set_parliamentary_threshold
+pub(crate) fn validate_parliamentary_threshold(threshold_bps: u16) -> Result<()> {
+    require!(threshold_bps <= 10_000, ErrorCode::InvalidParliamentaryThreshold);
+    Ok(())
+}
+
+/// Load every party of the election, in ascending party order
+fn load_parties<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    election: &Account<'info, Election>,
+) -> Result<Vec<Account<'info, Party>>> {
+    require!(
+        accounts.len() == usize::from(election.party_count),
+        ErrorCode::PartiesIncomplete
+    );
+
+    let mut parties: Vec<Account<'info, Party>> = Vec::with_capacity(accounts.len());
+    for info in accounts.iter() {
+        let party = Account::<Party>::try_from(info)?;
+        require!(
+            party.election == election.key()
+                && parties.last().is_none_or(|last| party.party_id > last.party_id),
+            ErrorCode::PartiesIncomplete
+        );
+        parties.push(party);
+    }
+    Ok(parties)
+}
+
+/// Sainte-Laguë: each seat goes to the highest quotient votes / (2s + 1),
+/// s being the seats already won. Equal quotients go to the party with more
+/// votes, then to the earlier party
+fn sainte_lague(votes: &[u64], seats: u8) -> Vec<u8> {
+    let divisor = |won: u8| u128::from(won) * 2 + 1;
+    let mut won = vec![0u8; votes.len()];
+
+    for _ in 0..seats {
+        let mut best: Option<usize> = None;
+        for (k, vote_count) in votes.iter().enumerate() {
+            if *vote_count == 0 {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some(b) => {
+                    let quotient = u128::from(*vote_count) * divisor(won[b]);
+                    let best_quotient = u128::from(votes[b]) * divisor(won[k]);
+                    quotient > best_quotient
+                        || (quotient == best_quotient && *vote_count > votes[b])
+                }
+            };
+            if better {
+                best = Some(k);
+            }
+        }
+        match best {
+            Some(k) => won[k] += 1,
+            None => break,
+        }
+    }
+
+    won
+}
+
+#[derive(Accounts)]
+#[instruction(district_number: u16)]
+pub struct AllocateSeats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        seeds = [b"electoral_district", election.key().as_ref(), &district_number.to_le_bytes()],
+        bump = electoral_district.bump
+    )]
+    pub electoral_district: Account<'info, ElectoralDistrict>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SeatAllocation::INIT_SPACE,
+        seeds = [b"seat_allocation", election.key().as_ref(), &district_number.to_le_bytes()],
+        bump
+    )]
+    pub seat_allocation: Box<Account<'info, SeatAllocation>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AssignSeats<'info> {
+    #[account(
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        seeds = [
+            b"electoral_district",
+            election.key().as_ref(),
+            &seat_allocation.district_number.to_le_bytes()
+        ],
+        bump = electoral_district.bump
+    )]
+    pub electoral_district: Account<'info, ElectoralDistrict>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"seat_allocation",
+            election.key().as_ref(),
+            &seat_allocation.district_number.to_le_bytes()
+        ],
+        bump = seat_allocation.bump,
+        has_one = election
+    )]
+    pub seat_allocation: Box<Account<'info, SeatAllocation>>,
+}
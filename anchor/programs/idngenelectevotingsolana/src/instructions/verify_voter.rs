@@ -1,21 +1,25 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, MintTo};
+use anchor_spl::token::{self, Token, TokenAccount, Mint, MintTo, FreezeAccount};
 use anchor_spl::associated_token::AssociatedToken;
-use crate::state::{Election, VoterCredential};
+use crate::state::{Election, NikRecord, PollingStation, VoterCredential};
 use crate::errors::ErrorCode;
-use crate::utils::generate_verification_code;
+use crate::instructions::polling_station::register_at_station;
+use crate::utils::{generate_verification_code, registrar_attestation_message, verify_ed25519_attestation};
 
 /// Verify voter biometrics and mint Voter Credential NFT
+/// The result must be attested by a registrar, either by co-signing or with an
+/// ed25519 signature-verify instruction immediately before this one
+/// The raw NIK never goes on-chain: the registrar computes the keyed commitment
+/// HMAC-SHA256(secret, nik_salt || NIK) off-chain and attests to it
 /// Stores cryptographic hashes on-chain, actual biometric data off-chain (IPFS)
 pub fn verify_voter(
     ctx: Context<VerifyVoter>,
-    voter_nik: String,
+    voter_nik_commitment: [u8; 32],
     biometric_hash: [u8; 32],
     photo_ipfs_hash: String,
     verification_timestamp: i64,
     ai_confidence_score: u8,
 ) -> Result<()> {
-    require!(voter_nik.len() == 16, ErrorCode::InvalidNIK);
     require!(photo_ipfs_hash.len() <= 100, ErrorCode::InvalidIPFSHash);
     require!(
         ai_confidence_score <= 100,
@@ -25,16 +29,64 @@ pub fn verify_voter(
     let election = &ctx.accounts.election;
     let clock = Clock::get()?;
 
-    // Check election hasn't started yet (registration phase)
+    // Check registration phase is open and within the registration window
     require!(
-        clock.unix_timestamp < election.start_time,
+        election.status.allows_voter_registration(),
         ErrorCode::RegistrationClosed
     );
+    require!(
+        clock.unix_timestamp >= election.registration_start,
+        ErrorCode::RegistrationNotOpen
+    );
+    require!(
+        clock.unix_timestamp <= election.registration_end,
+        ErrorCode::RegistrationClosed
+    );
+
+    // Registrar must attest to the NIK commitment, biometric hash, AI score
+    // and the polling station the voter is registered at. A co-signing
+    // registrar signs the transaction naming this station; an ed25519
+    // attestation names it in the signed message
+    let polling_station = ctx.accounts.polling_station.key();
+    if let Some(registrar) = &ctx.accounts.registrar {
+        require!(
+            election.is_registrar(&registrar.key()),
+            ErrorCode::RegistrarNotAuthorized
+        );
+    } else {
+        let instructions_sysvar = ctx
+            .accounts
+            .instructions_sysvar
+            .as_ref()
+            .ok_or(ErrorCode::MissingRegistrarAttestation)?;
+        let message = registrar_attestation_message(
+            &election.key(),
+            ctx.accounts.voter.key,
+            &polling_station,
+            &voter_nik_commitment,
+            &biometric_hash,
+            ai_confidence_score,
+            verification_timestamp,
+        );
+        verify_ed25519_attestation(instructions_sysvar, &election.registrars, &message)?;
+    }
+
+    // One NIK, one credential: a second wallet with the same NIK is rejected
+    let nik_record = &mut ctx.accounts.nik_record;
+    require!(
+        nik_record.voter_credential == Pubkey::default(),
+        ErrorCode::NikAlreadyRegistered
+    );
+    nik_record.election = election.key();
+    nik_record.voter_nik_commitment = voter_nik_commitment;
+    nik_record.voter_credential = ctx.accounts.voter_credential.key();
+    nik_record.voter_authority = ctx.accounts.voter.key();
+    nik_record.bump = ctx.bumps.nik_record;
 
     let voter_credential = &mut ctx.accounts.voter_credential;
     voter_credential.election = ctx.accounts.election.key();
     voter_credential.voter_authority = ctx.accounts.voter.key();
-    voter_credential.voter_nik_hash = solana_program::hash::hash(voter_nik.as_bytes()).to_bytes();
+    voter_credential.voter_nik_commitment = voter_nik_commitment;
     voter_credential.biometric_hash = biometric_hash;
     voter_credential.photo_ipfs_hash = photo_ipfs_hash;
     voter_credential.is_verified = true;
@@ -43,26 +95,24 @@ pub fn verify_voter(
     voter_credential.ai_confidence_score = ai_confidence_score;
     voter_credential.verification_code = generate_verification_code(
         *ctx.accounts.voter.key,
-        &voter_nik,
+        &voter_nik_commitment,
         verification_timestamp,
     );
+    voter_credential.identity_commitment = None;
+    voter_credential.region = ctx.accounts.polling_station.region;
+    voter_credential.polling_station = polling_station;
+    voter_credential.contests_voted = 0;
     voter_credential.bump = ctx.bumps.voter_credential;
 
+    register_at_station(&mut ctx.accounts.polling_station)?;
+
     // Mint non-transferable voting token (1 token = 1 vote right)
-    let cpi_accounts = MintTo {
-        mint: ctx.accounts.voting_token_mint.to_account_info(),
-        to: ctx.accounts.voter_token_account.to_account_info(),
-        authority: ctx.accounts.election.to_account_info(),
-    };
-    let election_seeds = &[
-        b"election",
-        election.election_name.as_bytes(),
-        &[election.bump],
-    ];
-    let signer = &[&election_seeds[..]];
-    let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-    token::mint_to(cpi_ctx, 1)?;
+    issue_voting_token(
+        &ctx.accounts.election,
+        &ctx.accounts.voting_token_mint,
+        &ctx.accounts.voter_token_account,
+        &ctx.accounts.token_program,
+    )?;
 
     // Increment registered voters count
     let election_mut = &mut ctx.accounts.election;
@@ -74,14 +124,45 @@ pub fn verify_voter(
     Ok(())
 }
 
+/// Mint one voting token to the voter and freeze it, so the voting right
+/// cannot be transferred; redeem_voting_token thaws and burns it when the
+/// voter spends it, in request_ballot_token, cast_encrypted_vote or
+/// request_contest_ballot_token
+pub(crate) fn issue_voting_token<'info>(
+    election: &Account<'info, Election>,
+    voting_token_mint: &Account<'info, Mint>,
+    voter_token_account: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let cpi_accounts = MintTo {
+        mint: voting_token_mint.to_account_info(),
+        to: voter_token_account.to_account_info(),
+        authority: election.to_account_info(),
+    };
+    let election_id = election.election_id.to_le_bytes();
+    let election_seeds = &[b"election".as_ref(), &election_id, &[election.bump]];
+    let signer = &[&election_seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token::mint_to(cpi_ctx, 1)?;
+
+    let cpi_accounts = FreezeAccount {
+        account: voter_token_account.to_account_info(),
+        mint: voting_token_mint.to_account_info(),
+        authority: election.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token::freeze_account(cpi_ctx)
+}
+
 #[derive(Accounts)]
+#[instruction(voter_nik_commitment: [u8; 32])]
 pub struct VerifyVoter<'info> {
     #[account(mut)]
     pub voter: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"election", election.election_name.as_bytes()],
+        seeds = [b"election", &election.election_id.to_le_bytes()],
         bump = election.bump
     )]
     pub election: Account<'info, Election>,
@@ -95,6 +176,26 @@ pub struct VerifyVoter<'info> {
     )]
     pub voter_credential: Account<'info, VoterCredential>,
 
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = 8 + NikRecord::INIT_SPACE,
+        seeds = [
+            b"nik_record",
+            election.key().as_ref(),
+            &voter_nik_commitment
+        ],
+        bump
+    )]
+    pub nik_record: Account<'info, NikRecord>,
+
+    /// Polling station (TPS) of the voter's village (from the KTP address)
+    #[account(
+        mut,
+        constraint = polling_station.election == election.key() @ ErrorCode::InvalidPollingStation
+    )]
+    pub polling_station: Account<'info, PollingStation>,
+
     #[account(
         mut,
         seeds = [b"voting_token_mint", election.key().as_ref()],
@@ -110,6 +211,13 @@ pub struct VerifyVoter<'info> {
     )]
     pub voter_token_account: Account<'info, TokenAccount>,
 
+    /// Co-signing registrar (omit when attesting via ed25519 instruction)
+    pub registrar: Option<Signer<'info>>,
+
+    /// CHECK: address constrained to the instructions sysvar
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
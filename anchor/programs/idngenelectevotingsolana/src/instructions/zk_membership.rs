@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+use crate::membership::{empty_tree, insert_leaf, Groth16VerifyingKey};
+use crate::state::{
+    Election, MembershipTree, Proposal, ProposalAction, VoterCredential, VotingMode,
+    ZkVerifyingKey,
+};
+use crate::errors::ErrorCode;
+
+/// Store the membership-proof verifying key and create the empty identity tree
+/// (requires an approved commissioner proposal naming the key hash)
+pub fn configure_membership_proofs(
+    ctx: Context<ConfigureMembershipProofs>,
+    verifying_key: Groth16VerifyingKey,
+) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+
+    require!(
+        election.voting_mode == VotingMode::ZkMembership,
+        ErrorCode::VotingModeMismatch
+    );
+    require!(
+        election.status.allows_candidate_registration(),
+        ErrorCode::ElectionAlreadyActive
+    );
+
+    ctx.accounts.proposal.execute(
+        election,
+        &ProposalAction::ConfigureMembershipProofs {
+            verifying_key_hash: verifying_key.hash(),
+        },
+    )?;
+
+    let zk_verifying_key = &mut ctx.accounts.zk_verifying_key;
+    zk_verifying_key.election = election.key();
+    zk_verifying_key.verifying_key = verifying_key;
+    zk_verifying_key.bump = ctx.bumps.zk_verifying_key;
+
+    let (filled_subtrees, root) = empty_tree()?;
+    let tree = &mut ctx.accounts.membership_tree;
+    tree.election = election.key();
+    tree.next_index = 0;
+    tree.filled_subtrees = filled_subtrees;
+    tree.bump = ctx.bumps.membership_tree;
+
+    election.membership_root = root;
+
+    Ok(())
+}
+
+/// Add a verified voter's Semaphore identity commitment to the membership tree
+/// One identity per credential; the tree is frozen once registration closes
+pub fn register_identity(
+    ctx: Context<RegisterIdentity>,
+    identity_commitment: [u8; 32],
+) -> Result<()> {
+    let election = &mut ctx.accounts.election;
+    let clock = Clock::get()?;
+
+    require!(
+        election.voting_mode == VotingMode::ZkMembership,
+        ErrorCode::VotingModeMismatch
+    );
+    require!(
+        election.status.allows_voter_registration()
+            && clock.unix_timestamp <= election.registration_end,
+        ErrorCode::RegistrationClosed
+    );
+
+    let voter_credential = &mut ctx.accounts.voter_credential;
+    require!(voter_credential.is_verified, ErrorCode::VoterNotVerified);
+    require!(
+        voter_credential.identity_commitment.is_none(),
+        ErrorCode::IdentityAlreadyRegistered
+    );
+
+    let tree = &mut ctx.accounts.membership_tree;
+    let index = tree.next_index;
+    election.membership_root = insert_leaf(&mut tree.filled_subtrees, index, &identity_commitment)?;
+    tree.next_index = index.checked_add(1).ok_or(ErrorCode::Overflow)?;
+
+    voter_credential.identity_commitment = Some(identity_commitment);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ConfigureMembershipProofs<'info> {
+    #[account(
+        mut,
+        constraint = election.is_commissioner(&commissioner.key()) @ ErrorCode::NotCommissioner
+    )]
+    pub commissioner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", election.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = election
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + ZkVerifyingKey::INIT_SPACE,
+        seeds = [b"zk_verifying_key", election.key().as_ref()],
+        bump
+    )]
+    pub zk_verifying_key: Account<'info, ZkVerifyingKey>,
+
+    #[account(
+        init,
+        payer = commissioner,
+        space = 8 + MembershipTree::INIT_SPACE,
+        seeds = [b"membership_tree", election.key().as_ref()],
+        bump
+    )]
+    pub membership_tree: Account<'info, MembershipTree>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterIdentity<'info> {
+    pub voter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"election", &election.election_id.to_le_bytes()],
+        bump = election.bump
+    )]
+    pub election: Account<'info, Election>,
+
+    #[account(
+        mut,
+        seeds = [b"voter_credential", election.key().as_ref(), voter.key().as_ref()],
+        bump = voter_credential.bump,
+        has_one = election
+    )]
+    pub voter_credential: Account<'info, VoterCredential>,
+
+    #[account(
+        mut,
+        seeds = [b"membership_tree", election.key().as_ref()],
+        bump = membership_tree.bump,
+        has_one = election
+    )]
+    pub membership_tree: Account<'info, MembershipTree>,
+}
@@ -1,4 +1,4 @@
-#![allow(clippy::result_large_err)]
+#![allow(clippy::result_large_err, clippy::too_many_arguments)]
 
 use anchor_lang::prelude::*;
 
@@ -9,9 +9,15 @@ pub mod state;
 pub mod instructions;
 pub mod errors;
 pub mod utils;
+pub mod elgamal;
+pub mod blind_signature;
+pub mod membership;
 
 use instructions::*;
-use state::{AuditData, ReceiptVerification};
+use elgamal::{EncryptedBallot, EqualityProof};
+use membership::Groth16VerifyingKey;
+use blind_signature::BlindSignature;
+use state::{AuditData, BallotKind, CandidateTicket, ElectionStatus, ProposalAction, ReceiptVerification, TallySummary, VotingMode};
 
 #[program]
 pub mod idngenelectevotingsolana {
@@ -39,6 +45,7 @@ pub mod idngenelectevotingsolana {
         Ok(())
     }
 
+    #[allow(clippy::clone_on_copy)]
     pub fn set(ctx: Context<Update>, value: u8) -> Result<()> {
         ctx.accounts.idngenelectevotingsolana.count = value.clone();
         Ok(())
@@ -48,39 +55,61 @@ pub mod idngenelectevotingsolana {
     // E-VOTING FUNCTIONALITY
     // ========================================================================
 
+    /// Create the global election registry that assigns election IDs
+    pub fn initialize_registry(ctx: Context<InitializeRegistry>) -> Result<()> {
+        instructions::initialize_election::initialize_registry(ctx)
+    }
+
     /// Initialize a new election with multi-sig commissioners
     pub fn initialize_election(
         ctx: Context<InitializeElection>,
         election_name: String,
+        registration_start: i64,
+        registration_end: i64,
         start_time: i64,
         end_time: i64,
         commissioners: Vec<Pubkey>,
         required_signatures: u8,
+        voting_mode: VotingMode,
     ) -> Result<()> {
         instructions::initialize_election::initialize_election(
             ctx,
             election_name,
+            registration_start,
+            registration_end,
             start_time,
             end_time,
             commissioners,
             required_signatures,
+            voting_mode,
         )
     }
 
-    /// Register a candidate for the election (requires commissioner authority)
+    /// Open a commissioner proposal for an election action
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: ProposalAction) -> Result<()> {
+        instructions::proposal::create_proposal(ctx, action)
+    }
+
+    /// Approve a commissioner proposal (rejects duplicates and non-commissioners)
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        instructions::proposal::approve_proposal(ctx)
+    }
+
+    /// Register a candidate ticket for the election (requires approved proposal)
     pub fn register_candidate(
         ctx: Context<RegisterCandidate>,
-        candidate_name: String,
+        ticket: CandidateTicket,
         candidate_id: u32,
     ) -> Result<()> {
-        instructions::register_candidate::register_candidate(ctx, candidate_name, candidate_id)
+        instructions::register_candidate::register_candidate(ctx, ticket, candidate_id)
     }
 
     /// Verify voter biometrics and mint Voter Credential NFT
+    /// Requires a registrar attestation (co-signature or ed25519 instruction)
     /// Stores cryptographic hashes on-chain, actual biometric data off-chain
     pub fn verify_voter(
         ctx: Context<VerifyVoter>,
-        voter_nik: String,
+        voter_nik_commitment: [u8; 32],
         biometric_hash: [u8; 32],
         photo_ipfs_hash: String,
         verification_timestamp: i64,
@@ -88,7 +117,7 @@ pub mod idngenelectevotingsolana {
     ) -> Result<()> {
         instructions::verify_voter::verify_voter(
             ctx,
-            voter_nik,
+            voter_nik_commitment,
             biometric_hash,
             photo_ipfs_hash,
             verification_timestamp,
@@ -96,23 +125,347 @@ pub mod idngenelectevotingsolana {
         )
     }
 
+    /// Rebind a NIK to a new wallet, revoking the old credential (requires approved proposal)
+    pub fn rebind_nik(ctx: Context<RebindNik>) -> Result<()> {
+        instructions::rebind_nik::rebind_nik(ctx)
+    }
+
+    /// Spend the voting token and request a blind-signed ballot token
+    pub fn request_ballot_token(
+        ctx: Context<RequestBallotToken>,
+        issuer_commitments: [[u8; 32]; 2],
+        blinded_challenges: [[u8; 32]; 2],
+    ) -> Result<()> {
+        instructions::ballot_token::request_ballot_token(ctx, issuer_commitments, blinded_challenges)
+    }
+
+    /// Post the issuer's verified blind signature response for the clause it chose (issuer only)
+    pub fn issue_ballot_token(
+        ctx: Context<IssueBallotToken>,
+        clause: u8,
+        blind_response: [u8; 32],
+    ) -> Result<()> {
+        instructions::ballot_token::issue_ballot_token(ctx, clause, blind_response)
+    }
+
+    /// Spend one contest of an election event and request its blind-signed ballot token
+    pub fn request_contest_ballot_token(
+        ctx: Context<RequestContestBallotToken>,
+        issuer_commitments: [[u8; 32]; 2],
+        blinded_challenges: [[u8; 32]; 2],
+    ) -> Result<()> {
+        instructions::election_event::request_contest_ballot_token(
+            ctx,
+            issuer_commitments,
+            blinded_challenges,
+        )
+    }
+
+    /// Make the election the host of an election event (requires approved proposal)
+    pub fn create_election_event(ctx: Context<CreateElectionEvent>, name: String) -> Result<()> {
+        instructions::election_event::create_election_event(ctx, name)
+    }
+
+    /// Add an election as a contest of the host's event (requires approved proposal)
+    pub fn add_event_contest(ctx: Context<AddEventContest>) -> Result<()> {
+        instructions::election_event::add_event_contest(ctx)
+    }
+
+    /// Store the Groth16 verifying key and create the identity tree (requires approved proposal)
+    pub fn configure_membership_proofs(
+        ctx: Context<ConfigureMembershipProofs>,
+        verifying_key: Groth16VerifyingKey,
+    ) -> Result<()> {
+        instructions::zk_membership::configure_membership_proofs(ctx, verifying_key)
+    }
+
+    /// Add a verified voter's identity commitment to the membership tree
+    pub fn register_identity(
+        ctx: Context<RegisterIdentity>,
+        identity_commitment: [u8; 32],
+    ) -> Result<()> {
+        instructions::zk_membership::register_identity(ctx, identity_commitment)
+    }
+
     /// Cast an anonymous vote
-    /// Separates voter identity from vote choice for ballot secrecy
-    pub fn cast_vote(
-        ctx: Context<CastVote>,
+    /// Submitted by a relayer with a nullifier and a blind ballot token or
+    /// membership proof, so the ballot is not linked to the voter credential
+    pub fn cast_vote<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CastVote<'info>>,
+        nullifier: [u8; 32],
+        authorization: BallotAuthorization,
+        kind: BallotKind,
         encrypted_vote_data: [u8; 32],
     ) -> Result<()> {
-        instructions::cast_vote::cast_vote(ctx, encrypted_vote_data)
+        instructions::cast_vote::cast_vote(ctx, nullifier, authorization, kind, encrypted_vote_data)
+    }
+
+    /// Open a commit-reveal ballot after polling closes and count it
+    pub fn reveal_vote<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RevealVote<'info>>,
+        candidate_id: u32,
+        nullifier: [u8; 32],
+        salt: [u8; 32],
+    ) -> Result<()> {
+        instructions::reveal_vote::reveal_vote(ctx, candidate_id, nullifier, salt)
+    }
+
+    /// Set up the encrypted tally for an Encrypted-mode election (requires approved proposal)
+    /// Candidate accounts are passed as remaining accounts in `candidate_ids` order
+    pub fn configure_encrypted_tally<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ConfigureEncryptedTally<'info>>,
+        threshold: u8,
+        candidate_ids: Vec<u32>,
+        key_commitments: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::encrypted_tally::configure_encrypted_tally(
+            ctx,
+            threshold,
+            candidate_ids,
+            key_commitments,
+        )
+    }
+
+    /// Cast an encrypted one-hot ballot with validity proofs
+    /// Only the homomorphic per-candidate sums are updated
+    pub fn cast_encrypted_vote<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CastEncryptedVote<'info>>,
+        nullifier: [u8; 32],
+        kind: BallotKind,
+        ballot: Option<EncryptedBallot>,
+    ) -> Result<()> {
+        instructions::encrypted_tally::cast_encrypted_vote(ctx, nullifier, kind, ballot)
+    }
+
+    /// Post a commissioner's verified decryption shares after polling closes
+    pub fn submit_decryption_share(
+        ctx: Context<SubmitDecryptionShare>,
+        shares: Vec<[u8; 32]>,
+        proofs: Vec<EqualityProof>,
+    ) -> Result<()> {
+        instructions::encrypted_tally::submit_decryption_share(ctx, shares, proofs)
     }
 
-    /// Activate the election (requires commissioner authority)
-    pub fn activate_election(ctx: Context<ManageElection>) -> Result<()> {
-        instructions::manage_election::activate_election(ctx)
+    /// Publish verified plaintext counts and move the election to Tallied
+    pub fn publish_tally<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PublishTally<'info>>,
+        counts: Vec<u64>,
+        lagrange_coefficients: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::encrypted_tally::publish_tally(ctx, counts, lagrange_coefficients)
     }
 
-    /// Finalize election and close voting (requires commissioner authority)
-    pub fn finalize_election(ctx: Context<ManageElection>) -> Result<()> {
-        instructions::manage_election::finalize_election(ctx)
+    /// Fold province regions and their ticket tallies (remaining accounts) into the result
+    pub fn tally_provinces<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TallyProvinces<'info>>,
+        candidate_ids: Vec<u32>,
+    ) -> Result<()> {
+        instructions::election_result::tally_provinces(ctx, candidate_ids)
+    }
+
+    /// Decide the winner or a runoff under the 50%+1 and provincial-spread rule
+    /// Candidate accounts are passed as remaining accounts in result order
+    pub fn compute_result<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ComputeResult<'info>>,
+    ) -> Result<()> {
+        instructions::election_result::compute_result(ctx)
+    }
+
+    /// Elect the top max_selections candidates of a multi-seat race
+    /// Candidate accounts are passed as remaining accounts in ascending ID order
+    pub fn tally_multi_seat<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TallyMultiSeat<'info>>,
+    ) -> Result<()> {
+        instructions::election_result::tally_multi_seat(ctx)
+    }
+
+    /// Spawn the second round between the top two tickets (requires approved proposal)
+    pub fn create_runoff(
+        ctx: Context<CreateRunoff>,
+        election_name: String,
+        registration_start: i64,
+        registration_end: i64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        instructions::runoff::create_runoff(
+            ctx,
+            election_name,
+            registration_start,
+            registration_end,
+            start_time,
+            end_time,
+        )
+    }
+
+    /// Carry a verified first-round credential over to the runoff
+    pub fn carry_over_voter(ctx: Context<CarryOverVoter>) -> Result<()> {
+        instructions::runoff::carry_over_voter(ctx)
+    }
+
+    /// Create a province, regency, district or village region (requires approved proposal)
+    pub fn create_region(ctx: Context<CreateRegion>, region_code: u64, name: String) -> Result<()> {
+        instructions::region::create_region(ctx, region_code, name)
+    }
+
+    /// Create a polling station (TPS) in a village (requires approved proposal)
+    pub fn create_polling_station(
+        ctx: Context<CreatePollingStation>,
+        region_code: u64,
+        tps_number: u16,
+        capacity: u32,
+    ) -> Result<()> {
+        instructions::polling_station::create_polling_station(ctx, region_code, tps_number, capacity)
+    }
+
+    /// Apply to vote at another polling station (DPTb)
+    pub fn request_transfer(ctx: Context<RequestTransfer>) -> Result<()> {
+        instructions::polling_station::request_transfer(ctx)
+    }
+
+    /// Approve a pending transfer as a commissioner or registrar
+    pub fn approve_transfer(ctx: Context<ApproveTransfer>) -> Result<()> {
+        instructions::polling_station::approve_transfer(ctx)
+    }
+
+    /// Create a legislative electoral district (dapil) (requires approved proposal)
+    pub fn create_electoral_district<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateElectoralDistrict<'info>>,
+        district_number: u16,
+        name: String,
+        seats: u8,
+        region_codes: Vec<u64>,
+    ) -> Result<()> {
+        instructions::legislative::create_electoral_district(
+            ctx,
+            district_number,
+            name,
+            seats,
+            region_codes,
+        )
+    }
+
+    /// Register a party for a legislative election (requires approved proposal)
+    pub fn register_party(ctx: Context<RegisterParty>, party_id: u16, name: String) -> Result<()> {
+        instructions::legislative::register_party(ctx, party_id, name)
+    }
+
+    /// Put a candidate on a party's list in a dapil (requires approved proposal)
+    pub fn register_list_candidate(
+        ctx: Context<RegisterListCandidate>,
+        party_id: u16,
+        district_number: u16,
+        list_number: u8,
+        name: String,
+    ) -> Result<()> {
+        instructions::legislative::register_list_candidate(
+            ctx,
+            party_id,
+            district_number,
+            list_number,
+            name,
+        )
+    }
+
+    /// Cast an open-list ballot for a party or one of its candidates
+    pub fn cast_legislative_vote<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CastLegislativeVote<'info>>,
+        nullifier: [u8; 32],
+        signature: BlindSignature,
+        contents_signature: BlindSignature,
+        kind: BallotKind,
+    ) -> Result<()> {
+        instructions::legislative::cast_legislative_vote(
+            ctx,
+            nullifier,
+            signature,
+            contents_signature,
+            kind,
+        )
+    }
+
+    /// Divide a dapil's seats among parties over the threshold (Sainte-Laguë)
+    pub fn allocate_seats<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AllocateSeats<'info>>,
+        district_number: u16,
+    ) -> Result<()> {
+        instructions::seat_allocation::allocate_seats(ctx, district_number)
+    }
+
+    /// Fill each party's dapil seats with its candidates by individual votes
+    pub fn assign_seats<'info>(ctx: Context<'_, '_, 'info, 'info, AssignSeats<'info>>) -> Result<()> {
+        instructions::seat_allocation::assign_seats(ctx)
+    }
+
+    /// Fix a ranked-choice election's candidates and create its tally (requires approved proposal)
+    pub fn configure_ranked_choice<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ConfigureRankedChoice<'info>>,
+        candidate_ids: Vec<u32>,
+    ) -> Result<()> {
+        instructions::ranked_choice::configure_ranked_choice(ctx, candidate_ids)
+    }
+
+    /// Cast a ballot ranking candidates in order of preference
+    pub fn cast_ranked_vote<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CastRankedVote<'info>>,
+        nullifier: [u8; 32],
+        signature: BlindSignature,
+        contents_signature: BlindSignature,
+        kind: BallotKind,
+        ranking: Vec<u32>,
+    ) -> Result<()> {
+        instructions::ranked_choice::cast_ranked_vote(
+            ctx,
+            nullifier,
+            signature,
+            contents_signature,
+            kind,
+            ranking,
+        )
+    }
+
+    /// Count ranked ballots for the current instant-runoff round
+    pub fn tally_irv<'info>(ctx: Context<'_, '_, 'info, 'info, TallyIrv<'info>>) -> Result<()> {
+        instructions::ranked_choice::tally_irv(ctx)
+    }
+
+    /// Move the election through its lifecycle (requires approved proposal)
+    pub fn transition_election(ctx: Context<ManageElection>, status: ElectionStatus) -> Result<()> {
+        instructions::manage_election::transition_election(ctx, status)
+    }
+
+    /// Extend the voter registration window before it closes (requires approved proposal)
+    pub fn extend_registration(ctx: Context<ManageElection>, registration_end: i64) -> Result<()> {
+        instructions::manage_election::extend_registration(ctx, registration_end)
+    }
+
+    /// Add a registrar allowed to attest voter verification (requires approved proposal)
+    pub fn add_registrar(ctx: Context<ManageElection>, registrar: Pubkey) -> Result<()> {
+        instructions::manage_election::add_registrar(ctx, registrar)
+    }
+
+    /// Remove a registrar (requires approved proposal)
+    pub fn remove_registrar(ctx: Context<ManageElection>, registrar: Pubkey) -> Result<()> {
+        instructions::manage_election::remove_registrar(ctx, registrar)
+    }
+
+    /// Set the blind ballot-token issuer key and its wallet (requires approved proposal)
+    pub fn set_ballot_issuer(
+        ctx: Context<ManageElection>,
+        issuer_key: [u8; 32],
+        issuer_authority: Pubkey,
+    ) -> Result<()> {
+        instructions::manage_election::set_ballot_issuer(ctx, issuer_key, issuer_authority)
+    }
+
+    /// Set the parliamentary threshold in basis points (requires approved proposal)
+    pub fn set_parliamentary_threshold(ctx: Context<ManageElection>, threshold_bps: u16) -> Result<()> {
+        instructions::manage_election::set_parliamentary_threshold(ctx, threshold_bps)
+    }
+
+    /// Set how many candidates a ballot may mark (requires approved proposal)
+    pub fn set_max_selections(ctx: Context<ManageElection>, max_selections: u8) -> Result<()> {
+        instructions::manage_election::set_max_selections(ctx, max_selections)
     }
 
     /// Admin function to audit AI verification integrity (for testing)
@@ -123,11 +476,17 @@ pub mod idngenelectevotingsolana {
         instructions::audit::audit_verification(ctx)
     }
 
-    /// Voter can verify their vote was counted using their receipt
+    /// Vote totals, with unrevealed commit-reveal ballots reported separately
+    pub fn tally_summary(ctx: Context<ElectionView>) -> Result<TallySummary> {
+        instructions::audit::tally_summary(ctx)
+    }
+
+    /// Voter can verify their vote was counted from their ballot nullifier
     pub fn verify_ballot_receipt(
         ctx: Context<VerifyReceipt>,
+        nullifier: [u8; 32],
     ) -> Result<ReceiptVerification> {
-        instructions::audit::verify_ballot_receipt(ctx)
+        instructions::audit::verify_ballot_receipt(ctx, nullifier)
     }
 }
 
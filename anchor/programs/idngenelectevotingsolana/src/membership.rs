@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+use solana_bn254::prelude::{alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing};
+use solana_poseidon::{hashv as poseidon_hashv, Endianness, Parameters};
+use crate::errors::ErrorCode;
+
+// Semaphore-style anonymous membership.
+// Verified voters add an identity commitment Poseidon(identity_nullifier,
+// identity_trapdoor) as a leaf of a Poseidon incremental Merkle tree. To vote
+// they prove in Groth16, over BN254, that they know the secrets behind some
+// leaf under the election's root, exposing only
+//   nullifier_hash = Poseidon(external_nullifier, identity_nullifier)
+// which is unique per identity and election but unlinkable to the leaf.
+// All values are big-endian field elements, as the alt_bn128 and Poseidon
+// syscalls expect.
+
+/// Depth of the membership tree (up to 2^32 identity commitments)
+pub const MEMBERSHIP_TREE_DEPTH: usize = 32;
+
+/// Public inputs: merkle_root, nullifier_hash, signal_hash, external_nullifier
+pub const MEMBERSHIP_PUBLIC_INPUTS: usize = 4;
+
+/// BN254 scalar field modulus r
+const SCALAR_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
+/// BN254 base field modulus q
+const BASE_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+
+/// Groth16 proof; G2 coordinates are (c1, c0) as in EIP-197
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct Groth16Proof {
+    pub a: [u8; 64],
+    pub b: [u8; 128],
+    pub c: [u8; 64],
+}
+
+/// Groth16 verifying key for the membership circuit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct Groth16VerifyingKey {
+    pub alpha_g1: [u8; 64],
+    pub beta_g2: [u8; 128],
+    pub gamma_g2: [u8; 128],
+    pub delta_g2: [u8; 128],
+    pub ic: [[u8; 64]; MEMBERSHIP_PUBLIC_INPUTS + 1],
+}
+
+impl Groth16VerifyingKey {
+    /// Digest a commissioner proposal approves instead of the full key
+    pub fn hash(&self) -> [u8; 32] {
+        let mut parts: Vec<&[u8]> = vec![&self.alpha_g1, &self.beta_g2, &self.gamma_g2, &self.delta_g2];
+        parts.extend(self.ic.iter().map(|point| point.as_slice()));
+        solana_program::hash::hashv(&parts).to_bytes()
+    }
+}
+
+/// Verify a Groth16 proof against `public_inputs`:
+/// e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1
+pub fn verify_groth16(
+    verifying_key: &Groth16VerifyingKey,
+    proof: &Groth16Proof,
+    public_inputs: &[[u8; 32]; MEMBERSHIP_PUBLIC_INPUTS],
+) -> Result<()> {
+    // vk_x = IC_0 + sum(input_i * IC_{i+1})
+    let mut vk_x = verifying_key.ic[0].to_vec();
+    for (input, ic) in public_inputs.iter().zip(verifying_key.ic[1..].iter()) {
+        require!(is_field_element(input), ErrorCode::InvalidMembershipProof);
+        let term = alt_bn128_multiplication(&[ic.as_slice(), input].concat())
+            .map_err(|_| error!(ErrorCode::InvalidMembershipProof))?;
+        vk_x = alt_bn128_addition(&[vk_x.as_slice(), &term].concat())
+            .map_err(|_| error!(ErrorCode::InvalidMembershipProof))?;
+    }
+
+    let pairing_input = [
+        negate_g1(&proof.a).as_slice(),
+        &proof.b,
+        &verifying_key.alpha_g1,
+        &verifying_key.beta_g2,
+        &vk_x,
+        &verifying_key.gamma_g2,
+        &proof.c,
+        &verifying_key.delta_g2,
+    ]
+    .concat();
+    let result = alt_bn128_pairing(&pairing_input)
+        .map_err(|_| error!(ErrorCode::InvalidMembershipProof))?;
+
+    let mut one = [0u8; 32];
+    one[31] = 1;
+    require!(result == one, ErrorCode::InvalidMembershipProof);
+
+    Ok(())
+}
+
+/// Map arbitrary bytes into the scalar field: SHA-256 with the top byte cleared
+pub fn hash_to_field(data: &[&[u8]]) -> [u8; 32] {
+    let mut hash = solana_program::hash::hashv(data).to_bytes();
+    hash[0] = 0;
+    hash
+}
+
+pub fn is_field_element(value: &[u8; 32]) -> bool {
+    *value < SCALAR_FIELD_MODULUS
+}
+
+/// Poseidon(left, right) over BN254, as used by Semaphore (circomlib)
+pub fn poseidon_pair(left: &[u8; 32], right: &[u8; 32]) -> Result<[u8; 32]> {
+    poseidon_hashv(Parameters::Bn254X5, Endianness::BigEndian, &[left, right])
+        .map(|hash| hash.to_bytes())
+        .map_err(|_| error!(ErrorCode::InvalidIdentityCommitment))
+}
+
+/// Empty-tree values: `filled_subtrees` start as the zero hash of each level
+/// (level 0 is the zero leaf), and the root is the zero hash of the top level
+pub fn empty_tree() -> Result<([[u8; 32]; MEMBERSHIP_TREE_DEPTH], [u8; 32])> {
+    let mut zeros = [[0u8; 32]; MEMBERSHIP_TREE_DEPTH];
+    let mut zero = [0u8; 32];
+    for level_zero in zeros.iter_mut() {
+        *level_zero = zero;
+        zero = poseidon_pair(&zero, &zero)?;
+    }
+    Ok((zeros, zero))
+}
+
+/// Append `leaf` at `index` to an incremental Merkle tree and return the new root
+pub fn insert_leaf(
+    filled_subtrees: &mut [[u8; 32]; MEMBERSHIP_TREE_DEPTH],
+    index: u64,
+    leaf: &[u8; 32],
+) -> Result<[u8; 32]> {
+    require!(
+        index < 1u64 << MEMBERSHIP_TREE_DEPTH,
+        ErrorCode::MembershipTreeFull
+    );
+    require!(is_field_element(leaf), ErrorCode::InvalidIdentityCommitment);
+
+    let mut node = *leaf;
+    let mut zero = [0u8; 32];
+    for (level, subtree) in filled_subtrees.iter_mut().enumerate() {
+        node = if (index >> level) & 1 == 0 {
+            // Left child: remember it, pair with the empty right sibling
+            *subtree = node;
+            poseidon_pair(&node, &zero)?
+        } else {
+            poseidon_pair(subtree, &node)?
+        };
+        zero = poseidon_pair(&zero, &zero)?;
+    }
+
+    Ok(node)
+}
+
+/// Negate a G1 point: (x, y) -> (x, q - y)
+fn negate_g1(point: &[u8; 64]) -> [u8; 64] {
+    let mut negated = *point;
+    if point[32..].iter().all(|&byte| byte == 0) {
+        return negated;
+    }
+
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut diff = BASE_FIELD_MODULUS[i] as i16 - point[32 + i] as i16 - borrow;
+        borrow = if diff < 0 {
+            diff += 256;
+            1
+        } else {
+            0
+        };
+        negated[32 + i] = diff as u8;
+    }
+    negated
+}
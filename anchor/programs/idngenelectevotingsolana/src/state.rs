@@ -1,20 +1,341 @@
 use anchor_lang::prelude::*;
+use crate::elgamal::ElGamalCiphertext;
+use crate::membership::{Groth16VerifyingKey, MEMBERSHIP_TREE_DEPTH};
+use crate::errors::ErrorCode;
+
+pub const MAX_COMMISSIONERS: usize = 10;
+pub const MAX_REGISTRARS: usize = 5;
+/// Parties that can jointly nominate one ticket
+pub const MAX_COALITION_PARTIES: usize = 18;
+/// Provinces in Indonesia; the presidential spread rule counts them
+pub const PROVINCE_COUNT: usize = 38;
+/// Kemendagri codes of the provinces, Aceh (11) to Papua Barat Daya (96)
+pub const PROVINCE_CODES: [u8; PROVINCE_COUNT] = [
+    11, 12, 13, 14, 15, 16, 17, 18, 19, 21, 31, 32, 33, 34, 35, 36, 51, 52, 53, 61, 62, 63, 64,
+    65, 71, 72, 73, 74, 75, 76, 81, 82, 91, 92, 93, 94, 95, 96,
+];
+/// Tickets a presidential result can rank
+pub const MAX_RESULT_CANDIDATES: usize = 8;
+/// Encrypted ballots carry ~200 bytes per candidate and must fit in one transaction
+pub const MAX_ENCRYPTED_CANDIDATES: usize = 4;
+/// DPR electoral districts (dapil); DPRD elections have fewer
+pub const MAX_ELECTORAL_DISTRICTS: usize = 84;
+/// Regions (regencies/cities, or districts for DPRD) making up one dapil
+pub const MAX_DISTRICT_REGIONS: usize = 24;
+/// Seats one dapil elects: 3 to 10 for DPR, up to 12 for DPRD
+pub const MAX_DISTRICT_SEATS: usize = 12;
+/// Parliamentary threshold (UU 7/2017 Art. 414): 4% of national party votes
+pub const DEFAULT_PARLIAMENTARY_THRESHOLD_BPS: u16 = 400;
+/// Candidates a ranked ballot can order
+pub const MAX_RANKED_CANDIDATES: usize = 8;
+/// Candidates one ballot can mark in a multi-seat race (DPD elects 4 per
+/// province)
+pub const MAX_SELECTIONS: usize = 8;
+/// Ballots on one polling day: President, DPR, DPD, DPRD province and regency
+pub const MAX_EVENT_CONTESTS: usize = 5;
+
+/// Program-wide index of elections; its counter assigns election IDs
+#[account]
+#[derive(InitSpace)]
+pub struct ElectionRegistry {
+    /// Number of elections created (next election ID)
+    pub election_count: u64,
+    pub bump: u8,
+}
 
 /// Main Election account storing election metadata and configuration
 #[account]
 #[derive(InitSpace)]
 pub struct Election {
+    /// Registry-assigned ID the election PDA is derived from
+    pub election_id: u64,
     pub authority: Pubkey,
+    /// Display name only; not part of any PDA seed
     #[max_len(100)]
     pub election_name: String,
+    /// Public per-election salt mixed into NIK commitments, so commitments
+    /// cannot be matched across elections
+    pub nik_salt: [u8; 32],
+    /// Voter registration (DPT) window, closes no later than start_time
+    pub registration_start: i64,
+    pub registration_end: i64,
     pub start_time: i64,
     pub end_time: i64,
-    pub is_active: bool,
+    pub status: ElectionStatus,
+    pub voting_mode: VotingMode,
     pub total_registered_voters: u64,
     pub total_votes_cast: u64,
+    /// Commitment ballots opened by reveal_vote (CommitReveal mode)
+    pub total_votes_revealed: u64,
+    /// Blank ballots (no choice marked), included in total_votes_cast
+    pub blank_votes: u64,
+    /// Spoiled (invalid) ballots, included in total_votes_cast
+    pub spoiled_votes: u64,
     #[max_len(10)]
     pub commissioners: Vec<Pubkey>,
     pub required_signatures: u8,
+    /// Registrars allowed to attest voter verification results
+    #[max_len(5)]
+    pub registrars: Vec<Pubkey>,
+    /// Number of commissioner proposals opened (next proposal ID)
+    pub proposal_count: u64,
+    /// Ristretto public key of the blind ballot-token issuer (zero until set)
+    pub ballot_issuer: [u8; 32],
+    /// Wallet of the issuer service; only it may post blind responses, so
+    /// the clause answered is the issuer's choice, not the requester's
+    pub ballot_issuer_authority: Pubkey,
+    /// Blind ballot tokens issued; anonymous ballots can never exceed this
+    pub ballot_tokens_issued: u64,
+    /// Root of the identity-commitment Merkle tree (ZkMembership mode)
+    pub membership_root: [u8; 32],
+    /// First-round election this runoff was spawned from
+    pub parent_election: Option<Pubkey>,
+    /// Share of national party votes, in basis points, a party needs to
+    /// win seats (Legislative mode)
+    pub parliamentary_threshold_bps: u16,
+    /// Parties registered (Legislative mode)
+    pub party_count: u16,
+    /// Election event (pemilu serentak) this election is a contest of
+    pub event: Option<Pubkey>,
+    /// Candidates a ballot may mark, and seats elected; 1 for a
+    /// single-winner race (Plurality and ZkMembership modes)
+    pub max_selections: u8,
+    /// Candidates marked on cast_vote ballots; tally_multi_seat checks the
+    /// candidates' votes add up to it
+    pub total_selections: u64,
+    /// Set by tally_multi_seat once every marked candidate is counted; a
+    /// multi-seat race is only certified after that
+    pub multi_seat_tallied: bool,
+    pub bump: u8,
+}
+
+impl Election {
+    pub fn is_commissioner(&self, key: &Pubkey) -> bool {
+        self.commissioners.contains(key)
+    }
+
+    pub fn is_registrar(&self, key: &Pubkey) -> bool {
+        self.registrars.contains(key)
+    }
+
+    /// Ballots counted towards a candidate; blank and spoiled ballots, and
+    /// unrevealed commitment ballots (CommitReveal mode), are not
+    pub fn counted_votes(&self) -> u64 {
+        match self.voting_mode {
+            VotingMode::CommitReveal => self.total_votes_revealed,
+            _ => self
+                .total_votes_cast
+                .saturating_sub(self.blank_votes)
+                .saturating_sub(self.spoiled_votes),
+        }
+    }
+
+    /// Candidate ballots not counted for anyone: commitment ballots never
+    /// revealed (CommitReveal mode); zero in every other mode
+    pub fn unrevealed_votes(&self) -> u64 {
+        self.total_votes_cast
+            .saturating_sub(self.counted_votes())
+            .saturating_sub(self.blank_votes)
+            .saturating_sub(self.spoiled_votes)
+    }
+
+    /// Count a blank or spoiled ballot; candidate ballots are counted by
+    /// their candidate
+    pub fn record_ballot_kind(&mut self, kind: BallotKind) -> Result<()> {
+        match kind {
+            BallotKind::Candidate => {}
+            BallotKind::Blank => {
+                self.blank_votes = self.blank_votes.checked_add(1).ok_or(ErrorCode::Overflow)?
+            }
+            BallotKind::Spoiled => {
+                self.spoiled_votes = self.spoiled_votes.checked_add(1).ok_or(ErrorCode::Overflow)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Polling hours in `time_zone`: start_time..end_time is the national
+    /// window, opening in WIT and closing in WIB, so each zone votes during
+    /// the same local hours (e.g. 07:00-13:00 over a 22:00-06:00 UTC window)
+    pub fn voting_window(&self, time_zone: TimeZone) -> (i64, i64) {
+        let opens_after = (TimeZone::Wit.utc_offset_hours() - time_zone.utc_offset_hours()) * 3600;
+        let closes_before = (time_zone.utc_offset_hours() - TimeZone::Wib.utc_offset_hours()) * 3600;
+        (self.start_time + opens_after, self.end_time - closes_before)
+    }
+
+    /// Hours when polls are open in every zone, from WIB opening to WIT
+    /// closing; for ballots that cannot show which zone they come from
+    pub fn common_voting_window(&self) -> (i64, i64) {
+        let (opens, _) = self.voting_window(TimeZone::Wib);
+        let (_, closes) = self.voting_window(TimeZone::Wit);
+        (opens, closes)
+    }
+
+    /// Move the election to `next`, enforcing the lifecycle and its time constraints
+    pub fn transition_to(&mut self, next: ElectionStatus, now: i64) -> Result<()> {
+        self.status.check_transition(next)?;
+
+        match next {
+            ElectionStatus::Voting => {
+                require!(now >= self.start_time, ErrorCode::ElectionNotStarted)
+            }
+            ElectionStatus::Closed => {
+                require!(now > self.end_time, ErrorCode::ElectionStillActive)
+            }
+            ElectionStatus::Tallied => {
+                // Encrypted tallies only become Tallied through publish_tally
+                require!(
+                    self.voting_mode != VotingMode::Encrypted,
+                    ErrorCode::TallyRequiresDecryption
+                )
+            }
+            ElectionStatus::Certified => {
+                require!(
+                    self.max_selections == 1 || self.multi_seat_tallied,
+                    ErrorCode::MultiSeatResultPending
+                )
+            }
+            _ => {}
+        }
+
+        self.status = next;
+
+        Ok(())
+    }
+}
+
+/// Election lifecycle
+///
+/// Draft -> Registration -> Voting -> Closed -> Tallied -> Certified,
+/// with Cancelled reachable from any phase before certification
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum ElectionStatus {
+    /// Being configured: candidates and registrars can be set up
+    Draft,
+    /// Voter registration open, candidates can still be registered
+    Registration,
+    /// Polling open
+    Voting,
+    /// Polling closed, awaiting tally
+    Closed,
+    /// Results counted, awaiting certification
+    Tallied,
+    /// Results certified by the commission (final)
+    Certified,
+    /// Election called off (final)
+    Cancelled,
+}
+
+impl ElectionStatus {
+    /// Central transition table; each illegal transition has its own error
+    pub fn check_transition(self, next: ElectionStatus) -> Result<()> {
+        use ElectionStatus::*;
+
+        if self == Cancelled {
+            return err!(ErrorCode::ElectionCancelled);
+        }
+
+        match (self, next) {
+            (Draft, Registration)
+            | (Registration, Voting)
+            | (Voting, Closed)
+            | (Closed, Tallied)
+            | (Tallied, Certified) => Ok(()),
+            (Certified, Cancelled) => err!(ErrorCode::CannotCancel),
+            (_, Cancelled) => Ok(()),
+            (_, Draft) => err!(ErrorCode::CannotReturnToDraft),
+            (_, Registration) => err!(ErrorCode::CannotOpenRegistration),
+            (_, Voting) => err!(ErrorCode::CannotOpenVoting),
+            (_, Closed) => err!(ErrorCode::CannotCloseVoting),
+            (_, Tallied) => err!(ErrorCode::CannotTally),
+            (_, Certified) => err!(ErrorCode::CannotCertify),
+        }
+    }
+
+    pub fn allows_candidate_registration(self) -> bool {
+        matches!(self, ElectionStatus::Draft | ElectionStatus::Registration)
+    }
+
+    pub fn allows_voter_registration(self) -> bool {
+        self == ElectionStatus::Registration
+    }
+
+    pub fn allows_voting(self) -> bool {
+        self == ElectionStatus::Voting
+    }
+
+    pub fn allows_nik_rebind(self) -> bool {
+        matches!(self, ElectionStatus::Registration | ElectionStatus::Voting)
+    }
+
+    pub fn allows_recapitulation(self) -> bool {
+        matches!(self, ElectionStatus::Closed | ElectionStatus::Tallied)
+    }
+
+    pub fn allows_result_computation(self) -> bool {
+        matches!(self, ElectionStatus::Tallied | ElectionStatus::Certified)
+    }
+}
+
+/// What a ballot records, as on the C1 tally sheet
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum BallotKind {
+    /// A valid vote for a candidate (or a commitment to one)
+    Candidate,
+    /// Valid ballot with no choice marked
+    Blank,
+    /// Invalid ballot (marked more than once, torn, etc.)
+    Spoiled,
+}
+
+/// How ballots are cast and counted
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum VotingMode {
+    /// Plaintext per-candidate counters, cast anonymously with blind
+    /// ballot tokens (request_ballot_token, cast_vote)
+    Plurality,
+    /// Homomorphic ElGamal tally with threshold decryption (cast_encrypted_vote)
+    Encrypted,
+    /// Plaintext per-candidate counters, cast with a Groth16 membership proof
+    /// and nullifier (register_identity, cast_vote)
+    ZkMembership,
+    /// Ballots hold only a commitment while polling is open and are opened
+    /// with reveal_vote after close (request_ballot_token, cast_vote)
+    CommitReveal,
+    /// Open party-list legislative ballots (DPR/DPRD): a party, or one
+    /// candidate on its list (request_ballot_token, cast_legislative_vote)
+    Legislative,
+    /// Ranked ballots counted by instant runoff (request_ballot_token,
+    /// cast_ranked_vote, tally_irv)
+    RankedChoice,
+}
+
+impl VotingMode {
+    pub fn uses_ballot_tokens(self) -> bool {
+        matches!(
+            self,
+            VotingMode::Plurality
+                | VotingMode::CommitReveal
+                | VotingMode::Legislative
+                | VotingMode::RankedChoice
+        )
+    }
+}
+
+/// Simultaneous elections (pemilu serentak), seeded by the host election
+/// Voters register once, in the host; every contest (the host included) is
+/// an election sharing the host's polling window, and each credential votes
+/// once per contest
+#[account]
+#[derive(InitSpace)]
+pub struct ElectionEvent {
+    pub host: Pubkey,
+    #[max_len(100)]
+    pub name: String,
+    /// Contest elections, host first; bit k of a credential's
+    /// contests_voted stands for contests[k]
+    #[max_len(5)]
+    pub contests: Vec<Pubkey>,
     pub bump: u8,
 }
 
@@ -24,12 +345,75 @@ pub struct Election {
 pub struct Candidate {
     pub election: Pubkey,
     pub candidate_id: u32,
+    /// Presidential candidate (head of the ticket)
     #[max_len(100)]
     pub candidate_name: String,
+    /// Vice-presidential running mate (empty for single-candidate contests)
+    #[max_len(100)]
+    pub running_mate_name: String,
+    /// Ballot number (nomor urut), unique within the election
+    pub ballot_number: u8,
+    /// Nominating coalition; empty for independent tickets
+    #[max_len(18)]
+    pub coalition_party_ids: Vec<u16>,
+    /// IPFS hash of the vision and mission (visi-misi) document
+    #[max_len(100)]
+    pub vision_mission_ipfs_hash: String,
     pub vote_count: u64,
     pub bump: u8,
 }
 
+/// Ticket (pasangan calon) details a commissioner proposal approves
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct CandidateTicket {
+    #[max_len(100)]
+    pub candidate_name: String,
+    #[max_len(100)]
+    pub running_mate_name: String,
+    pub ballot_number: u8,
+    #[max_len(18)]
+    pub coalition_party_ids: Vec<u16>,
+    #[max_len(100)]
+    pub vision_mission_ipfs_hash: String,
+}
+
+impl CandidateTicket {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.candidate_name.len() <= 100 && self.running_mate_name.len() <= 100,
+            ErrorCode::NameTooLong
+        );
+        require!(
+            self.vision_mission_ipfs_hash.len() <= 100,
+            ErrorCode::InvalidIPFSHash
+        );
+        // Ballot numbers are drawn starting at 1
+        require!(self.ballot_number > 0, ErrorCode::InvalidBallotNumber);
+        require!(
+            self.coalition_party_ids.len() <= MAX_COALITION_PARTIES,
+            ErrorCode::InvalidCoalition
+        );
+        for (i, party_id) in self.coalition_party_ids.iter().enumerate() {
+            require!(
+                !self.coalition_party_ids[..i].contains(party_id),
+                ErrorCode::InvalidCoalition
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Per-election ballot number registry entry, seeded by the ballot number
+/// Ensures no two tickets share a nomor urut
+#[account]
+#[derive(InitSpace)]
+pub struct BallotNumberRecord {
+    pub election: Pubkey,
+    pub ballot_number: u8,
+    pub candidate: Pubkey,
+    pub bump: u8,
+}
+
 /// Voter Credential NFT - stores cryptographic proofs, not raw biometric data
 /// Acts as proof of identity verification
 #[account]
@@ -37,8 +421,9 @@ pub struct Candidate {
 pub struct VoterCredential {
     pub election: Pubkey,
     pub voter_authority: Pubkey,
-    /// SHA-256 hash of voter NIK (National Identity Number)
-    pub voter_nik_hash: [u8; 32],
+    /// Keyed commitment to the voter NIK (National Identity Number):
+    /// HMAC-SHA256(registrar secret, nik_salt || NIK), computed off-chain
+    pub voter_nik_commitment: [u8; 32],
     /// SHA-256 hash of combined biometric data (retina + face + fingerprint)
     pub biometric_hash: [u8; 32],
     /// IPFS hash pointing to encrypted selfie photo + ID card
@@ -53,11 +438,164 @@ pub struct VoterCredential {
     /// Unique verification code for voter to confirm their registration
     #[max_len(64)]
     pub verification_code: String,
+    /// Semaphore identity commitment added to the membership tree (ZkMembership mode)
+    pub identity_commitment: Option<[u8; 32]>,
+    /// Village (kelurahan/desa) region the voter is registered in
+    pub region: Pubkey,
+    /// Polling station (TPS) the voter votes at; moved by an approved transfer
+    pub polling_station: Pubkey,
+    /// Contests of the election event already voted in, one bit each
+    pub contests_voted: u8,
+    pub bump: u8,
+}
+
+/// Administrative level of a region, following Kemendagri region codes
+/// (kode wilayah): province 2 digits, regency/city 4, district 6, village 10
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum RegionLevel {
+    /// Provinsi
+    Province,
+    /// Kabupaten/kota
+    Regency,
+    /// Kecamatan
+    District,
+    /// Kelurahan/desa
+    Village,
+}
+
+impl RegionLevel {
+    /// Level of `region_code` and the code of its parent region
+    pub fn from_code(region_code: u64) -> Result<(RegionLevel, Option<u64>)> {
+        match region_code {
+            11..=99 => Ok((RegionLevel::Province, None)),
+            1_000..=9_999 => Ok((RegionLevel::Regency, Some(region_code / 100))),
+            100_000..=999_999 => Ok((RegionLevel::District, Some(region_code / 100))),
+            1_000_000_000..=9_999_999_999 => {
+                Ok((RegionLevel::Village, Some(region_code / 10_000)))
+            }
+            _ => err!(ErrorCode::InvalidRegionCode),
+        }
+    }
+}
+
+/// Indonesian time zone a region votes in
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum TimeZone {
+    /// UTC+7: Sumatra, Java, West and Central Kalimantan
+    Wib,
+    /// UTC+8: Bali, Nusa Tenggara, South, East and North Kalimantan, Sulawesi
+    Wita,
+    /// UTC+9: Maluku and Papua
+    Wit,
+}
+
+impl TimeZone {
+    pub fn utc_offset_hours(self) -> i64 {
+        match self {
+            TimeZone::Wib => 7,
+            TimeZone::Wita => 8,
+            TimeZone::Wit => 9,
+        }
+    }
+
+    /// Time zone of a province by its 2-digit region code
+    pub fn for_province(province_code: u64) -> TimeZone {
+        match province_code {
+            51..=53 | 63..=76 => TimeZone::Wita,
+            81..=99 => TimeZone::Wit,
+            _ => TimeZone::Wib,
+        }
+    }
+}
+
+/// Electoral region under an election, seeded by its region code
+/// Vote tallies roll up: a ballot counts in its village and every ancestor
+#[account]
+#[derive(InitSpace)]
+pub struct Region {
+    pub election: Pubkey,
+    pub region_code: u64,
+    pub level: RegionLevel,
+    /// Enclosing region; None for provinces
+    pub parent: Option<Pubkey>,
+    #[max_len(100)]
+    pub name: String,
+    /// Inherited from the province
+    pub time_zone: TimeZone,
+    /// Ballots cast by voters registered in this region
+    pub votes_cast: u64,
+    /// Blank and spoiled ballots among votes_cast
+    pub blank_votes: u64,
+    pub spoiled_votes: u64,
+    pub bump: u8,
+}
+
+/// Votes for one candidate in one region, seeded by
+/// ["region_tally", region, candidate_id (u32 LE)]
+/// Created by the first ballot for the candidate in the region, so a region
+/// tallies every candidate on the ballot however many there are
+#[account]
+#[derive(InitSpace)]
+pub struct RegionTally {
+    pub election: Pubkey,
+    pub region: Pubkey,
+    pub candidate_id: u32,
+    pub vote_count: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct CandidateVotes {
+    pub candidate_id: u32,
+    pub vote_count: u64,
+}
+
+/// Polling station (TPS), seeded by its village's region code and TPS number
+#[account]
+#[derive(InitSpace)]
+pub struct PollingStation {
+    pub election: Pubkey,
+    /// Village region the station serves
+    pub region: Pubkey,
+    pub region_code: u64,
+    pub tps_number: u16,
+    /// Maximum registered voters (DPT size)
+    pub capacity: u32,
+    pub registered_voters: u32,
+    pub votes_cast: u64,
+    pub bump: u8,
+}
+
+/// Request to vote away from the home station (DPTb / pindah memilih, form A5),
+/// seeded by the voter credential so a voter holds at most one; a new
+/// request overwrites it
+#[account]
+#[derive(InitSpace)]
+pub struct VoterTransfer {
+    pub election: Pubkey,
+    pub voter_credential: Pubkey,
+    pub from_station: Pubkey,
+    pub to_station: Pubkey,
+    pub approved: bool,
+    pub bump: u8,
+}
+
+/// Per-election NIK registry entry, seeded by the NIK commitment
+/// Ensures one NIK backs at most one voter credential across all wallets
+#[account]
+#[derive(InitSpace)]
+pub struct NikRecord {
+    pub election: Pubkey,
+    pub voter_nik_commitment: [u8; 32],
+    /// Credential currently bound to this NIK
+    pub voter_credential: Pubkey,
+    pub voter_authority: Pubkey,
     pub bump: u8,
 }
 
 /// Anonymous ballot record
-/// Deliberately separates voter identity from vote choice
+/// Deliberately separates voter identity from vote choice: plurality ballots
+/// are cast by a relayer with a blind ballot token, never by the voter wallet
 #[account]
 #[derive(InitSpace)]
 pub struct Ballot {
@@ -71,25 +609,697 @@ pub struct Ballot {
     /// Receipt hash that voter can use to verify their vote was counted
     #[max_len(64)]
     pub verification_receipt: String,
+    /// Commit-reveal ballots: set once the commitment has been opened
+    pub is_revealed: bool,
+    /// Village the ballot token was bound to (blind-token ballots only)
+    pub region: Option<Pubkey>,
+    /// Polling station the ballot token was bound to (blind-token ballots only)
+    pub polling_station: Option<Pubkey>,
+    /// Blank and spoiled ballots name no candidate
+    pub kind: BallotKind,
+    /// Candidate IDs in order of preference (RankedChoice mode)
+    #[max_len(8)]
+    pub ranking: Vec<u32>,
+    /// Candidate IDs marked, when the election has more than one seat
+    #[max_len(8)]
+    pub selections: Vec<u32>,
+    pub bump: u8,
+}
+
+/// Blind ballot-token request, created when a voter spends their voting token
+/// Holds only blinded values, so the token the voter unblinds cannot be
+/// matched back to this request or the voter credential
+#[account]
+#[derive(InitSpace)]
+pub struct BallotTokenRequest {
+    pub election: Pubkey,
+    /// Issuer nonce commitments R'_0, R'_1 obtained off-chain from the issuer
+    pub issuer_commitments: [[u8; 32]; 2],
+    /// Voter's blinded challenges c'_0, c'_1, one per clause
+    pub blinded_challenges: [[u8; 32]; 2],
+    /// Issuer response s' for one clause, verified on-chain when posted
+    pub blind_response: Option<[u8; 32]>,
+    /// Clause the issuer chose to answer (0 or 1)
+    pub clause: u8,
+    /// Station the voter votes at; the issuer signs with its derived key
+    pub polling_station: Pubkey,
+    pub bump: u8,
+}
+
+/// Spent ballot-token nullifier, seeded by the nullifier itself
+/// Blocks casting two ballots with one token; the voter can find their
+/// ballot from it without revealing who they are
+#[account]
+#[derive(InitSpace)]
+pub struct Nullifier {
+    pub election: Pubkey,
+    pub ballot: Pubkey,
+    pub bump: u8,
+}
+
+/// Incremental Merkle tree of voter identity commitments
+/// Only the rightmost path is stored; the root is kept on the Election
+#[account]
+#[derive(InitSpace)]
+pub struct MembershipTree {
+    pub election: Pubkey,
+    /// Number of leaves inserted (next leaf index)
+    pub next_index: u64,
+    pub filled_subtrees: [[u8; 32]; MEMBERSHIP_TREE_DEPTH],
+    pub bump: u8,
+}
+
+/// Per-election Groth16 verifying key for membership proofs
+#[account]
+#[derive(InitSpace)]
+pub struct ZkVerifyingKey {
+    pub election: Pubkey,
+    pub verifying_key: Groth16VerifyingKey,
+    pub bump: u8,
+}
+
+/// Election action that requires M-of-N commissioner approval
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum ProposalAction {
+    TransitionElection {
+        status: ElectionStatus,
+    },
+    RegisterCandidate {
+        candidate_id: u32,
+        ticket: CandidateTicket,
+    },
+    ExtendRegistration {
+        registration_end: i64,
+    },
+    RebindNik {
+        voter_nik_commitment: [u8; 32],
+        new_voter: Pubkey,
+    },
+    AddRegistrar {
+        registrar: Pubkey,
+    },
+    RemoveRegistrar {
+        registrar: Pubkey,
+    },
+    ConfigureEncryptedTally {
+        threshold: u8,
+        #[max_len(4)]
+        candidate_ids: Vec<u32>,
+        #[max_len(10)]
+        key_commitments: Vec<[u8; 32]>,
+    },
+    SetBallotIssuer {
+        issuer_key: [u8; 32],
+        issuer_authority: Pubkey,
+    },
+    /// Approves the verifying key by its hash (the key is too large for a proposal)
+    ConfigureMembershipProofs {
+        verifying_key_hash: [u8; 32],
+    },
+    CreateRunoff {
+        #[max_len(100)]
+        election_name: String,
+        registration_start: i64,
+        registration_end: i64,
+        start_time: i64,
+        end_time: i64,
+    },
+    CreateRegion {
+        region_code: u64,
+        #[max_len(100)]
+        name: String,
+    },
+    CreatePollingStation {
+        region_code: u64,
+        tps_number: u16,
+        capacity: u32,
+    },
+    CreateElectoralDistrict {
+        district_number: u16,
+        #[max_len(100)]
+        name: String,
+        seats: u8,
+        #[max_len(24)]
+        region_codes: Vec<u64>,
+    },
+    RegisterParty {
+        party_id: u16,
+        #[max_len(100)]
+        name: String,
+    },
+    RegisterListCandidate {
+        party_id: u16,
+        district_number: u16,
+        list_number: u8,
+        #[max_len(100)]
+        name: String,
+    },
+    SetParliamentaryThreshold {
+        threshold_bps: u16,
+    },
+    CreateElectionEvent {
+        #[max_len(100)]
+        name: String,
+    },
+    AddEventContest {
+        contest: Pubkey,
+    },
+    ConfigureRankedChoice {
+        #[max_len(8)]
+        candidate_ids: Vec<u32>,
+    },
+    SetMaxSelections {
+        max_selections: u8,
+    },
+}
+
+/// Commissioner proposal - the action only runs once `required_signatures`
+/// commissioners have approved it
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub election: Pubkey,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    /// Commissioners that approved this proposal (proposer included)
+    #[max_len(10)]
+    pub approvals: Vec<Pubkey>,
+    pub executed: bool,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Proposal {
+    /// Check the proposal authorizes `action` with enough approvals and mark it executed
+    pub fn execute(&mut self, election: &Election, action: &ProposalAction) -> Result<()> {
+        require!(!self.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(self.action == *action, ErrorCode::ProposalActionMismatch);
+        require!(
+            self.approvals.len() >= election.required_signatures as usize,
+            ErrorCode::InsufficientApprovals
+        );
+
+        self.executed = true;
+
+        Ok(())
+    }
+}
+
+/// Encrypted tally for an election in Encrypted voting mode
+/// Holds per-candidate ElGamal ciphertext sums under the joint commission key;
+/// plaintext counts are only published once a threshold of commissioners
+/// has posted verified decryption shares
+#[account]
+#[derive(InitSpace)]
+pub struct EncryptedTally {
+    pub election: Pubkey,
+    /// Decryption shares needed to open the tally
+    pub threshold: u8,
+    /// Feldman commitments A_0..A_{t-1} to the shared secret polynomial;
+    /// A_0 is the joint public key ballots are encrypted under
+    #[max_len(10)]
+    pub key_commitments: Vec<[u8; 32]>,
+    /// Candidate IDs in ballot vector order
+    #[max_len(4)]
+    pub candidate_ids: Vec<u32>,
+    /// Running encrypted count per candidate
+    #[max_len(4)]
+    pub tallies: Vec<ElGamalCiphertext>,
+    #[max_len(10)]
+    pub decryptions: Vec<TrusteeDecryption>,
+    /// Plaintext counts per candidate, set by publish_tally
+    #[max_len(4)]
+    pub results: Vec<u64>,
+    /// Blank and spoiled ballots (cast unencrypted), set by publish_tally
+    pub blank_votes: u64,
+    pub spoiled_votes: u64,
+    pub is_published: bool,
+    pub bump: u8,
+}
+
+impl EncryptedTally {
+    pub fn joint_public_key(&self) -> [u8; 32] {
+        self.key_commitments[0]
+    }
+}
+
+/// Verified decryption shares x_i * C1 posted by one commissioner (trustee)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TrusteeDecryption {
+    /// Shamir share index (commissioner position + 1)
+    pub trustee_index: u8,
+    /// One share per candidate tally
+    #[max_len(4)]
+    pub shares: Vec<[u8; 32]>,
+}
+
+/// Presidential result under UUD 1945 Article 6A: a ticket wins outright with
+/// more than 50% of the national vote and at least 20% in more than half of
+/// the provinces; otherwise the top two tickets go to a runoff
+#[account]
+#[derive(InitSpace)]
+pub struct ElectionResult {
+    pub election: Pubkey,
+    /// Ticket order shared by every per-candidate vector below
+    #[max_len(8)]
+    pub candidate_ids: Vec<u32>,
+    /// National votes, read from the Candidate accounts by compute_result
+    #[max_len(8)]
+    pub vote_counts: Vec<u64>,
+    /// Provinces already folded in by tally_provinces
+    #[max_len(38)]
+    pub provinces_counted: Vec<u8>,
+    /// Sum of provincial votes per ticket; must match the national count
+    #[max_len(8)]
+    pub provincial_vote_sums: Vec<u64>,
+    /// Provinces where the ticket reached at least 20%
+    #[max_len(8)]
+    pub provinces_with_spread: Vec<u8>,
+    pub outcome: ElectionOutcome,
+    /// Blank and spoiled ballots nationally, copied by compute_result
+    pub blank_votes: u64,
+    pub spoiled_votes: u64,
+    /// Commitment ballots never revealed, copied by compute_result
+    pub unrevealed_votes: u64,
+    /// Second-round election created by create_runoff
+    pub runoff_election: Option<Pubkey>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum ElectionOutcome {
+    /// Provinces are still being counted
+    Pending,
+    Winner {
+        candidate_id: u32,
+    },
+    RunoffRequired {
+        first_candidate_id: u32,
+        second_candidate_id: u32,
+    },
+}
+
+/// Result of a multi-seat race (max_selections > 1): the max_selections
+/// candidates with the most votes win. Candidates are folded in by
+/// tally_multi_seat in candidate ID order, over as many transactions as needed
+#[account]
+#[derive(InitSpace)]
+pub struct MultiSeatResult {
+    pub election: Pubkey,
+    pub seats: u8,
+    pub candidates_counted: u32,
+    /// Last candidate folded in; the next must have a higher ID
+    pub last_candidate_id: u32,
+    /// Votes of the candidates folded in so far
+    pub votes_counted: u64,
+    /// Leading candidates, most votes first
+    #[max_len(8)]
+    pub elected: Vec<CandidateVotes>,
+    /// Set once votes_counted reaches the election's total_selections
+    pub complete: bool,
+    pub blank_votes: u64,
+    pub spoiled_votes: u64,
+    pub bump: u8,
+}
+
+impl MultiSeatResult {
+    /// Keep the candidate if it is among the leaders so far; equal votes go
+    /// to the lower candidate ID, which is folded in first
+    pub fn consider(&mut self, candidate_id: u32, vote_count: u64) {
+        if vote_count == 0 {
+            return;
+        }
+        let position = self
+            .elected
+            .iter()
+            .position(|entry| vote_count > entry.vote_count)
+            .unwrap_or(self.elected.len());
+        if position < usize::from(self.seats) {
+            self.elected.insert(position, CandidateVotes { candidate_id, vote_count });
+            self.elected.truncate(usize::from(self.seats));
+        }
+    }
+}
+
+/// Legislative electoral district (daerah pemilihan), seeded by its number
+/// Covers whole regions; a ballot counts in the dapil of the voter's station
+#[account]
+#[derive(InitSpace)]
+pub struct ElectoralDistrict {
+    pub election: Pubkey,
+    pub district_number: u16,
+    #[max_len(100)]
+    pub name: String,
+    /// Seats the dapil elects
+    pub seats: u8,
+    #[max_len(24)]
+    pub region_codes: Vec<u64>,
+    /// Candidates on all party lists in the dapil
+    pub list_candidate_count: u16,
+    /// Blank and spoiled ballots cast in the dapil
+    pub blank_votes: u64,
+    pub spoiled_votes: u64,
+    pub bump: u8,
+}
+
+/// Dapil membership of a province, regency or district, seeded by its code
+/// Keeps dapils apart: a region belongs to at most one dapil, and one with
+/// a dapil above or below it cannot join another
+#[account]
+#[derive(InitSpace)]
+pub struct DistrictRegion {
+    pub election: Pubkey,
+    pub region_code: u64,
+    /// Dapil the region belongs to; None when only regions below it do
+    pub district_number: Option<u16>,
+    pub bump: u8,
+}
+
+impl ElectoralDistrict {
+    /// Whether a region chain (village first) lies inside this dapil
+    pub fn covers(&self, regions: &[Account<Region>]) -> bool {
+        regions
+            .iter()
+            .any(|region| self.region_codes.contains(&region.region_code))
+    }
+}
+
+/// Political party contesting a legislative election, seeded by its party number
+#[account]
+#[derive(InitSpace)]
+pub struct Party {
+    pub election: Pubkey,
+    /// Party ballot number (nomor urut partai)
+    pub party_id: u16,
+    #[max_len(100)]
+    pub name: String,
+    /// National votes: party-only ballots plus its candidates' ballots
+    pub vote_count: u64,
+    /// The same votes per dapil, added as dapils receive them
+    #[max_len(84)]
+    pub district_votes: Vec<DistrictVotes>,
+    pub bump: u8,
+}
+
+impl Party {
+    pub fn record_vote(&mut self, district_number: u16) -> Result<()> {
+        self.vote_count = self.vote_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        match self
+            .district_votes
+            .iter_mut()
+            .find(|entry| entry.district_number == district_number)
+        {
+            Some(entry) => {
+                entry.vote_count = entry.vote_count.checked_add(1).ok_or(ErrorCode::Overflow)?
+            }
+            None => {
+                require!(
+                    self.district_votes.len() < MAX_ELECTORAL_DISTRICTS,
+                    ErrorCode::PartyTallyFull
+                );
+                self.district_votes.push(DistrictVotes {
+                    district_number,
+                    vote_count: 1,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn district_votes(&self, district_number: u16) -> u64 {
+        self.district_votes
+            .iter()
+            .find(|entry| entry.district_number == district_number)
+            .map_or(0, |entry| entry.vote_count)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct DistrictVotes {
+    pub district_number: u16,
+    pub vote_count: u64,
+}
+
+/// Candidate (caleg) on a party's list in one dapil, seeded by party,
+/// dapil and list position
+#[account]
+#[derive(InitSpace)]
+pub struct PartyListCandidate {
+    pub election: Pubkey,
+    pub party: Pubkey,
+    pub party_id: u16,
+    pub district_number: u16,
+    /// Position on the party list (nomor urut caleg)
+    pub list_number: u8,
+    #[max_len(100)]
+    pub name: String,
+    /// Ballots marking this candidate (each also counts for the party)
+    pub vote_count: u64,
+    pub bump: u8,
+}
+
+/// Seat allocation for one dapil, seeded by its number
+/// allocate_seats divides the seats among parties; assign_seats then fills
+/// each party's seats from its list, over as many transactions as needed
+#[account]
+#[derive(InitSpace)]
+pub struct SeatAllocation {
+    pub election: Pubkey,
+    pub district_number: u16,
+    pub seats: u8,
+    /// Parliamentary threshold the allocation applied
+    pub threshold_bps: u16,
+    /// Parties that won seats, in party order
+    #[max_len(12)]
+    pub party_seats: Vec<PartySeats>,
+    /// List candidates counted by assign_seats so far
+    pub candidates_counted: u16,
+    /// Last candidate counted; candidates are passed in (party, list) order
+    pub last_party_id: u16,
+    pub last_list_number: u8,
+    /// Candidates holding the seats, by party, most votes first
+    #[max_len(12)]
+    pub elected: Vec<ElectedCandidate>,
+    /// Every list candidate in the dapil has been counted
+    pub complete: bool,
+    /// Blank and spoiled ballots in the dapil, copied by allocate_seats
+    pub blank_votes: u64,
+    pub spoiled_votes: u64,
     pub bump: u8,
 }
 
+impl SeatAllocation {
+    /// Rank a list candidate within its party; keeps only as many as the
+    /// party has seats (equal votes go to the lower list number)
+    pub fn consider(&mut self, candidate: &Account<PartyListCandidate>) {
+        let party_id = candidate.party_id;
+        let Some(seats) = self
+            .party_seats
+            .iter()
+            .find(|entry| entry.party_id == party_id)
+            .map(|entry| usize::from(entry.seats))
+        else {
+            return;
+        };
+
+        self.elected.push(ElectedCandidate {
+            candidate: candidate.key(),
+            party_id,
+            list_number: candidate.list_number,
+            vote_count: candidate.vote_count,
+        });
+        self.elected.sort_by_key(|entry| {
+            (entry.party_id, std::cmp::Reverse(entry.vote_count), entry.list_number)
+        });
+        if self.elected.iter().filter(|entry| entry.party_id == party_id).count() > seats {
+            if let Some(last) = self.elected.iter().rposition(|entry| entry.party_id == party_id) {
+                self.elected.remove(last);
+            }
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct PartySeats {
+    pub party_id: u16,
+    /// Party votes in the dapil
+    pub vote_count: u64,
+    pub seats: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct ElectedCandidate {
+    pub candidate: Pubkey,
+    pub party_id: u16,
+    pub list_number: u8,
+    pub vote_count: u64,
+}
+
+/// Instant-runoff count for an election in RankedChoice mode
+/// Each round recounts every ballot for its highest-ranked continuing
+/// candidate; a round without a majority eliminates the last candidate and
+/// the next round transfers those ballots to their next preference
+#[account]
+#[derive(InitSpace)]
+pub struct IrvTally {
+    pub election: Pubkey,
+    /// Candidate IDs, in the order of every per-candidate vector below
+    #[max_len(8)]
+    pub candidate_ids: Vec<u32>,
+    /// Candidates not yet eliminated
+    #[max_len(8)]
+    pub continuing: Vec<bool>,
+    /// Completed rounds, first preferences first
+    #[max_len(8)]
+    pub rounds: Vec<IrvRound>,
+    /// Round in progress: ballots counted so far (the next ballot sequence)
+    pub ballots_counted: u64,
+    #[max_len(8)]
+    pub counts: Vec<u64>,
+    /// Ballots ranking no continuing candidate
+    pub exhausted: u64,
+    /// Set once a candidate holds a majority of continuing ballots
+    pub winner: Option<u32>,
+    /// Blank and spoiled ballots, never counted in a round; copied from the
+    /// election as each round closes
+    pub blank_votes: u64,
+    pub spoiled_votes: u64,
+    pub bump: u8,
+}
+
+impl IrvTally {
+    /// Count a ballot for its highest-ranked continuing candidate; blank
+    /// and spoiled ballots are passed over without being exhausted
+    pub fn count_ballot(&mut self, kind: BallotKind, ranking: &[u32]) -> Result<()> {
+        if kind == BallotKind::Candidate {
+            let preference = ranking.iter().find_map(|candidate_id| {
+                self.candidate_ids
+                    .iter()
+                    .position(|id| id == candidate_id)
+                    .filter(|k| self.continuing[*k])
+            });
+            match preference {
+                Some(k) => {
+                    self.counts[k] = self.counts[k].checked_add(1).ok_or(ErrorCode::Overflow)?
+                }
+                None => {
+                    self.exhausted = self.exhausted.checked_add(1).ok_or(ErrorCode::Overflow)?
+                }
+            }
+        }
+        self.ballots_counted = self.ballots_counted.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Record the round; declare a winner on a majority of continuing
+    /// ballots (or a last candidate standing), otherwise eliminate the
+    /// trailing candidate and start the next round
+    /// A round in which no ballot counts for any candidate (no ballots, or
+    /// only blank and spoiled ones) elects no one and is refused
+    pub fn close_round(&mut self) -> Result<()> {
+        let continuing_total = self
+            .counts
+            .iter()
+            .try_fold(0u64, |total, count| total.checked_add(*count))
+            .ok_or(ErrorCode::Overflow)?;
+        require!(continuing_total > 0, ErrorCode::NoContinuingVotes);
+        let continuing: Vec<usize> =
+            (0..self.candidate_ids.len()).filter(|k| self.continuing[*k]).collect();
+        let leader = continuing
+            .iter()
+            .copied()
+            .max_by_key(|k| (self.counts[*k], std::cmp::Reverse(*k)))
+            .ok_or(ErrorCode::InvalidTallyConfig)?;
+
+        self.rounds.push(IrvRound {
+            counts: self.counts.clone(),
+            exhausted: self.exhausted,
+            eliminated: None,
+        });
+
+        if continuing.len() == 1
+            || u128::from(self.counts[leader]) * 2 > u128::from(continuing_total)
+        {
+            self.winner = Some(self.candidate_ids[leader]);
+        } else {
+            let mut trailing = continuing[0];
+            for k in continuing.iter().copied().skip(1) {
+                if self.trails(k, trailing) {
+                    trailing = k;
+                }
+            }
+            self.continuing[trailing] = false;
+            if let Some(round) = self.rounds.last_mut() {
+                round.eliminated = Some(self.candidate_ids[trailing]);
+            }
+        }
+
+        self.counts = vec![0; self.candidate_ids.len()];
+        self.exhausted = 0;
+        self.ballots_counted = 0;
+        Ok(())
+    }
+
+    /// Whether candidate k trails l: fewer votes in the latest round, then
+    /// in each earlier round going back; still tied, the higher candidate ID
+    fn trails(&self, k: usize, l: usize) -> bool {
+        for round in self.rounds.iter().rev() {
+            if round.counts[k] != round.counts[l] {
+                return round.counts[k] < round.counts[l];
+            }
+        }
+        self.candidate_ids[k] > self.candidate_ids[l]
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct IrvRound {
+    /// Votes per candidate, in IrvTally.candidate_ids order
+    #[max_len(8)]
+    pub counts: Vec<u64>,
+    pub exhausted: u64,
+    /// Candidate eliminated at the end of the round
+    pub eliminated: Option<u32>,
+}
+
 /// Audit data returned for commissioner review (testing AI integrity)
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct AuditData {
-    pub voter_nik_hash: [u8; 32],
+    pub nik_salt: [u8; 32],
+    pub voter_nik_commitment: [u8; 32],
     pub biometric_hash: [u8; 32],
     pub ai_confidence_score: u8,
     pub verification_timestamp: i64,
     pub has_voted: bool,
     pub is_verified: bool,
+    /// Election-wide blank and spoiled ballots so far
+    pub blank_votes: u64,
+    pub spoiled_votes: u64,
+}
+
+/// Vote totals, with unopened commit-reveal ballots reported separately
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct TallySummary {
+    pub status: ElectionStatus,
+    pub total_votes_cast: u64,
+    /// Ballots counted towards a candidate
+    pub counted_votes: u64,
+    /// Commitment ballots never revealed (CommitReveal mode)
+    pub unrevealed_votes: u64,
+    /// Blank ballots (no choice marked)
+    pub blank_votes: u64,
+    /// Spoiled (invalid) ballots
+    pub spoiled_votes: u64,
 }
 
 /// Receipt verification response for voters
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct ReceiptVerification {
+    /// The ballot's stored receipt matches the one recomputed from the nullifier
     pub is_valid: bool,
     pub ballot_sequence: u64,
     pub timestamp: i64,
-    pub verification_code: String,
+    /// Receipt recomputed from the nullifier
+    pub receipt: String,
 }
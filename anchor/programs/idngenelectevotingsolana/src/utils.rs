@@ -1,23 +1,103 @@
 use anchor_lang::prelude::*;
+use solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use crate::errors::ErrorCode;
 
 /// Generate unique verification code for voter
 pub fn generate_verification_code(
     voter_pubkey: Pubkey,
-    voter_nik: &str,
+    voter_nik_commitment: &[u8; 32],
     timestamp: i64,
 ) -> String {
-    let data = format!("{}{}{}", voter_pubkey, voter_nik, timestamp);
-    let hash = solana_program::hash::hash(data.as_bytes());
+    let hash = solana_program::hash::hashv(&[
+        voter_pubkey.as_ref(),
+        voter_nik_commitment,
+        &timestamp.to_le_bytes(),
+    ]);
     bs58::encode(hash.to_bytes()).into_string()[..16].to_string()
 }
 
-/// Generate ballot receipt for voter verification
-pub fn generate_ballot_receipt(
-    voter_pubkey: Pubkey,
-    verification_code: &str,
-    timestamp: i64,
-) -> String {
-    let data = format!("{}{}{}", voter_pubkey, verification_code, timestamp);
-    let hash = solana_program::hash::hash(data.as_bytes());
+/// Generate ballot receipt for an anonymous ballot
+/// Derived from the ballot's nullifier only, which the voter keeps
+pub fn generate_anonymous_ballot_receipt(nullifier: &[u8; 32], timestamp: i64) -> String {
+    let hash = solana_program::hash::hashv(&[nullifier, &timestamp.to_le_bytes()]);
     bs58::encode(hash.to_bytes()).into_string()[..32].to_string()
 }
+
+/// Domain separator for registrar attestations
+pub const REGISTRAR_ATTESTATION_DOMAIN: &[u8] = b"IDN-EVOTE-ATTEST";
+
+/// Message a registrar signs to attest a voter verification result.
+/// Binds the election and voter wallet so an attestation cannot be replayed,
+/// and the polling station so it cannot be used to register elsewhere.
+pub fn registrar_attestation_message(
+    election: &Pubkey,
+    voter: &Pubkey,
+    polling_station: &Pubkey,
+    voter_nik_commitment: &[u8; 32],
+    biometric_hash: &[u8; 32],
+    ai_confidence_score: u8,
+    verification_timestamp: i64,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(REGISTRAR_ATTESTATION_DOMAIN.len() + 32 * 5 + 1 + 8);
+    message.extend_from_slice(REGISTRAR_ATTESTATION_DOMAIN);
+    message.extend_from_slice(election.as_ref());
+    message.extend_from_slice(voter.as_ref());
+    message.extend_from_slice(polling_station.as_ref());
+    message.extend_from_slice(voter_nik_commitment);
+    message.extend_from_slice(biometric_hash);
+    message.push(ai_confidence_score);
+    message.extend_from_slice(&verification_timestamp.to_le_bytes());
+    message
+}
+
+/// Check that the instruction preceding the current one is an ed25519
+/// signature-verify instruction over `message` by one of `registrars`.
+/// The ed25519 program has already verified the signature itself, so only
+/// the signed pubkey and message need checking here.
+pub fn verify_ed25519_attestation(
+    instructions_sysvar: &AccountInfo,
+    registrars: &[Pubkey],
+    message: &[u8],
+) -> Result<()> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    require!(current_index > 0, ErrorCode::MissingRegistrarAttestation);
+    let ix = load_instruction_at_checked((current_index - 1) as usize, instructions_sysvar)?;
+
+    require!(
+        ix.program_id == solana_program::ed25519_program::ID,
+        ErrorCode::MissingRegistrarAttestation
+    );
+
+    // Layout: [num_signatures, padding, offsets (14 bytes) ..., data ...]
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::InvalidRegistrarAttestation);
+
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let signature_ix_index = read_u16(4);
+    let public_key_offset = read_u16(6) as usize;
+    let public_key_ix_index = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    let message_ix_index = read_u16(14);
+
+    // All parts must live in the ed25519 instruction itself
+    require!(
+        signature_ix_index == u16::MAX
+            && public_key_ix_index == u16::MAX
+            && message_ix_index == u16::MAX,
+        ErrorCode::InvalidRegistrarAttestation
+    );
+
+    let signer = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(ErrorCode::InvalidRegistrarAttestation)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(ErrorCode::InvalidRegistrarAttestation)?;
+
+    let signer = Pubkey::try_from(signer).map_err(|_| ErrorCode::InvalidRegistrarAttestation)?;
+    require!(registrars.contains(&signer), ErrorCode::RegistrarNotAuthorized);
+    require!(signed_message == message, ErrorCode::InvalidRegistrarAttestation);
+
+    Ok(())
+}
@@ -5,14 +5,23 @@ import {
   Keypair,
   SystemProgram,
   LAMPORTS_PER_SOL,
+  Ed25519Program,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   getAssociatedTokenAddress,
+  getOrCreateAssociatedTokenAccount,
+  getAccount,
+  transfer,
 } from "@solana/spl-token";
 import { expect } from "chai";
-import { createHash } from "crypto";
+import { createHash, createHmac, randomBytes } from "crypto";
+import { RistrettoPoint } from "@noble/curves/ed25519";
+import { bn254 } from "@noble/curves/bn254";
+import { startAnchor, Clock, ProgramTestContext } from "solana-bankrun";
+import { BankrunProvider } from "anchor-bankrun";
 
 // Import the IDL type - adjust path based on your setup
 import { Idngenelectevotingsolana } from "../target/types/idngenelectevotingsolana";
@@ -24,6 +33,15 @@ import { Idngenelectevotingsolana } from "../target/types/idngenelectevotingsola
 const TEST_ELECTION_NAME = "TestElection2024";
 const FUTURE_START_OFFSET = 60; // 60 seconds in the future
 const ELECTION_DURATION = 86400 * 7; // 7 days
+// Registrar-held key for NIK commitments (never on-chain)
+const REGISTRAR_NIK_SECRET = "test-registrar-nik-secret";
+// Compressed Ristretto base point, a valid stand-in for curve public keys
+const RISTRETTO_BASEPOINT = Array.from(
+  Buffer.from(
+    "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76",
+    "hex"
+  )
+);
 
 // ============================================================================
 // Helper Functions
@@ -37,6 +55,16 @@ function sha256(data: string): number[] {
   return Array.from(hash);
 }
 
+/**
+ * Keyed NIK commitment: HMAC-SHA256(registrar secret, nik_salt || NIK)
+ */
+function nikCommitment(nikSalt: Buffer, nik: string): number[] {
+  const hmac = createHmac("sha256", REGISTRAR_NIK_SECRET)
+    .update(Buffer.concat([nikSalt, Buffer.from(nik)]))
+    .digest();
+  return Array.from(hmac);
+}
+
 /**
  * Get current Unix timestamp
  */
@@ -45,18 +73,75 @@ function getCurrentTimestamp(): number {
 }
 
 /**
- * Derive Election PDA
+ * Derive the global Election Registry PDA
+ */
+function deriveElectionRegistryPDA(programId: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("election_registry")],
+    programId
+  );
+}
+
+/**
+ * Derive Election PDA from its registry-assigned ID
  */
 function deriveElectionPDA(
-  electionName: string,
+  electionId: number | BN,
+  programId: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("election"), new BN(electionId).toArrayLike(Buffer, "le", 8)],
+    programId
+  );
+}
+
+/**
+ * Derive the PDA the next initializeElection call will create
+ */
+async function deriveNextElectionPDA(
+  program: Program<Idngenelectevotingsolana>
+): Promise<[PublicKey, number]> {
+  const [registryPda] = deriveElectionRegistryPDA(program.programId);
+  const registry = await program.account.electionRegistry.fetch(registryPda);
+  return deriveElectionPDA(registry.electionCount, program.programId);
+}
+
+/**
+ * Derive Ballot Number Record PDA (one per nomor urut)
+ */
+function deriveBallotNumberPDA(
+  electionPda: PublicKey,
+  ballotNumber: number,
   programId: PublicKey
 ): [PublicKey, number] {
   return PublicKey.findProgramAddressSync(
-    [Buffer.from("election"), Buffer.from(electionName)],
+    [Buffer.from("ballot_number"), electionPda.toBuffer(), Buffer.from([ballotNumber])],
     programId
   );
 }
 
+/**
+ * Build a candidate ticket (pasangan calon)
+ */
+function candidateTicket(
+  candidateName: string,
+  ballotNumber: number,
+  overrides: Partial<{
+    runningMateName: string;
+    coalitionPartyIds: number[];
+    visionMissionIpfsHash: string;
+  }> = {}
+) {
+  return {
+    candidateName,
+    runningMateName: `${candidateName} Running Mate`,
+    ballotNumber,
+    coalitionPartyIds: [],
+    visionMissionIpfsHash: "QmVisiMisi" + ballotNumber,
+    ...overrides,
+  };
+}
+
 /**
  * Derive Voting Token Mint PDA
  */
@@ -104,6 +189,24 @@ function deriveVoterCredentialPDA(
   );
 }
 
+/**
+ * Derive NIK registry PDA
+ */
+function deriveNikRecordPDA(
+  electionPda: PublicKey,
+  nikCommitment: number[],
+  programId: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("nik_record"),
+      electionPda.toBuffer(),
+      Buffer.from(nikCommitment),
+    ],
+    programId
+  );
+}
+
 /**
  * Derive Ballot PDA
  */
@@ -120,6 +223,457 @@ function deriveBallotPDA(
   );
 }
 
+/**
+ * Derive Proposal PDA
+ */
+function deriveProposalPDA(
+  electionPda: PublicKey,
+  proposalId: number,
+  programId: PublicKey
+): [PublicKey, number] {
+  const idBuffer = Buffer.alloc(8);
+  idBuffer.writeBigUInt64LE(BigInt(proposalId));
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("proposal"), electionPda.toBuffer(), idBuffer],
+    programId
+  );
+}
+
+function deriveRegionPDA(
+  electionPda: PublicKey,
+  regionCode: number,
+  programId: PublicKey
+): [PublicKey, number] {
+  const codeBuffer = Buffer.alloc(8);
+  codeBuffer.writeBigUInt64LE(BigInt(regionCode));
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("region"), electionPda.toBuffer(), codeBuffer],
+    programId
+  );
+}
+
+function deriveRegionTallyPDA(
+  regionPda: PublicKey,
+  candidateId: number,
+  programId: PublicKey
+): [PublicKey, number] {
+  const candidateIdBuffer = Buffer.alloc(4);
+  candidateIdBuffer.writeUInt32LE(candidateId);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("region_tally"), regionPda.toBuffer(), candidateIdBuffer],
+    programId
+  );
+}
+
+// Order of the Ristretto group
+const RISTRETTO_ORDER =
+  2n ** 252n + 27742317777372353535851937790883648493n;
+
+function scalarFromBytes(bytes: Buffer): bigint {
+  return BigInt("0x" + Buffer.from(bytes).reverse().toString("hex")) % RISTRETTO_ORDER;
+}
+
+function scalarBytes(scalar: bigint): number[] {
+  return Array.from(
+    Buffer.from(scalar.toString(16).padStart(64, "0"), "hex").reverse()
+  );
+}
+
+function randomScalar(): bigint {
+  return scalarFromBytes(randomBytes(64)) || 1n;
+}
+
+function modOrder(value: bigint): bigint {
+  return ((value % RISTRETTO_ORDER) + RISTRETTO_ORDER) % RISTRETTO_ORDER;
+}
+
+/**
+ * Fiat-Shamir challenge as the program derives it: two domain-separated
+ * SHA-256 digests read as a 64-byte little-endian integer mod l
+ */
+function challengeScalar(domain: string, context: Buffer[], points: Uint8Array[]): bigint {
+  const wide = [0, 1].map((counter) =>
+    createHash("sha256")
+      .update(Buffer.concat([Buffer.from(domain), Buffer.from([counter]), ...context, ...points]))
+      .digest()
+  );
+  return scalarFromBytes(Buffer.concat(wide));
+}
+
+/**
+ * Chaum-Pedersen proof that log_g1(h1) == log_g2(h2) == x
+ */
+function proveEquality(
+  x: bigint,
+  [g1, h1]: [typeof RistrettoPoint.BASE, typeof RistrettoPoint.BASE],
+  [g2, h2]: [typeof RistrettoPoint.BASE, typeof RistrettoPoint.BASE],
+  domain: string,
+  context: Buffer[]
+): { challenge: number[]; response: number[] } {
+  const nonce = randomScalar();
+  const a = g1.multiply(nonce);
+  const b = g2.multiply(nonce);
+  const challenge = challengeScalar(
+    domain,
+    context,
+    [g1, h1, g2, h2, a, b].map((point) => point.toRawBytes())
+  );
+  return {
+    challenge: scalarBytes(challenge),
+    response: scalarBytes(modOrder(nonce + challenge * x)),
+  };
+}
+
+/**
+ * Encrypt a one-hot ballot for candidate index `choice` under the joint key,
+ * with a 0-or-1 proof per ciphertext (the other branch simulated) and a
+ * proof that the ciphertexts sum to 1
+ */
+function encryptBallot(
+  publicKey: typeof RistrettoPoint.BASE,
+  choice: number,
+  candidateCount: number,
+  context: Buffer[]
+) {
+  const G = RistrettoPoint.BASE;
+  const ciphertexts = [];
+  const validityProofs = [];
+  let randomnessSum = 0n;
+  let sumC1 = RistrettoPoint.ZERO;
+  let sumC2 = RistrettoPoint.ZERO;
+
+  for (let k = 0; k < candidateCount; k++) {
+    const r = randomScalar();
+    const c1 = G.multiply(r);
+    const c2 = k === choice ? publicKey.multiply(r).add(G) : publicKey.multiply(r);
+
+    const nonce = randomScalar();
+    const simulatedChallenge = randomScalar();
+    const simulatedResponse = randomScalar();
+    const simulatedH = k === choice ? c2 : c2.subtract(G);
+    const real = [G.multiply(nonce), publicKey.multiply(nonce)];
+    const simulated = [
+      G.multiply(simulatedResponse).subtract(c1.multiply(simulatedChallenge)),
+      publicKey.multiply(simulatedResponse).subtract(simulatedH.multiply(simulatedChallenge)),
+    ];
+    const [a0, b0, a1, b1] =
+      k === choice ? [...simulated, ...real] : [...real, ...simulated];
+    const challenge = challengeScalar(
+      "IDN-EVOTE-BALLOT",
+      context,
+      [publicKey, c1, c2, a0, b0, a1, b1].map((point) => point.toRawBytes())
+    );
+    const realChallenge = modOrder(challenge - simulatedChallenge);
+    const realResponse = modOrder(nonce + realChallenge * r);
+    const branches = [
+      { challenge: realChallenge, response: realResponse },
+      { challenge: simulatedChallenge, response: simulatedResponse },
+    ];
+    const [zero, one] = k === choice ? [branches[1], branches[0]] : branches;
+
+    ciphertexts.push({
+      c1: Array.from(c1.toRawBytes()),
+      c2: Array.from(c2.toRawBytes()),
+    });
+    validityProofs.push({
+      challenge0: scalarBytes(zero.challenge),
+      challenge1: scalarBytes(one.challenge),
+      response0: scalarBytes(zero.response),
+      response1: scalarBytes(one.response),
+    });
+    randomnessSum = modOrder(randomnessSum + r);
+    sumC1 = sumC1.add(c1);
+    sumC2 = sumC2.add(c2);
+  }
+
+  const sumProof = proveEquality(
+    randomnessSum,
+    [G, sumC1],
+    [publicKey, sumC2.subtract(G)],
+    "IDN-EVOTE-BALLOT-SUM",
+    context
+  );
+  return { ciphertexts, validityProofs, sumProof };
+}
+
+/**
+ * Issue and unblind a station-bound ballot token for `nullifier`, playing
+ * both the issuer (secret key 1, i.e. RISTRETTO_BASEPOINT) and the voter
+ */
+async function obtainBallotToken(
+  program: Program<Idngenelectevotingsolana>,
+  electionPda: PublicKey,
+  pollingStationPda: PublicKey,
+  voter: Keypair,
+  nullifier: Buffer
+): Promise<{ r: number[]; s: number[] }> {
+  const G = RistrettoPoint.BASE;
+  const stationTweak = challengeScalar(
+    "IDN-EVOTE-STATION-KEY",
+    [electionPda.toBuffer(), pollingStationPda.toBuffer()],
+    [Uint8Array.from(RISTRETTO_BASEPOINT)]
+  );
+  const stationSecret = (1n + stationTweak) % RISTRETTO_ORDER;
+  const stationKey = G.multiply(stationSecret);
+
+  // Two clauses; the issuer answers one
+  const clauses = [0, 1].map(() => {
+    const nonce = randomScalar();
+    const alpha = randomScalar();
+    const beta = randomScalar();
+    const commitment = G.multiply(nonce);
+    const r = commitment.add(G.multiply(alpha)).add(stationKey.multiply(beta));
+    const challenge = challengeScalar(
+      "IDN-EVOTE-BALLOT-TOKEN",
+      [electionPda.toBuffer()],
+      [stationKey.toRawBytes(), r.toRawBytes(), nullifier]
+    );
+    const blindedChallenge = (challenge + beta) % RISTRETTO_ORDER;
+    return { nonce, alpha, commitment, r, blindedChallenge };
+  });
+
+  const [voterCredentialPda] = deriveVoterCredentialPDA(
+    electionPda,
+    voter.publicKey,
+    program.programId
+  );
+  const [ballotTokenRequestPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("ballot_token"), electionPda.toBuffer(), voterCredentialPda.toBuffer()],
+    program.programId
+  );
+  const [votingTokenMintPda] = deriveVotingTokenMintPDA(electionPda, program.programId);
+
+  await program.methods
+    .requestBallotToken(
+      clauses.map((clause) => Array.from(clause.commitment.toRawBytes())),
+      clauses.map((clause) => scalarBytes(clause.blindedChallenge))
+    )
+    .accounts({
+      voter: voter.publicKey,
+      election: electionPda,
+      voterCredential: voterCredentialPda,
+      pollingStation: pollingStationPda,
+      ballotTokenRequest: ballotTokenRequestPda,
+      votingTokenMint: votingTokenMintPda,
+      voterTokenAccount: await getAssociatedTokenAddress(votingTokenMintPda, voter.publicKey),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([voter])
+    .rpc();
+
+  const clause = clauses[0];
+  const blindResponse =
+    (clause.nonce + clause.blindedChallenge * stationSecret) % RISTRETTO_ORDER;
+  await program.methods
+    .issueBallotToken(0, scalarBytes(blindResponse))
+    .accounts({
+      issuer: program.provider.publicKey,
+      election: electionPda,
+      ballotTokenRequest: ballotTokenRequestPda,
+    })
+    .rpc();
+
+  return {
+    r: Array.from(clause.r.toRawBytes()),
+    s: scalarBytes((blindResponse + clause.alpha) % RISTRETTO_ORDER),
+  };
+}
+
+/**
+ * One-time ballot key: the nullifier is the point uG, so only the holder of
+ * u can sign what a ballot cast with its token says
+ */
+function ballotKey(): { secret: bigint; nullifier: Buffer } {
+  const secret = randomScalar();
+  return {
+    secret,
+    nullifier: Buffer.from(RistrettoPoint.BASE.multiply(secret).toRawBytes()),
+  };
+}
+
+/**
+ * Sign a ballot's contents (kind byte, then the fixed-width parts the cast
+ * instruction lists) under its nullifier key
+ */
+function signBallotContents(
+  electionPda: PublicKey,
+  key: { secret: bigint; nullifier: Buffer },
+  contents: Buffer[]
+): { r: number[]; s: number[] } {
+  const nonce = randomScalar();
+  const r = RistrettoPoint.BASE.multiply(nonce);
+  const challenge = challengeScalar(
+    "IDN-EVOTE-BALLOT-CONTENTS",
+    [electionPda.toBuffer(), ...contents],
+    [key.nullifier, r.toRawBytes()]
+  );
+  return {
+    r: Array.from(r.toRawBytes()),
+    s: scalarBytes((nonce + challenge * key.secret) % RISTRETTO_ORDER),
+  };
+}
+
+/**
+ * Open a commissioner proposal and return its PDA
+ */
+async function createProposal(
+  program: Program<Idngenelectevotingsolana>,
+  electionPda: PublicKey,
+  action: any,
+  commissioner?: Keypair
+): Promise<PublicKey> {
+  const election = await program.account.election.fetch(electionPda);
+  const [proposalPda] = deriveProposalPDA(
+    electionPda,
+    election.proposalCount.toNumber(),
+    program.programId
+  );
+
+  const builder = program.methods.createProposal(action).accounts({
+    commissioner: commissioner
+      ? commissioner.publicKey
+      : program.provider.publicKey,
+    election: electionPda,
+    proposal: proposalPda,
+    systemProgram: SystemProgram.programId,
+  });
+  await (commissioner ? builder.signers([commissioner]) : builder).rpc();
+
+  return proposalPda;
+}
+
+/**
+ * Move an election to `status` through a commissioner proposal
+ */
+async function transitionElection(
+  program: Program<Idngenelectevotingsolana>,
+  electionPda: PublicKey,
+  status: any
+): Promise<void> {
+  const proposalPda = await createProposal(program, electionPda, {
+    transitionElection: { status },
+  });
+  await program.methods
+    .transitionElection(status)
+    .accounts({
+      commissioner: program.provider.publicKey,
+      election: electionPda,
+      proposal: proposalPda,
+    })
+    .rpc();
+}
+
+/**
+ * Create a region (and, below province level, under its parent) through a
+ * commissioner proposal and return its PDA
+ */
+async function createRegion(
+  program: Program<Idngenelectevotingsolana>,
+  electionPda: PublicKey,
+  regionCode: number,
+  name: string,
+  parentCode: number | null
+): Promise<PublicKey> {
+  const proposalPda = await createProposal(program, electionPda, {
+    createRegion: { regionCode: new BN(regionCode), name },
+  });
+  const [regionPda] = deriveRegionPDA(electionPda, regionCode, program.programId);
+  await program.methods
+    .createRegion(new BN(regionCode), name)
+    .accounts({
+      commissioner: program.provider.publicKey,
+      election: electionPda,
+      proposal: proposalPda,
+      region: regionPda,
+      parent:
+        parentCode === null
+          ? null
+          : deriveRegionPDA(electionPda, parentCode, program.programId)[0],
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+
+  return regionPda;
+}
+
+function derivePollingStationPDA(
+  electionPda: PublicKey,
+  regionCode: number,
+  tpsNumber: number,
+  programId: PublicKey
+): [PublicKey, number] {
+  const codeBuffer = Buffer.alloc(8);
+  codeBuffer.writeBigUInt64LE(BigInt(regionCode));
+  const tpsBuffer = Buffer.alloc(2);
+  tpsBuffer.writeUInt16LE(tpsNumber);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("polling_station"), electionPda.toBuffer(), codeBuffer, tpsBuffer],
+    programId
+  );
+}
+
+/**
+ * Create a polling station in a village through a commissioner proposal
+ * and return its PDA
+ */
+async function createPollingStation(
+  program: Program<Idngenelectevotingsolana>,
+  electionPda: PublicKey,
+  villageCode: number,
+  tpsNumber: number,
+  capacity: number
+): Promise<PublicKey> {
+  const proposalPda = await createProposal(program, electionPda, {
+    createPollingStation: { regionCode: new BN(villageCode), tpsNumber, capacity },
+  });
+  const [pollingStationPda] = derivePollingStationPDA(
+    electionPda,
+    villageCode,
+    tpsNumber,
+    program.programId
+  );
+  await program.methods
+    .createPollingStation(new BN(villageCode), tpsNumber, capacity)
+    .accounts({
+      commissioner: program.provider.publicKey,
+      election: electionPda,
+      proposal: proposalPda,
+      region: deriveRegionPDA(electionPda, villageCode, program.programId)[0],
+      pollingStation: pollingStationPda,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+
+  return pollingStationPda;
+}
+
+/**
+ * Build the message a registrar signs to attest a voter verification
+ */
+function attestationMessage(
+  electionPda: PublicKey,
+  voterPubkey: PublicKey,
+  pollingStation: PublicKey,
+  nikCommitment: number[],
+  biometricHash: number[],
+  aiScore: number,
+  timestamp: number
+): Buffer {
+  const timestampBuffer = Buffer.alloc(8);
+  timestampBuffer.writeBigInt64LE(BigInt(timestamp));
+  return Buffer.concat([
+    Buffer.from("IDN-EVOTE-ATTEST"),
+    electionPda.toBuffer(),
+    voterPubkey.toBuffer(),
+    pollingStation.toBuffer(),
+    Buffer.from(nikCommitment),
+    Buffer.from(biometricHash),
+    Buffer.from([aiScore]),
+    timestampBuffer,
+  ]);
+}
+
 /**
  * Airdrop SOL to an account
  */
@@ -140,7 +694,11 @@ async function airdrop(
  * Create test voter data
  */
 function createTestVoterData(voterPubkey: PublicKey) {
-  const nik = "1234567890123456"; // 16 digits
+  // 16 digits, unique per wallet (one NIK backs one credential)
+  const nik = sha256(voterPubkey.toString())
+    .slice(0, 16)
+    .map((byte) => byte % 10)
+    .join("");
   const biometricData = `biometric_${voterPubkey.toString().slice(0, 8)}`;
   const biometricHash = sha256(biometricData);
   const photoIpfsHash = `QmTest${voterPubkey.toString().slice(0, 20)}`;
@@ -153,6 +711,275 @@ function createTestVoterData(voterPubkey: PublicKey) {
   };
 }
 
+// ============================================================================
+// Warped-Clock Bank
+// ============================================================================
+
+// Papua keeps WIT hours, so polls there open at start_time
+const BANK_REGION_CHAIN = [
+  { code: 9171011001, name: "Gurabesi" },
+  { code: 917101, name: "Jayapura Utara" },
+  { code: 9171, name: "Kota Jayapura" },
+  { code: 91, name: "Papua" },
+];
+
+type Bank = {
+  context: ProgramTestContext;
+  provider: BankrunProvider;
+  program: Program<Idngenelectevotingsolana>;
+};
+
+type BankElection = {
+  electionPda: PublicKey;
+  votingTokenMintPda: PublicKey;
+  pollingStationPda: PublicKey;
+  regionChain: PublicKey[]; // Village first
+  startTime: number;
+  endTime: number;
+};
+
+/**
+ * Start an in-process bank with the workspace build of the program, for
+ * tests that must move past polling hours instead of waiting them out
+ */
+async function startBank(idl: Idngenelectevotingsolana): Promise<Bank> {
+  const context = await startAnchor(".", [], []);
+  const provider = new BankrunProvider(context);
+  const program = new Program<Idngenelectevotingsolana>(idl, provider);
+
+  await program.methods
+    .initializeRegistry()
+    .accounts({
+      payer: provider.wallet.publicKey,
+      registry: deriveElectionRegistryPDA(program.programId)[0],
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+
+  return { context, provider, program };
+}
+
+async function bankTime(bank: Bank): Promise<number> {
+  const clock = await bank.context.banksClient.getClock();
+  return Number(clock.unixTimestamp);
+}
+
+/**
+ * Move the bank clock to `unixTimestamp`, a slot on so that a transaction
+ * repeated after the warp gets a fresh blockhash
+ */
+async function warpTo(bank: Bank, unixTimestamp: number): Promise<void> {
+  const { slot } = await bank.context.banksClient.getClock();
+  bank.context.warpToSlot(slot + 1n);
+  const clock = await bank.context.banksClient.getClock();
+  bank.context.setClock(
+    new Clock(
+      clock.slot,
+      clock.epochStartTimestamp,
+      clock.epoch,
+      clock.leaderScheduleEpoch,
+      BigInt(unixTimestamp)
+    )
+  );
+}
+
+function fundOnBank(bank: Bank, publicKey: PublicKey): void {
+  bank.context.setAccount(publicKey, {
+    lamports: 10 * LAMPORTS_PER_SOL,
+    data: Buffer.alloc(0),
+    owner: SystemProgram.programId,
+    executable: false,
+  });
+}
+
+/**
+ * Name of the program error a transaction failed with; banks errors carry
+ * no logs, so only the custom error number is recovered from them
+ */
+function programErrorCode(program: Program<Idngenelectevotingsolana>, error: any): string {
+  if (error.error?.errorCode) {
+    return error.error.errorCode.code;
+  }
+  const match = /custom program error: (0x[0-9a-f]+)/.exec(String(error.message));
+  const code = error.code ?? (match ? parseInt(match[1], 16) : undefined);
+  return program.idl.errors?.find((idlError) => idlError.code === code)?.name ?? String(error);
+}
+
+/**
+ * Register a verified voter at `pollingStationPda`, co-signed by the registrar
+ */
+async function verifyTestVoter(
+  program: Program<Idngenelectevotingsolana>,
+  electionPda: PublicKey,
+  pollingStationPda: PublicKey,
+  voter: Keypair,
+  registrar: Keypair,
+  timestamp: number
+): Promise<void> {
+  const election = await program.account.election.fetch(electionPda);
+  const [votingTokenMintPda] = deriveVotingTokenMintPDA(electionPda, program.programId);
+  const voterData = createTestVoterData(voter.publicKey);
+  const commitment = nikCommitment(Buffer.from(election.nikSalt), voterData.nik);
+
+  await program.methods
+    .verifyVoter(
+      commitment,
+      voterData.biometricHash,
+      voterData.photoIpfsHash,
+      new BN(timestamp),
+      voterData.aiScore
+    )
+    .accounts({
+      voter: voter.publicKey,
+      election: electionPda,
+      voterCredential: deriveVoterCredentialPDA(electionPda, voter.publicKey, program.programId)[0],
+      nikRecord: deriveNikRecordPDA(electionPda, commitment, program.programId)[0],
+      pollingStation: pollingStationPda,
+      votingTokenMint: votingTokenMintPda,
+      voterTokenAccount: await getAssociatedTokenAddress(votingTokenMintPda, voter.publicKey),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      registrar: registrar.publicKey,
+      instructionsSysvar: null,
+    })
+    .signers([voter, registrar])
+    .rpc();
+}
+
+/**
+ * Create the Papua region chain, province first, and TPS 001 in its village
+ */
+async function createBankRegionChain(
+  program: Program<Idngenelectevotingsolana>,
+  electionPda: PublicKey
+): Promise<{ regionChain: PublicKey[]; pollingStationPda: PublicKey }> {
+  const regionChain: PublicKey[] = [];
+  let parentCode: number | null = null;
+  for (const region of [...BANK_REGION_CHAIN].reverse()) {
+    regionChain.unshift(
+      await createRegion(program, electionPda, region.code, region.name, parentCode)
+    );
+    parentCode = region.code;
+  }
+  const pollingStationPda = await createPollingStation(
+    program,
+    electionPda,
+    BANK_REGION_CHAIN[0].code,
+    1,
+    300
+  );
+  return { regionChain, pollingStationPda };
+}
+
+/**
+ * Set up an election on the bank up to Registration: candidates, registrar,
+ * the Papua region chain with TPS 001, the ballot issuer (secret key 1) for
+ * token modes, then the verified `voters`. Polls open an hour later and
+ * stay open a day
+ */
+async function setupBankElection(
+  bank: Bank,
+  options: {
+    name: string;
+    votingMode: any;
+    candidateIds: number[];
+    registrar: Keypair;
+    voters: Keypair[];
+    commissioners?: PublicKey[];
+  }
+): Promise<BankElection> {
+  const { program, provider } = bank;
+  const now = await bankTime(bank);
+  const startTime = now + 3600;
+  const endTime = startTime + 86400;
+  const [electionPda] = await deriveNextElectionPDA(program);
+  const [votingTokenMintPda] = deriveVotingTokenMintPDA(electionPda, program.programId);
+
+  await program.methods
+    .initializeElection(
+      options.name,
+      new BN(now - 60),
+      new BN(startTime),
+      new BN(startTime),
+      new BN(endTime),
+      options.commissioners ?? [provider.wallet.publicKey],
+      1,
+      options.votingMode
+    )
+    .accounts({
+      authority: provider.wallet.publicKey,
+      registry: deriveElectionRegistryPDA(program.programId)[0],
+      election: electionPda,
+      votingTokenMint: votingTokenMintPda,
+      systemProgram: SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+    })
+    .rpc();
+
+  for (const candidateId of options.candidateIds) {
+    const ticket = candidateTicket(`${options.name} ${candidateId}`, candidateId);
+    const proposalPda = await createProposal(program, electionPda, {
+      registerCandidate: { candidateId, ticket },
+    });
+    await program.methods
+      .registerCandidate(ticket, candidateId)
+      .accounts({
+        commissioner: provider.wallet.publicKey,
+        election: electionPda,
+        proposal: proposalPda,
+        candidate: deriveCandidatePDA(electionPda, candidateId, program.programId)[0],
+        ballotNumberRecord: deriveBallotNumberPDA(electionPda, candidateId, program.programId)[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  const registrarProposalPda = await createProposal(program, electionPda, {
+    addRegistrar: { registrar: options.registrar.publicKey },
+  });
+  await program.methods
+    .addRegistrar(options.registrar.publicKey)
+    .accounts({
+      commissioner: provider.wallet.publicKey,
+      election: electionPda,
+      proposal: registrarProposalPda,
+    })
+    .rpc();
+
+  const { regionChain, pollingStationPda } = await createBankRegionChain(program, electionPda);
+
+  if (
+    "plurality" in options.votingMode ||
+    "commitReveal" in options.votingMode ||
+    "rankedChoice" in options.votingMode
+  ) {
+    const issuerProposalPda = await createProposal(program, electionPda, {
+      setBallotIssuer: {
+        issuerKey: RISTRETTO_BASEPOINT,
+        issuerAuthority: provider.wallet.publicKey,
+      },
+    });
+    await program.methods
+      .setBallotIssuer(RISTRETTO_BASEPOINT, provider.wallet.publicKey)
+      .accounts({
+        commissioner: provider.wallet.publicKey,
+        election: electionPda,
+        proposal: issuerProposalPda,
+      })
+      .rpc();
+  }
+
+  await transitionElection(program, electionPda, { registration: {} });
+  for (const voter of options.voters) {
+    fundOnBank(bank, voter.publicKey);
+    await verifyTestVoter(program, electionPda, pollingStationPda, voter, options.registrar, now);
+  }
+
+  return { electionPda, votingTokenMintPda, pollingStationPda, regionChain, startTime, endTime };
+}
+
 // ============================================================================
 // Test Suite
 // ============================================================================
@@ -166,6 +993,7 @@ describe("E-Voting Smart Contract Tests", () => {
     .Idngenelectevotingsolana as Program<Idngenelectevotingsolana>;
 
   // Test accounts
+  const [registryPda] = deriveElectionRegistryPDA(program.programId);
   let electionPda: PublicKey;
   let electionBump: number;
   let votingTokenMintPda: PublicKey;
@@ -174,6 +1002,9 @@ describe("E-Voting Smart Contract Tests", () => {
   // Test keypairs
   const voter1 = Keypair.generate();
   const voter2 = Keypair.generate();
+  const voter3 = Keypair.generate();
+  const voter2Replacement = Keypair.generate();
+  const registrar = Keypair.generate();
   const unauthorizedUser = Keypair.generate();
 
   // Candidate PDAs
@@ -181,7 +1012,17 @@ describe("E-Voting Smart Contract Tests", () => {
   let candidate2Pda: PublicKey;
   let candidate3Pda: PublicKey;
 
+  // Per-election NIK salt (read from the election account)
+  let nikSalt: Buffer;
+
+  // Region chain of the test voters' village, village first
+  const VILLAGE_CODE = 3171011001; // Gambir, Gambir, Jakarta Pusat
+  let regionChain: PublicKey[];
+  let pollingStationPda: PublicKey; // TPS 001 in that village
+
   // Election timing
+  let registrationStartTime: number;
+  let registrationEndTime: number;
   let electionStartTime: number;
   let electionEndTime: number;
 
@@ -192,11 +1033,21 @@ describe("E-Voting Smart Contract Tests", () => {
   before(async () => {
     console.log("🔧 Setting up test environment...\n");
 
+    // The registry is created once per deployment
+    const registryInfo = await provider.connection.getAccountInfo(registryPda);
+    if (!registryInfo) {
+      await program.methods
+        .initializeRegistry()
+        .accounts({
+          payer: provider.wallet.publicKey,
+          registry: registryPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
     // Derive PDAs
-    [electionPda, electionBump] = deriveElectionPDA(
-      TEST_ELECTION_NAME,
-      program.programId
-    );
+    [electionPda, electionBump] = await deriveNextElectionPDA(program);
     [votingTokenMintPda, votingTokenMintBump] = deriveVotingTokenMintPDA(
       electionPda,
       program.programId
@@ -208,8 +1059,11 @@ describe("E-Voting Smart Contract Tests", () => {
     [candidate3Pda] = deriveCandidatePDA(electionPda, 3, program.programId);
 
     // Calculate election times
+    registrationStartTime = getCurrentTimestamp() - 60;
     electionStartTime = getCurrentTimestamp() + FUTURE_START_OFFSET;
     electionEndTime = electionStartTime + ELECTION_DURATION;
+    // Closes early so the extension path can be exercised
+    registrationEndTime = electionStartTime - 15;
 
     console.log("📋 Test Configuration:");
     console.log(`   Election Name: ${TEST_ELECTION_NAME}`);
@@ -236,17 +1090,24 @@ describe("E-Voting Smart Contract Tests", () => {
     it("Should initialize an election successfully", async () => {
       const commissioners = [provider.wallet.publicKey];
       const requiredSignatures = 1;
+      const registryBefore = await program.account.electionRegistry.fetch(
+        registryPda
+      );
 
       const tx = await program.methods
         .initializeElection(
           TEST_ELECTION_NAME,
+          new BN(registrationStartTime),
+          new BN(registrationEndTime),
           new BN(electionStartTime),
           new BN(electionEndTime),
           commissioners,
-          requiredSignatures
+          requiredSignatures,
+          { plurality: {} }
         )
         .accounts({
           authority: provider.wallet.publicKey,
+          registry: registryPda,
           election: electionPda,
           votingTokenMint: votingTokenMintPda,
           systemProgram: SystemProgram.programId,
@@ -264,21 +1125,69 @@ describe("E-Voting Smart Contract Tests", () => {
         provider.wallet.publicKey.toString()
       );
       expect(election.electionName).to.equal(TEST_ELECTION_NAME);
-      expect(election.isActive).to.equal(false);
+      expect(election.registrationEnd.toNumber()).to.equal(
+        registrationEndTime
+      );
+      expect(election.status).to.deep.equal({ draft: {} });
       expect(election.totalRegisteredVoters.toNumber()).to.equal(0);
       expect(election.totalVotesCast.toNumber()).to.equal(0);
       expect(election.commissioners.length).to.equal(1);
       expect(election.requiredSignatures).to.equal(requiredSignatures);
-    });
 
-    it("Should fail with invalid election period (end before start)", async () => {
-      const badElectionName = "BadElection1";
-      const [badElectionPda] = deriveElectionPDA(
-        badElectionName,
-        program.programId
+      // The election takes the registry's next ID and the counter advances
+      expect(election.electionId.toString()).to.equal(
+        registryBefore.electionCount.toString()
       );
-      const [badMintPda] = deriveVotingTokenMintPDA(
-        badElectionPda,
+      const registryAfter = await program.account.electionRegistry.fetch(
+        registryPda
+      );
+      expect(registryAfter.electionCount.toString()).to.equal(
+        registryBefore.electionCount.addn(1).toString()
+      );
+    });
+
+    it("Should accept names longer than the 32-byte seed limit", async () => {
+      // Names are display metadata only; the PDA comes from the election ID
+      const longName = "Pemilihan Umum Presiden dan Wakil Presiden 2029";
+      const [longNamePda] = await deriveNextElectionPDA(program);
+      const [longNameMintPda] = deriveVotingTokenMintPDA(
+        longNamePda,
+        program.programId
+      );
+
+      await program.methods
+        .initializeElection(
+          longName,
+          new BN(registrationStartTime),
+          new BN(registrationEndTime),
+          new BN(electionStartTime),
+          new BN(electionEndTime),
+          [provider.wallet.publicKey],
+          1,
+          { plurality: {} }
+        )
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: registryPda,
+          election: longNamePda,
+          votingTokenMint: longNameMintPda,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+
+      const election = await program.account.election.fetch(longNamePda);
+      expect(Buffer.byteLength(longName)).to.be.greaterThan(32);
+      expect(election.electionName).to.equal(longName);
+      console.log("   ✅ Long election name accepted");
+    });
+
+    it("Should fail with invalid election period (end before start)", async () => {
+      const badElectionName = "BadElection1";
+      const [badElectionPda] = await deriveNextElectionPDA(program);
+      const [badMintPda] = deriveVotingTokenMintPDA(
+        badElectionPda,
         program.programId
       );
 
@@ -289,13 +1198,17 @@ describe("E-Voting Smart Contract Tests", () => {
         await program.methods
           .initializeElection(
             badElectionName,
+            new BN(startTime - 3600),
+            new BN(startTime),
             new BN(startTime),
             new BN(endTime),
             [provider.wallet.publicKey],
-            1
+            1,
+            { plurality: {} }
           )
           .accounts({
             authority: provider.wallet.publicKey,
+            registry: registryPda,
             election: badElectionPda,
             votingTokenMint: badMintPda,
             systemProgram: SystemProgram.programId,
@@ -311,12 +1224,95 @@ describe("E-Voting Smart Contract Tests", () => {
       }
     });
 
-    it("Should fail with empty commissioners list", async () => {
-      const badElectionName = "BadElection2";
-      const [badElectionPda] = deriveElectionPDA(
-        badElectionName,
+    it("Should fail with polling too short for every time zone", async () => {
+      const [badElectionPda] = await deriveNextElectionPDA(program);
+      const [badMintPda] = deriveVotingTokenMintPDA(badElectionPda, program.programId);
+      const startTime = getCurrentTimestamp() + 1000;
+
+      // WIB opens two hours after WIT, and WIT closes two hours before WIB,
+      // so two hours leaves one zone no polling time; membership-proof
+      // ballots need polls open everywhere, so need over four
+      for (const { votingMode, hours } of [
+        { votingMode: { plurality: {} }, hours: 2 },
+        { votingMode: { zkMembership: {} }, hours: 4 },
+      ]) {
+        try {
+          await program.methods
+            .initializeElection(
+              "BadElectionShort",
+              new BN(startTime - 3600),
+              new BN(startTime),
+              new BN(startTime),
+              new BN(startTime + hours * 3600),
+              [provider.wallet.publicKey],
+              1,
+              votingMode
+            )
+            .accounts({
+              authority: provider.wallet.publicKey,
+              registry: registryPda,
+              election: badElectionPda,
+              votingTokenMint: badMintPda,
+              systemProgram: SystemProgram.programId,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            })
+            .rpc();
+
+          expect.fail("Should have thrown InvalidElectionPeriod error");
+        } catch (error: any) {
+          expect(error.error.errorCode.code).to.equal("InvalidElectionPeriod");
+        }
+      }
+      console.log("   ✅ Correctly rejected polling windows of 2h, and 4h for ZK ballots");
+    });
+
+    it("Should fail with registration closing after voting starts", async () => {
+      const badElectionName = "BadElection3";
+      const [badElectionPda] = await deriveNextElectionPDA(program);
+      const [badMintPda] = deriveVotingTokenMintPDA(
+        badElectionPda,
         program.programId
       );
+
+      const startTime = getCurrentTimestamp() + 1000;
+      const endTime = startTime + 86400;
+
+      try {
+        await program.methods
+          .initializeElection(
+            badElectionName,
+            new BN(startTime - 3600),
+            new BN(startTime + 1), // Registration overlaps polling
+            new BN(startTime),
+            new BN(endTime),
+            [provider.wallet.publicKey],
+            1,
+            { plurality: {} }
+          )
+          .accounts({
+            authority: provider.wallet.publicKey,
+            registry: registryPda,
+            election: badElectionPda,
+            votingTokenMint: badMintPda,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown InvalidRegistrationPeriod error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal(
+          "InvalidRegistrationPeriod"
+        );
+        console.log("   ✅ Correctly rejected overlapping registration window");
+      }
+    });
+
+    it("Should fail with empty commissioners list", async () => {
+      const badElectionName = "BadElection2";
+      const [badElectionPda] = await deriveNextElectionPDA(program);
       const [badMintPda] = deriveVotingTokenMintPDA(
         badElectionPda,
         program.programId
@@ -329,13 +1325,17 @@ describe("E-Voting Smart Contract Tests", () => {
         await program.methods
           .initializeElection(
             badElectionName,
+            new BN(startTime - 3600),
+            new BN(startTime),
             new BN(startTime),
             new BN(endTime),
             [], // Empty commissioners
-            1
+            1,
+            { plurality: {} }
           )
           .accounts({
             authority: provider.wallet.publicKey,
+            registry: registryPda,
             election: badElectionPda,
             votingTokenMint: badMintPda,
             systemProgram: SystemProgram.programId,
@@ -353,7 +1353,7 @@ describe("E-Voting Smart Contract Tests", () => {
 
     it("Should fail with election name too long", async () => {
       const longName = "A".repeat(101); // 101 chars, max is 100
-      const [badElectionPda] = deriveElectionPDA(longName, program.programId);
+      const [badElectionPda] = await deriveNextElectionPDA(program);
       const [badMintPda] = deriveVotingTokenMintPDA(
         badElectionPda,
         program.programId
@@ -366,13 +1366,17 @@ describe("E-Voting Smart Contract Tests", () => {
         await program.methods
           .initializeElection(
             longName,
+            new BN(startTime - 3600),
+            new BN(startTime),
             new BN(startTime),
             new BN(endTime),
             [provider.wallet.publicKey],
-            1
+            1,
+            { plurality: {} }
           )
           .accounts({
             authority: provider.wallet.publicKey,
+            registry: registryPda,
             election: badElectionPda,
             votingTokenMint: badMintPda,
             systemProgram: SystemProgram.programId,
@@ -397,13 +1401,24 @@ describe("E-Voting Smart Contract Tests", () => {
     it("Should register candidate 1 successfully", async () => {
       const candidateId = 1;
       const candidateName = "Candidate Alpha - Party A";
+      const ticket = candidateTicket(candidateName, 1, {
+        runningMateName: "Running Mate Alpha",
+        coalitionPartyIds: [1, 2, 5],
+        visionMissionIpfsHash: "QmVisiMisiAlpha",
+      });
+
+      const proposalPda = await createProposal(program, electionPda, {
+        registerCandidate: { candidateId, ticket },
+      });
 
       const tx = await program.methods
-        .registerCandidate(candidateName, candidateId)
+        .registerCandidate(ticket, candidateId)
         .accounts({
-          authority: provider.wallet.publicKey,
+          commissioner: provider.wallet.publicKey,
           election: electionPda,
+          proposal: proposalPda,
           candidate: candidate1Pda,
+          ballotNumberRecord: deriveBallotNumberPDA(electionPda, 1, program.programId)[0],
           systemProgram: SystemProgram.programId,
         })
         .rpc();
@@ -416,19 +1431,35 @@ describe("E-Voting Smart Contract Tests", () => {
       expect(candidate.election.toString()).to.equal(electionPda.toString());
       expect(candidate.candidateId).to.equal(candidateId);
       expect(candidate.candidateName).to.equal(candidateName);
+      expect(candidate.runningMateName).to.equal("Running Mate Alpha");
+      expect(candidate.ballotNumber).to.equal(1);
+      expect(candidate.coalitionPartyIds).to.deep.equal([1, 2, 5]);
+      expect(candidate.visionMissionIpfsHash).to.equal("QmVisiMisiAlpha");
       expect(candidate.voteCount.toNumber()).to.equal(0);
+
+      const record = await program.account.ballotNumberRecord.fetch(
+        deriveBallotNumberPDA(electionPda, 1, program.programId)[0]
+      );
+      expect(record.candidate.toString()).to.equal(candidate1Pda.toString());
     });
 
     it("Should register candidate 2 successfully", async () => {
       const candidateId = 2;
       const candidateName = "Candidate Beta - Party B";
+      const ticket = candidateTicket(candidateName, 2, { coalitionPartyIds: [3] });
+
+      const proposalPda = await createProposal(program, electionPda, {
+        registerCandidate: { candidateId, ticket },
+      });
 
       const tx = await program.methods
-        .registerCandidate(candidateName, candidateId)
+        .registerCandidate(ticket, candidateId)
         .accounts({
-          authority: provider.wallet.publicKey,
+          commissioner: provider.wallet.publicKey,
           election: electionPda,
+          proposal: proposalPda,
           candidate: candidate2Pda,
+          ballotNumberRecord: deriveBallotNumberPDA(electionPda, 2, program.programId)[0],
           systemProgram: SystemProgram.programId,
         })
         .rpc();
@@ -442,13 +1473,20 @@ describe("E-Voting Smart Contract Tests", () => {
     it("Should register candidate 3 successfully", async () => {
       const candidateId = 3;
       const candidateName = "Candidate Gamma - Independent";
+      const ticket = candidateTicket(candidateName, 3);
+
+      const proposalPda = await createProposal(program, electionPda, {
+        registerCandidate: { candidateId, ticket },
+      });
 
       const tx = await program.methods
-        .registerCandidate(candidateName, candidateId)
+        .registerCandidate(ticket, candidateId)
         .accounts({
-          authority: provider.wallet.publicKey,
+          commissioner: provider.wallet.publicKey,
           election: electionPda,
+          proposal: proposalPda,
           candidate: candidate3Pda,
+          ballotNumberRecord: deriveBallotNumberPDA(electionPda, 3, program.programId)[0],
           systemProgram: SystemProgram.programId,
         })
         .rpc();
@@ -459,27 +1497,78 @@ describe("E-Voting Smart Contract Tests", () => {
     it("Should fail with candidate name too long", async () => {
       const candidateId = 99;
       const longName = "X".repeat(101); // 101 chars
-      const [badCandidatePda] = deriveCandidatePDA(
+      try {
+        // The name is rejected as soon as the proposal is opened
+        await createProposal(program, electionPda, {
+          registerCandidate: { candidateId, ticket: candidateTicket(longName, 99) },
+        });
+
+        expect.fail("Should have thrown NameTooLong error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NameTooLong");
+        console.log("   ✅ Correctly rejected candidate name too long");
+      }
+    });
+
+    it("Should reject a ballot number already taken by another ticket", async () => {
+      const candidateId = 4;
+      const [candidatePda] = deriveCandidatePDA(
         electionPda,
         candidateId,
         program.programId
       );
+      // Ballot number 1 already belongs to candidate 1
+      const ticket = candidateTicket("Candidate Delta", 1);
+      const proposalPda = await createProposal(program, electionPda, {
+        registerCandidate: { candidateId, ticket },
+      });
 
       try {
         await program.methods
-          .registerCandidate(longName, candidateId)
+          .registerCandidate(ticket, candidateId)
           .accounts({
-            authority: provider.wallet.publicKey,
+            commissioner: provider.wallet.publicKey,
             election: electionPda,
-            candidate: badCandidatePda,
+            proposal: proposalPda,
+            candidate: candidatePda,
+            ballotNumberRecord: deriveBallotNumberPDA(electionPda, 1, program.programId)[0],
             systemProgram: SystemProgram.programId,
           })
           .rpc();
 
-        expect.fail("Should have thrown NameTooLong error");
+        expect.fail("Should have thrown DuplicateBallotNumber error");
       } catch (error: any) {
-        expect(error.error.errorCode.code).to.equal("NameTooLong");
-        console.log("   ✅ Correctly rejected candidate name too long");
+        expect(error.error.errorCode.code).to.equal("DuplicateBallotNumber");
+        console.log("   ✅ Correctly rejected duplicate ballot number");
+      }
+    });
+
+    it("Should reject ballot number zero", async () => {
+      try {
+        await createProposal(program, electionPda, {
+          registerCandidate: { candidateId: 98, ticket: candidateTicket("Candidate Zero", 0) },
+        });
+
+        expect.fail("Should have thrown InvalidBallotNumber error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidBallotNumber");
+        console.log("   ✅ Correctly rejected ballot number zero");
+      }
+    });
+
+    it("Should reject a coalition listing a party twice", async () => {
+      try {
+        await createProposal(program, electionPda, {
+          registerCandidate: {
+            candidateId: 97,
+            ticket: candidateTicket("Candidate Echo", 7, { coalitionPartyIds: [4, 4] }),
+          },
+        });
+
+        expect.fail("Should have thrown InvalidCoalition error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidCoalition");
+        console.log("   ✅ Correctly rejected duplicate coalition party");
       }
     });
   });
@@ -489,6 +1578,56 @@ describe("E-Voting Smart Contract Tests", () => {
   // ============================================================================
 
   describe("3. Verify Voter", () => {
+    before(async () => {
+      await transitionElection(program, electionPda, { registration: {} });
+
+      const extendProposalPda = await createProposal(program, electionPda, {
+        extendRegistration: { registrationEnd: new BN(electionStartTime) },
+      });
+      await program.methods
+        .extendRegistration(new BN(electionStartTime))
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: electionPda,
+          proposal: extendProposalPda,
+        })
+        .rpc();
+
+      const extended = await program.account.election.fetch(electionPda);
+      expect(extended.registrationEnd.toNumber()).to.equal(electionStartTime);
+
+      const proposalPda = await createProposal(program, electionPda, {
+        addRegistrar: { registrar: registrar.publicKey },
+      });
+      await program.methods
+        .addRegistrar(registrar.publicKey)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: electionPda,
+          proposal: proposalPda,
+        })
+        .rpc();
+
+      const province = await createRegion(program, electionPda, 31, "DKI Jakarta", null);
+      const regency = await createRegion(program, electionPda, 3171, "Kota Jakarta Pusat", 31);
+      const district = await createRegion(program, electionPda, 317101, "Gambir", 3171);
+      const village = await createRegion(program, electionPda, VILLAGE_CODE, "Gambir", 317101);
+      regionChain = [village, district, regency, province];
+
+      const villageRegion = await program.account.region.fetch(village);
+      expect(villageRegion.level).to.deep.equal({ village: {} });
+      expect(villageRegion.parent.toString()).to.equal(district.toString());
+      expect(villageRegion.timeZone).to.deep.equal({ wib: {} });
+
+      pollingStationPda = await createPollingStation(program, electionPda, VILLAGE_CODE, 1, 300);
+
+      const election = await program.account.election.fetch(electionPda);
+      nikSalt = Buffer.from(election.nikSalt);
+      expect(election.registrars.map((r) => r.toString())).to.include(
+        registrar.publicKey.toString()
+      );
+    });
+
     it("Should verify voter 1 successfully", async () => {
       const voterData = createTestVoterData(voter1.publicKey);
       const [voterCredentialPda] = deriveVoterCredentialPDA(
@@ -505,7 +1644,7 @@ describe("E-Voting Smart Contract Tests", () => {
 
       const tx = await program.methods
         .verifyVoter(
-          voterData.nik,
+          nikCommitment(nikSalt, voterData.nik),
           voterData.biometricHash,
           voterData.photoIpfsHash,
           new BN(timestamp),
@@ -515,13 +1654,21 @@ describe("E-Voting Smart Contract Tests", () => {
           voter: voter1.publicKey,
           election: electionPda,
           voterCredential: voterCredentialPda,
+          nikRecord: deriveNikRecordPDA(
+            electionPda,
+            nikCommitment(nikSalt, voterData.nik),
+            program.programId
+          )[0],
+          pollingStation: pollingStationPda,
           votingTokenMint: votingTokenMintPda,
           voterTokenAccount: voterTokenAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
+          registrar: registrar.publicKey,
+          instructionsSysvar: null,
         })
-        .signers([voter1])
+        .signers([voter1, registrar])
         .rpc();
 
       console.log(`   ✅ Verify voter 1 tx: ${tx.slice(0, 20)}...`);
@@ -539,12 +1686,63 @@ describe("E-Voting Smart Contract Tests", () => {
       expect(credential.hasVoted).to.equal(false);
       expect(credential.aiConfidenceScore).to.equal(voterData.aiScore);
       expect(credential.verificationCode.length).to.be.greaterThan(0);
+      expect(credential.region.toString()).to.equal(regionChain[0].toString());
+      expect(credential.pollingStation.toString()).to.equal(
+        pollingStationPda.toString()
+      );
+
+      // Only the keyed commitment is stored, never a plain NIK hash
+      expect(credential.voterNikCommitment).to.deep.equal(
+        nikCommitment(nikSalt, voterData.nik)
+      );
+      expect(credential.voterNikCommitment).to.not.deep.equal(
+        sha256(voterData.nik)
+      );
 
       console.log(`   📝 Verification Code: ${credential.verificationCode}`);
 
+      // Voting token is minted frozen (non-transferable)
+      const tokenAccount = await getAccount(
+        provider.connection,
+        voterTokenAccount
+      );
+      expect(Number(tokenAccount.amount)).to.equal(1);
+      expect(tokenAccount.isFrozen).to.equal(true);
+
       // Verify election stats updated
       const election = await program.account.election.fetch(electionPda);
       expect(election.totalRegisteredVoters.toNumber()).to.equal(1);
+      const station = await program.account.pollingStation.fetch(pollingStationPda);
+      expect(station.registeredVoters).to.equal(1);
+    });
+
+    it("Should not allow transferring a voting token", async () => {
+      const voterTokenAccount = await getAssociatedTokenAddress(
+        votingTokenMintPda,
+        voter1.publicKey
+      );
+      const recipient = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        voter1,
+        votingTokenMintPda,
+        unauthorizedUser.publicKey
+      );
+
+      try {
+        await transfer(
+          provider.connection,
+          voter1,
+          voterTokenAccount,
+          recipient.address,
+          voter1,
+          1
+        );
+
+        expect.fail("Should have rejected transfer of a frozen token");
+      } catch (error: any) {
+        expect(error.message).to.not.include("Should have rejected");
+        console.log("   ✅ Voting token cannot be transferred");
+      }
     });
 
     it("Should verify voter 2 successfully", async () => {
@@ -563,7 +1761,7 @@ describe("E-Voting Smart Contract Tests", () => {
 
       const tx = await program.methods
         .verifyVoter(
-          voterData.nik,
+          nikCommitment(nikSalt, voterData.nik),
           voterData.biometricHash,
           voterData.photoIpfsHash,
           new BN(timestamp),
@@ -573,13 +1771,21 @@ describe("E-Voting Smart Contract Tests", () => {
           voter: voter2.publicKey,
           election: electionPda,
           voterCredential: voterCredentialPda,
+          nikRecord: deriveNikRecordPDA(
+            electionPda,
+            nikCommitment(nikSalt, voterData.nik),
+            program.programId
+          )[0],
+          pollingStation: pollingStationPda,
           votingTokenMint: votingTokenMintPda,
           voterTokenAccount: voterTokenAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
+          registrar: registrar.publicKey,
+          instructionsSysvar: null,
         })
-        .signers([voter2])
+        .signers([voter2, registrar])
         .rpc();
 
       console.log(`   ✅ Verify voter 2 tx: ${tx.slice(0, 20)}...`);
@@ -589,7 +1795,7 @@ describe("E-Voting Smart Contract Tests", () => {
       expect(election.totalRegisteredVoters.toNumber()).to.equal(2);
     });
 
-    it("Should fail with invalid NIK (wrong length)", async () => {
+    it("Should fail with invalid AI confidence score (> 100)", async () => {
       const badVoter = Keypair.generate();
       await airdrop(provider.connection, badVoter.publicKey);
 
@@ -603,40 +1809,146 @@ describe("E-Voting Smart Contract Tests", () => {
         badVoter.publicKey
       );
 
-      const invalidNik = "12345"; // Only 5 digits, should be 16
-
       try {
         await program.methods
           .verifyVoter(
-            invalidNik,
+            nikCommitment(nikSalt, "1234567890123456"),
             Array(32).fill(0),
             "QmTest123",
             new BN(getCurrentTimestamp()),
-            90
+            150 // Invalid: > 100
           )
           .accounts({
             voter: badVoter.publicKey,
             election: electionPda,
             voterCredential: voterCredentialPda,
+            nikRecord: deriveNikRecordPDA(
+              electionPda,
+              nikCommitment(nikSalt, "1234567890123456"),
+              program.programId
+            )[0],
+            pollingStation: pollingStationPda,
             votingTokenMint: votingTokenMintPda,
             voterTokenAccount: voterTokenAccount,
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
+            registrar: registrar.publicKey,
+            instructionsSysvar: null,
           })
-          .signers([badVoter])
+          .signers([badVoter, registrar])
           .rpc();
 
-        expect.fail("Should have thrown InvalidNIK error");
+        expect.fail("Should have thrown InvalidConfidenceScore error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidConfidenceScore");
+        console.log("   ✅ Correctly rejected invalid confidence score");
+      }
+    });
+
+    it("Should reject a region code outside the Kemendagri levels", async () => {
+      try {
+        // 3 digits: between a province and a regency code
+        await createProposal(program, electionPda, {
+          createRegion: { regionCode: new BN(317), name: "Invalid" },
+        });
+
+        expect.fail("Should have thrown InvalidRegionCode error");
       } catch (error: any) {
-        expect(error.error.errorCode.code).to.equal("InvalidNIK");
-        console.log("   ✅ Correctly rejected invalid NIK");
+        expect(error.error.errorCode.code).to.equal("InvalidRegionCode");
+        console.log("   ✅ Correctly rejected invalid region code");
       }
     });
 
-    it("Should fail with invalid AI confidence score (> 100)", async () => {
+    it("Should give regions their province's time zone", async () => {
+      await createRegion(program, electionPda, 91, "Papua", null);
+      const city = await createRegion(program, electionPda, 9171, "Kota Jayapura", 91);
+
+      const region = await program.account.region.fetch(city);
+      expect(region.timeZone).to.deep.equal({ wit: {} });
+      console.log("   ✅ Jayapura votes on WIT hours");
+    });
+
+    it("Should only open polling stations in a village", async () => {
+      const districtCode = 317101;
+      const proposalPda = await createProposal(program, electionPda, {
+        createPollingStation: {
+          regionCode: new BN(districtCode),
+          tpsNumber: 1,
+          capacity: 300,
+        },
+      });
+
+      try {
+        await program.methods
+          .createPollingStation(new BN(districtCode), 1, 300)
+          .accounts({
+            commissioner: provider.wallet.publicKey,
+            election: electionPda,
+            proposal: proposalPda,
+            region: regionChain[1],
+            pollingStation: derivePollingStationPDA(
+              electionPda,
+              districtCode,
+              1,
+              program.programId
+            )[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown InvalidRegion error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidRegion");
+        console.log("   ✅ Correctly rejected a station outside a village");
+      }
+    });
+
+    it("Should reject a polling station without capacity", async () => {
+      try {
+        await createProposal(program, electionPda, {
+          createPollingStation: {
+            regionCode: new BN(VILLAGE_CODE),
+            tpsNumber: 2,
+            capacity: 0,
+          },
+        });
+
+        expect.fail("Should have thrown InvalidPollingStation error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidPollingStation");
+        console.log("   ✅ Correctly rejected a zero-capacity station");
+      }
+    });
+
+    it("Should not extend registration past the voting start", async () => {
+      const proposalPda = await createProposal(program, electionPda, {
+        extendRegistration: { registrationEnd: new BN(electionStartTime + 1) },
+      });
+
+      try {
+        await program.methods
+          .extendRegistration(new BN(electionStartTime + 1))
+          .accounts({
+            commissioner: provider.wallet.publicKey,
+            election: electionPda,
+            proposal: proposalPda,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown InvalidRegistrationPeriod error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal(
+          "InvalidRegistrationPeriod"
+        );
+        console.log("   ✅ Correctly rejected extension into polling day");
+      }
+    });
+
+    it("Should fail without a registrar attestation", async () => {
       const badVoter = Keypair.generate();
       await airdrop(provider.connection, badVoter.publicKey);
+      const voterData = createTestVoterData(badVoter.publicKey);
 
       const [voterCredentialPda] = deriveVoterCredentialPDA(
         electionPda,
@@ -651,161 +1963,3560 @@ describe("E-Voting Smart Contract Tests", () => {
       try {
         await program.methods
           .verifyVoter(
-            "1234567890123456",
-            Array(32).fill(0),
-            "QmTest123",
+            nikCommitment(nikSalt, voterData.nik),
+            voterData.biometricHash,
+            voterData.photoIpfsHash,
             new BN(getCurrentTimestamp()),
-            150 // Invalid: > 100
+            voterData.aiScore
           )
           .accounts({
             voter: badVoter.publicKey,
             election: electionPda,
             voterCredential: voterCredentialPda,
+            nikRecord: deriveNikRecordPDA(
+              electionPda,
+              nikCommitment(nikSalt, voterData.nik),
+              program.programId
+            )[0],
+            pollingStation: pollingStationPda,
             votingTokenMint: votingTokenMintPda,
             voterTokenAccount: voterTokenAccount,
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
+            registrar: null,
+            instructionsSysvar: null,
           })
           .signers([badVoter])
           .rpc();
 
-        expect.fail("Should have thrown InvalidConfidenceScore error");
+        expect.fail("Should have thrown MissingRegistrarAttestation error");
       } catch (error: any) {
-        expect(error.error.errorCode.code).to.equal("InvalidConfidenceScore");
-        console.log("   ✅ Correctly rejected invalid confidence score");
+        expect(error.error.errorCode.code).to.equal(
+          "MissingRegistrarAttestation"
+        );
+        console.log("   ✅ Correctly rejected self-verification");
       }
     });
-  });
-
-  // ============================================================================
-  // 4. Manage Election Tests
-  // ============================================================================
 
-  describe("4. Manage Election", () => {
-    it("Should fail to activate election before start time", async () => {
-      // Election start time is in the future
-      try {
-        await program.methods
-          .activateElection()
-          .accounts({
-            commissioner: provider.wallet.publicKey,
-            election: electionPda,
-          })
-          .rpc();
+    it("Should fail when co-signed by a non-registrar", async () => {
+      const badVoter = Keypair.generate();
+      await airdrop(provider.connection, badVoter.publicKey);
+      const voterData = createTestVoterData(badVoter.publicKey);
 
-        expect.fail("Should have thrown ElectionNotStarted error");
-      } catch (error: any) {
-        expect(error.error.errorCode.code).to.equal("ElectionNotStarted");
-        console.log(
-          "   ✅ Correctly prevented activation before start time"
-        );
-      }
-    });
+      const [voterCredentialPda] = deriveVoterCredentialPDA(
+        electionPda,
+        badVoter.publicKey,
+        program.programId
+      );
+      const voterTokenAccount = await getAssociatedTokenAddress(
+        votingTokenMintPda,
+        badVoter.publicKey
+      );
 
-    it("Should reject non-commissioner activation", async () => {
       try {
         await program.methods
-          .activateElection()
+          .verifyVoter(
+            nikCommitment(nikSalt, voterData.nik),
+            voterData.biometricHash,
+            voterData.photoIpfsHash,
+            new BN(getCurrentTimestamp()),
+            voterData.aiScore
+          )
           .accounts({
-            commissioner: unauthorizedUser.publicKey,
+            voter: badVoter.publicKey,
             election: electionPda,
+            voterCredential: voterCredentialPda,
+            nikRecord: deriveNikRecordPDA(
+              electionPda,
+              nikCommitment(nikSalt, voterData.nik),
+              program.programId
+            )[0],
+            pollingStation: pollingStationPda,
+            votingTokenMint: votingTokenMintPda,
+            voterTokenAccount: voterTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            registrar: unauthorizedUser.publicKey,
+            instructionsSysvar: null,
           })
-          .signers([unauthorizedUser])
+          .signers([badVoter, unauthorizedUser])
           .rpc();
 
-        expect.fail("Should have rejected unauthorized activation");
+        expect.fail("Should have thrown RegistrarNotAuthorized error");
       } catch (error: any) {
-        // Should fail due to constraint
-        console.log("   ✅ Correctly rejected unauthorized activation");
+        expect(error.error.errorCode.code).to.equal("RegistrarNotAuthorized");
+        console.log("   ✅ Correctly rejected non-registrar co-signer");
       }
     });
-  });
 
-  // ============================================================================
-  // 5. Security Tests
-  // ============================================================================
-
-  describe("5. Security Tests", () => {
-    it("Should prevent registering candidate after election is active", async () => {
-      // Note: This test would require activating the election first
-      // For now, we verify the constraint exists
-      console.log(
-        "   ⏭️  Skipped - requires time manipulation for full test"
+    it("Should verify voter 3 with an ed25519 registrar attestation", async () => {
+      await airdrop(provider.connection, voter3.publicKey);
+      const voterData = createTestVoterData(voter3.publicKey);
+      const [voterCredentialPda] = deriveVoterCredentialPDA(
+        electionPda,
+        voter3.publicKey,
+        program.programId
+      );
+      const voterTokenAccount = await getAssociatedTokenAddress(
+        votingTokenMintPda,
+        voter3.publicKey
+      );
+
+      const timestamp = getCurrentTimestamp();
+      const attestationIx = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: registrar.secretKey,
+        message: attestationMessage(
+          electionPda,
+          voter3.publicKey,
+          pollingStationPda,
+          nikCommitment(nikSalt, voterData.nik),
+          voterData.biometricHash,
+          voterData.aiScore,
+          timestamp
+        ),
+      });
+
+      await program.methods
+        .verifyVoter(
+          nikCommitment(nikSalt, voterData.nik),
+          voterData.biometricHash,
+          voterData.photoIpfsHash,
+          new BN(timestamp),
+          voterData.aiScore
+        )
+        .accounts({
+          voter: voter3.publicKey,
+          election: electionPda,
+          voterCredential: voterCredentialPda,
+          nikRecord: deriveNikRecordPDA(
+            electionPda,
+            nikCommitment(nikSalt, voterData.nik),
+            program.programId
+          )[0],
+          pollingStation: pollingStationPda,
+          votingTokenMint: votingTokenMintPda,
+          voterTokenAccount: voterTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          registrar: null,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .preInstructions([attestationIx])
+        .signers([voter3])
+        .rpc();
+
+      const credential = await program.account.voterCredential.fetch(
+        voterCredentialPda
       );
+      expect(credential.isVerified).to.equal(true);
+      console.log("   ✅ Voter 3 verified via ed25519 attestation");
     });
 
-    it("Should verify PDA derivation is deterministic", () => {
-      // Verify same inputs always produce same PDA
-      const [pda1] = deriveElectionPDA(TEST_ELECTION_NAME, program.programId);
-      const [pda2] = deriveElectionPDA(TEST_ELECTION_NAME, program.programId);
+    it("Should reject an ed25519 attestation for another polling station", async () => {
+      const wallet = Keypair.generate();
+      await airdrop(provider.connection, wallet.publicKey);
+      const voterData = createTestVoterData(wallet.publicKey);
+      const [voterCredentialPda] = deriveVoterCredentialPDA(
+        electionPda,
+        wallet.publicKey,
+        program.programId
+      );
+      const voterTokenAccount = await getAssociatedTokenAddress(
+        votingTokenMintPda,
+        wallet.publicKey
+      );
 
-      expect(pda1.toString()).to.equal(pda2.toString());
-      console.log("   ✅ PDA derivation is deterministic");
+      const timestamp = getCurrentTimestamp();
+      // Signed for a different station than the one the voter registers at
+      const attestationIx = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: registrar.secretKey,
+        message: attestationMessage(
+          electionPda,
+          wallet.publicKey,
+          Keypair.generate().publicKey,
+          nikCommitment(nikSalt, voterData.nik),
+          voterData.biometricHash,
+          voterData.aiScore,
+          timestamp
+        ),
+      });
+
+      try {
+        await program.methods
+          .verifyVoter(
+            nikCommitment(nikSalt, voterData.nik),
+            voterData.biometricHash,
+            voterData.photoIpfsHash,
+            new BN(timestamp),
+            voterData.aiScore
+          )
+          .accounts({
+            voter: wallet.publicKey,
+            election: electionPda,
+            voterCredential: voterCredentialPda,
+            nikRecord: deriveNikRecordPDA(
+              electionPda,
+              nikCommitment(nikSalt, voterData.nik),
+              program.programId
+            )[0],
+            pollingStation: pollingStationPda,
+            votingTokenMint: votingTokenMintPda,
+            voterTokenAccount: voterTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            registrar: null,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .preInstructions([attestationIx])
+          .signers([wallet])
+          .rpc();
+
+        expect.fail("Should have thrown InvalidRegistrarAttestation error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidRegistrarAttestation");
+        console.log("   ✅ Correctly rejected attestation for another station");
+      }
     });
 
-    it("Should verify different inputs produce different PDAs", () => {
-      const [pda1] = deriveElectionPDA("Election1", program.programId);
-      const [pda2] = deriveElectionPDA("Election2", program.programId);
+    it("Should reject a second wallet registering the same NIK", async () => {
+      const secondWallet = Keypair.generate();
+      await airdrop(provider.connection, secondWallet.publicKey);
+      // Voter 1's NIK from a different wallet
+      const voterData = createTestVoterData(voter1.publicKey);
 
-      expect(pda1.toString()).to.not.equal(pda2.toString());
-      console.log("   ✅ Different inputs produce different PDAs");
+      const [voterCredentialPda] = deriveVoterCredentialPDA(
+        electionPda,
+        secondWallet.publicKey,
+        program.programId
+      );
+      const voterTokenAccount = await getAssociatedTokenAddress(
+        votingTokenMintPda,
+        secondWallet.publicKey
+      );
+
+      try {
+        await program.methods
+          .verifyVoter(
+            nikCommitment(nikSalt, voterData.nik),
+            voterData.biometricHash,
+            voterData.photoIpfsHash,
+            new BN(getCurrentTimestamp()),
+            voterData.aiScore
+          )
+          .accounts({
+            voter: secondWallet.publicKey,
+            election: electionPda,
+            voterCredential: voterCredentialPda,
+            nikRecord: deriveNikRecordPDA(
+              electionPda,
+              nikCommitment(nikSalt, voterData.nik),
+              program.programId
+            )[0],
+            pollingStation: pollingStationPda,
+            votingTokenMint: votingTokenMintPda,
+            voterTokenAccount: voterTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            registrar: registrar.publicKey,
+            instructionsSysvar: null,
+          })
+          .signers([secondWallet, registrar])
+          .rpc();
+
+        expect.fail("Should have thrown NikAlreadyRegistered error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NikAlreadyRegistered");
+        console.log("   ✅ Correctly rejected duplicate NIK");
+      }
+    });
+
+    it("Should rebind voter 2's NIK to a replacement wallet", async () => {
+      await airdrop(provider.connection, voter2Replacement.publicKey);
+      const commitment = nikCommitment(
+        nikSalt,
+        createTestVoterData(voter2.publicKey).nik
+      );
+      const [nikRecordPda] = deriveNikRecordPDA(
+        electionPda,
+        commitment,
+        program.programId
+      );
+      const [oldCredentialPda] = deriveVoterCredentialPDA(
+        electionPda,
+        voter2.publicKey,
+        program.programId
+      );
+      const [newCredentialPda] = deriveVoterCredentialPDA(
+        electionPda,
+        voter2Replacement.publicKey,
+        program.programId
+      );
+
+      const proposalPda = await createProposal(program, electionPda, {
+        rebindNik: {
+          voterNikCommitment: commitment,
+          newVoter: voter2Replacement.publicKey,
+        },
+      });
+
+      await program.methods
+        .rebindNik()
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          newVoter: voter2Replacement.publicKey,
+          election: electionPda,
+          proposal: proposalPda,
+          nikRecord: nikRecordPda,
+          oldVoterCredential: oldCredentialPda,
+          newVoterCredential: newCredentialPda,
+          votingTokenMint: votingTokenMintPda,
+          newVoterTokenAccount: await getAssociatedTokenAddress(
+            votingTokenMintPda,
+            voter2Replacement.publicKey
+          ),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter2Replacement])
+        .rpc();
+
+      const oldCredential = await program.account.voterCredential.fetch(
+        oldCredentialPda
+      );
+      const newCredential = await program.account.voterCredential.fetch(
+        newCredentialPda
+      );
+      const nikRecord = await program.account.nikRecord.fetch(nikRecordPda);
+
+      expect(oldCredential.isVerified).to.equal(false);
+      expect(newCredential.isVerified).to.equal(true);
+      expect(newCredential.region.toString()).to.equal(
+        oldCredential.region.toString()
+      );
+      expect(nikRecord.voterCredential.toString()).to.equal(
+        newCredentialPda.toString()
+      );
+      console.log("   ✅ NIK rebound, old credential revoked");
     });
   });
 
   // ============================================================================
-  // 6. Data Integrity Tests
+  // 3a. Voter Transfer Tests
   // ============================================================================
 
-  describe("6. Data Integrity", () => {
-    it("Should correctly track total registered voters", async () => {
-      const election = await program.account.election.fetch(electionPda);
-      expect(election.totalRegisteredVoters.toNumber()).to.equal(2);
-      console.log(
-        `   ✅ Total registered voters: ${election.totalRegisteredVoters.toNumber()}`
+  describe("3a. Voter Transfer (DPTb)", () => {
+    let destinationPda: PublicKey;
+    let firstChoicePda: PublicKey;
+    let voterCredentialPda: PublicKey;
+    let voterTransferPda: PublicKey;
+
+    const requestTransfer = (toStation: PublicKey) =>
+      program.methods
+        .requestTransfer()
+        .accounts({
+          voter: voter1.publicKey,
+          election: electionPda,
+          voterCredential: voterCredentialPda,
+          toStation,
+          voterTransfer: voterTransferPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter1])
+        .rpc();
+
+    const approveTransfer = (approver: Keypair) =>
+      program.methods
+        .approveTransfer()
+        .accounts({
+          approver: approver.publicKey,
+          election: electionPda,
+          voterTransfer: voterTransferPda,
+          voterCredential: voterCredentialPda,
+          fromStation: pollingStationPda,
+          toStation: destinationPda,
+        })
+        .signers([approver])
+        .rpc();
+
+    before(async () => {
+      destinationPda = await createPollingStation(program, electionPda, VILLAGE_CODE, 2, 300);
+      firstChoicePda = await createPollingStation(program, electionPda, VILLAGE_CODE, 3, 300);
+      [voterCredentialPda] = deriveVoterCredentialPDA(
+        electionPda,
+        voter1.publicKey,
+        program.programId
+      );
+      [voterTransferPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("voter_transfer"),
+          electionPda.toBuffer(),
+          voterCredentialPda.toBuffer(),
+        ],
+        program.programId
       );
     });
 
-    it("Should have correct candidate count", async () => {
-      const candidates = await program.account.candidate.all([
-        {
-          memcmp: {
-            offset: 8, // After discriminator
-            bytes: electionPda.toBase58(),
-          },
-        },
-      ]);
+    it("Should record a transfer request without moving the voter", async () => {
+      await requestTransfer(firstChoicePda);
 
-      expect(candidates.length).to.equal(3);
-      console.log(`   ✅ Total candidates registered: ${candidates.length}`);
+      const transfer = await program.account.voterTransfer.fetch(voterTransferPda);
+      expect(transfer.fromStation.toString()).to.equal(pollingStationPda.toString());
+      expect(transfer.toStation.toString()).to.equal(firstChoicePda.toString());
+      expect(transfer.approved).to.equal(false);
+
+      const credential = await program.account.voterCredential.fetch(voterCredentialPda);
+      expect(credential.pollingStation.toString()).to.equal(pollingStationPda.toString());
     });
 
-    it("Should have correct voter credentials", async () => {
-      const credentials = await program.account.voterCredential.all([
-        {
-          memcmp: {
-            offset: 8, // After discriminator
-            bytes: electionPda.toBase58(),
-          },
-        },
-      ]);
+    it("Should replace a pending transfer with a new request", async () => {
+      await requestTransfer(destinationPda);
 
-      expect(credentials.length).to.equal(2);
-      console.log(`   ✅ Total voter credentials: ${credentials.length}`);
+      const transfer = await program.account.voterTransfer.fetch(voterTransferPda);
+      expect(transfer.fromStation.toString()).to.equal(pollingStationPda.toString());
+      expect(transfer.toStation.toString()).to.equal(destinationPda.toString());
+      expect(transfer.approved).to.equal(false);
 
-      // Verify all are verified and haven't voted yet
-      for (const cred of credentials) {
-        expect(cred.account.isVerified).to.equal(true);
-        expect(cred.account.hasVoted).to.equal(false);
+      const firstChoice = await program.account.pollingStation.fetch(firstChoicePda);
+      expect(firstChoice.registeredVoters).to.equal(0);
+      console.log("   ✅ TPS 003 request replaced by TPS 002");
+    });
+
+    it("Should reject a transfer to the voter's own station", async () => {
+      try {
+        await requestTransfer(pollingStationPda);
+
+        expect.fail("Should have thrown InvalidPollingStation error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidPollingStation");
+        console.log("   ✅ Correctly rejected a transfer to the home station");
+      }
+    });
+
+    it("Should reject approval by a non-registrar", async () => {
+      try {
+        await approveTransfer(unauthorizedUser);
+
+        expect.fail("Should have thrown NotTransferApprover error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NotTransferApprover");
+        console.log("   ✅ Correctly rejected an unauthorized approver");
+      }
+    });
+
+    it("Should move the voter between station registers on approval", async () => {
+      const homeBefore = await program.account.pollingStation.fetch(pollingStationPda);
+
+      await approveTransfer(registrar);
+
+      const home = await program.account.pollingStation.fetch(pollingStationPda);
+      const destination = await program.account.pollingStation.fetch(destinationPda);
+      expect(home.registeredVoters).to.equal(homeBefore.registeredVoters - 1);
+      expect(destination.registeredVoters).to.equal(1);
+
+      const credential = await program.account.voterCredential.fetch(voterCredentialPda);
+      expect(credential.pollingStation.toString()).to.equal(destinationPda.toString());
+      console.log("   ✅ Voter 1 moved to TPS 002");
+    });
+
+    it("Should not approve a transfer twice", async () => {
+      try {
+        await approveTransfer(registrar);
+
+        expect.fail("Should have thrown TransferAlreadyApproved error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("TransferAlreadyApproved");
+        console.log("   ✅ Correctly rejected a repeated approval");
       }
-      console.log("   ✅ All voters verified and haven't voted");
+    });
+
+    it("Should let a transferred voter request another move", async () => {
+      await requestTransfer(firstChoicePda);
+
+      // The new request starts from the station the voter moved to
+      const transfer = await program.account.voterTransfer.fetch(voterTransferPda);
+      expect(transfer.fromStation.toString()).to.equal(destinationPda.toString());
+      expect(transfer.toStation.toString()).to.equal(firstChoicePda.toString());
+      expect(transfer.approved).to.equal(false);
+
+      const credential = await program.account.voterCredential.fetch(voterCredentialPda);
+      expect(credential.pollingStation.toString()).to.equal(destinationPda.toString());
+      console.log("   ✅ New transfer from TPS 002 pending");
     });
   });
 
   // ============================================================================
-  // Summary
+  // 4. Manage Election Tests
   // ============================================================================
 
+  describe("4. Manage Election", () => {
+    let activationProposalPda: PublicKey;
+
+    it("Should fail to activate election before start time", async () => {
+      // Election start time is in the future
+      activationProposalPda = await createProposal(program, electionPda, {
+        transitionElection: { status: { voting: {} } },
+      });
+
+      try {
+        await program.methods
+          .transitionElection({ voting: {} })
+          .accounts({
+            commissioner: provider.wallet.publicKey,
+            election: electionPda,
+            proposal: activationProposalPda,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown ElectionNotStarted error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("ElectionNotStarted");
+        console.log(
+          "   ✅ Correctly prevented activation before start time"
+        );
+      }
+    });
+
+    it("Should reject non-commissioner activation", async () => {
+      try {
+        await program.methods
+          .transitionElection({ voting: {} })
+          .accounts({
+            commissioner: unauthorizedUser.publicKey,
+            election: electionPda,
+            proposal: activationProposalPda,
+          })
+          .signers([unauthorizedUser])
+          .rpc();
+
+        expect.fail("Should have rejected unauthorized activation");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NotCommissioner");
+        console.log("   ✅ Correctly rejected unauthorized activation");
+      }
+    });
+
+    it("Should reject closing an election that is not in voting", async () => {
+      try {
+        await transitionElection(program, electionPda, { closed: {} });
+
+        expect.fail("Should have thrown CannotCloseVoting error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("CannotCloseVoting");
+        console.log("   ✅ Correctly rejected Registration -> Closed");
+      }
+    });
+
+    it("Should reject returning an election to draft", async () => {
+      try {
+        await transitionElection(program, electionPda, { draft: {} });
+
+        expect.fail("Should have thrown CannotReturnToDraft error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("CannotReturnToDraft");
+        console.log("   ✅ Correctly rejected Registration -> Draft");
+      }
+    });
+
+    it("Should reject the identity point as ballot issuer key", async () => {
+      const identity = Array(32).fill(0);
+      const proposalPda = await createProposal(program, electionPda, {
+        setBallotIssuer: { issuerKey: identity, issuerAuthority: provider.wallet.publicKey },
+      });
+
+      try {
+        await program.methods
+          .setBallotIssuer(identity, provider.wallet.publicKey)
+          .accounts({
+            commissioner: provider.wallet.publicKey,
+            election: electionPda,
+            proposal: proposalPda,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown InvalidCurvePoint error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidCurvePoint");
+        console.log("   ✅ Correctly rejected identity issuer key");
+      }
+    });
+
+    it("Should set the blind ballot-token issuer key", async () => {
+      const proposalPda = await createProposal(program, electionPda, {
+        setBallotIssuer: {
+          issuerKey: RISTRETTO_BASEPOINT,
+          issuerAuthority: provider.wallet.publicKey,
+        },
+      });
+
+      await program.methods
+        .setBallotIssuer(RISTRETTO_BASEPOINT, provider.wallet.publicKey)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: electionPda,
+          proposal: proposalPda,
+        })
+        .rpc();
+
+      const election = await program.account.election.fetch(electionPda);
+      expect(election.ballotIssuer).to.deep.equal(RISTRETTO_BASEPOINT);
+      expect(election.ballotIssuerAuthority.toString()).to.equal(
+        provider.wallet.publicKey.toString()
+      );
+      expect(election.ballotTokensIssued.toNumber()).to.equal(0);
+      console.log("   ✅ Ballot issuer key set");
+    });
+  });
+
+  // ============================================================================
+  // 5. Security Tests
+  // ============================================================================
+
+  describe("5. Security Tests", () => {
+    it("Should prevent registering candidate after election is active", async () => {
+      // Note: This test would require activating the election first
+      // For now, we verify the constraint exists
+      console.log(
+        "   ⏭️  Skipped - requires time manipulation for full test"
+      );
+    });
+
+    it("Should verify PDA derivation is deterministic", () => {
+      // Verify same inputs always produce same PDA
+      const [pda1] = deriveElectionPDA(0, program.programId);
+      const [pda2] = deriveElectionPDA(0, program.programId);
+
+      expect(pda1.toString()).to.equal(pda2.toString());
+      console.log("   ✅ PDA derivation is deterministic");
+    });
+
+    it("Should verify different inputs produce different PDAs", () => {
+      const [pda1] = deriveElectionPDA(1, program.programId);
+      const [pda2] = deriveElectionPDA(2, program.programId);
+
+      expect(pda1.toString()).to.not.equal(pda2.toString());
+      console.log("   ✅ Different inputs produce different PDAs");
+    });
+  });
+
+  // ============================================================================
+  // 6. Data Integrity Tests
+  // ============================================================================
+
+  describe("6. Data Integrity", () => {
+    it("Should correctly track total registered voters", async () => {
+      const election = await program.account.election.fetch(electionPda);
+      expect(election.totalRegisteredVoters.toNumber()).to.equal(3);
+      console.log(
+        `   ✅ Total registered voters: ${election.totalRegisteredVoters.toNumber()}`
+      );
+    });
+
+    it("Should have correct candidate count", async () => {
+      const candidates = await program.account.candidate.all([
+        {
+          memcmp: {
+            offset: 8, // After discriminator
+            bytes: electionPda.toBase58(),
+          },
+        },
+      ]);
+
+      expect(candidates.length).to.equal(3);
+      console.log(`   ✅ Total candidates registered: ${candidates.length}`);
+    });
+
+    it("Should report no votes in the tally summary before polling", async () => {
+      const summary = await program.methods
+        .tallySummary()
+        .accounts({ election: electionPda })
+        .view();
+
+      expect(summary.totalVotesCast.toNumber()).to.equal(0);
+      expect(summary.countedVotes.toNumber()).to.equal(0);
+      expect(summary.unrevealedVotes.toNumber()).to.equal(0);
+      expect(summary.blankVotes.toNumber()).to.equal(0);
+      expect(summary.spoiledVotes.toNumber()).to.equal(0);
+      console.log("   ✅ Tally summary reports no votes");
+    });
+
+    it("Should only let commissioners audit a voter verification", async () => {
+      const [voterCredentialPda] = deriveVoterCredentialPDA(
+        electionPda,
+        voter1.publicKey,
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .auditVerification()
+          .accounts({
+            commissioner: unauthorizedUser.publicKey,
+            election: electionPda,
+            voterCredential: voterCredentialPda,
+          })
+          .signers([unauthorizedUser])
+          .rpc();
+
+        expect.fail("Should have thrown NotCommissioner error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NotCommissioner");
+      }
+
+      const audit = await program.methods
+        .auditVerification()
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: electionPda,
+          voterCredential: voterCredentialPda,
+        })
+        .view();
+      const credential = await program.account.voterCredential.fetch(voterCredentialPda);
+      expect(audit.voterNikCommitment).to.deep.equal(credential.voterNikCommitment);
+      expect(audit.isVerified).to.equal(credential.isVerified);
+      console.log("   ✅ Audit data withheld from a non-commissioner");
+    });
+
+    it("Should have correct voter credentials", async () => {
+      const credentials = await program.account.voterCredential.all([
+        {
+          memcmp: {
+            offset: 8, // After discriminator
+            bytes: electionPda.toBase58(),
+          },
+        },
+      ]);
+
+      // Voter 2's revoked credential plus its replacement
+      expect(credentials.length).to.equal(4);
+      console.log(`   ✅ Total voter credentials: ${credentials.length}`);
+
+      // Verify all active credentials are verified and haven't voted yet
+      const active = credentials.filter((cred) => cred.account.isVerified);
+      expect(active.length).to.equal(3);
+      for (const cred of credentials) {
+        expect(cred.account.hasVoted).to.equal(false);
+      }
+      console.log("   ✅ All active voters verified and haven't voted");
+    });
+  });
+
+  // ============================================================================
+  // 7. Commissioner Proposal Tests
+  // ============================================================================
+
+  describe("7. Commissioner Proposals", () => {
+    const MULTISIG_ELECTION_NAME = "MultiSigElection";
+    const commissioner2 = Keypair.generate();
+    let multiSigElectionPda: PublicKey;
+    let registerProposalPda: PublicKey;
+
+    before(async () => {
+      await airdrop(provider.connection, commissioner2.publicKey);
+
+      [multiSigElectionPda] = await deriveNextElectionPDA(program);
+      const [mintPda] = deriveVotingTokenMintPDA(
+        multiSigElectionPda,
+        program.programId
+      );
+
+      // 2-of-2 commission
+      await program.methods
+        .initializeElection(
+          MULTISIG_ELECTION_NAME,
+          new BN(registrationStartTime),
+          new BN(electionStartTime),
+          new BN(electionStartTime),
+          new BN(electionEndTime),
+          [provider.wallet.publicKey, commissioner2.publicKey],
+          2,
+          { plurality: {} }
+        )
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: registryPda,
+          election: multiSigElectionPda,
+          votingTokenMint: mintPda,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+    });
+
+    it("Should reject proposals from non-commissioners", async () => {
+      try {
+        await createProposal(
+          program,
+          multiSigElectionPda,
+          { transitionElection: { status: { voting: {} } } },
+          unauthorizedUser
+        );
+
+        expect.fail("Should have thrown NotCommissioner error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NotCommissioner");
+        console.log("   ✅ Correctly rejected non-commissioner proposal");
+      }
+    });
+
+    it("Should not execute with fewer than required approvals", async () => {
+      registerProposalPda = await createProposal(program, multiSigElectionPda, {
+        registerCandidate: { candidateId: 1, ticket: candidateTicket("Ticket One", 1) },
+      });
+      const [candidatePda] = deriveCandidatePDA(
+        multiSigElectionPda,
+        1,
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .registerCandidate(candidateTicket("Ticket One", 1), 1)
+          .accounts({
+            commissioner: provider.wallet.publicKey,
+            election: multiSigElectionPda,
+            proposal: registerProposalPda,
+            candidate: candidatePda,
+            ballotNumberRecord: deriveBallotNumberPDA(
+              multiSigElectionPda,
+              1,
+              program.programId
+            )[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown InsufficientApprovals error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InsufficientApprovals");
+        console.log("   ✅ Correctly blocked 1-of-2 approved proposal");
+      }
+    });
+
+    it("Should reject duplicate approvals", async () => {
+      try {
+        await program.methods
+          .approveProposal()
+          .accounts({
+            commissioner: provider.wallet.publicKey,
+            election: multiSigElectionPda,
+            proposal: registerProposalPda,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown DuplicateApproval error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("DuplicateApproval");
+        console.log("   ✅ Correctly rejected duplicate approval");
+      }
+    });
+
+    it("Should reject approvals from non-commissioners", async () => {
+      try {
+        await program.methods
+          .approveProposal()
+          .accounts({
+            commissioner: unauthorizedUser.publicKey,
+            election: multiSigElectionPda,
+            proposal: registerProposalPda,
+          })
+          .signers([unauthorizedUser])
+          .rpc();
+
+        expect.fail("Should have thrown NotCommissioner error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NotCommissioner");
+        console.log("   ✅ Correctly rejected non-commissioner approval");
+      }
+    });
+
+    it("Should execute once the threshold is reached", async () => {
+      await program.methods
+        .approveProposal()
+        .accounts({
+          commissioner: commissioner2.publicKey,
+          election: multiSigElectionPda,
+          proposal: registerProposalPda,
+        })
+        .signers([commissioner2])
+        .rpc();
+
+      const [candidatePda] = deriveCandidatePDA(
+        multiSigElectionPda,
+        1,
+        program.programId
+      );
+      await program.methods
+        .registerCandidate(candidateTicket("Ticket One", 1), 1)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: multiSigElectionPda,
+          proposal: registerProposalPda,
+          candidate: candidatePda,
+          ballotNumberRecord: deriveBallotNumberPDA(
+            multiSigElectionPda,
+            1,
+            program.programId
+          )[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const proposal = await program.account.proposal.fetch(registerProposalPda);
+      expect(proposal.approvals.length).to.equal(2);
+      expect(proposal.executed).to.equal(true);
+      console.log("   ✅ Proposal executed with 2-of-2 approvals");
+    });
+
+    it("Should not reuse an executed proposal", async () => {
+      const [candidatePda] = deriveCandidatePDA(
+        multiSigElectionPda,
+        2,
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .registerCandidate(candidateTicket("Ticket One", 1), 2)
+          .accounts({
+            commissioner: provider.wallet.publicKey,
+            election: multiSigElectionPda,
+            proposal: registerProposalPda,
+            candidate: candidatePda,
+            ballotNumberRecord: deriveBallotNumberPDA(
+              multiSigElectionPda,
+              1,
+              program.programId
+            )[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown ProposalAlreadyExecuted error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("ProposalAlreadyExecuted");
+        console.log("   ✅ Correctly rejected replayed proposal");
+      }
+    });
+  });
+
+  // ============================================================================
+  // 8. Encrypted Tally Tests
+  // ============================================================================
+
+  describe("8. Encrypted Tally", () => {
+    const ENCRYPTED_ELECTION_NAME = "EncryptedElection";
+    const JOINT_PUBLIC_KEY = RISTRETTO_BASEPOINT;
+    let encryptedElectionPda: PublicKey;
+    let encryptedTallyPda: PublicKey;
+    let encryptedCandidatePda: PublicKey;
+
+    const configure = async (
+      election: PublicKey,
+      proposal: PublicKey,
+      tally: PublicKey,
+      keyCommitments: number[][],
+      candidates: PublicKey[]
+    ) =>
+      program.methods
+        .configureEncryptedTally(1, [1], keyCommitments)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election,
+          proposal,
+          encryptedTally: tally,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          candidates.map((pubkey) => ({
+            pubkey,
+            isSigner: false,
+            isWritable: false,
+          }))
+        )
+        .rpc();
+
+    before(async () => {
+      [encryptedElectionPda] = await deriveNextElectionPDA(program);
+      const [mintPda] = deriveVotingTokenMintPDA(
+        encryptedElectionPda,
+        program.programId
+      );
+      [encryptedTallyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("encrypted_tally"), encryptedElectionPda.toBuffer()],
+        program.programId
+      );
+      [encryptedCandidatePda] = deriveCandidatePDA(
+        encryptedElectionPda,
+        1,
+        program.programId
+      );
+
+      await program.methods
+        .initializeElection(
+          ENCRYPTED_ELECTION_NAME,
+          new BN(registrationStartTime),
+          new BN(electionStartTime),
+          new BN(electionStartTime),
+          new BN(electionEndTime),
+          [provider.wallet.publicKey],
+          1,
+          { encrypted: {} }
+        )
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: registryPda,
+          election: encryptedElectionPda,
+          votingTokenMint: mintPda,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+
+      const ticket = candidateTicket("Ticket Encrypted", 1);
+      const proposalPda = await createProposal(program, encryptedElectionPda, {
+        registerCandidate: { candidateId: 1, ticket },
+      });
+      await program.methods
+        .registerCandidate(ticket, 1)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: encryptedElectionPda,
+          proposal: proposalPda,
+          candidate: encryptedCandidatePda,
+          ballotNumberRecord: deriveBallotNumberPDA(
+            encryptedElectionPda,
+            1,
+            program.programId
+          )[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Should reject an encrypted tally on a plurality election", async () => {
+      const [tallyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("encrypted_tally"), electionPda.toBuffer()],
+        program.programId
+      );
+      const action = {
+        configureEncryptedTally: {
+          threshold: 1,
+          candidateIds: [1],
+          keyCommitments: [JOINT_PUBLIC_KEY],
+        },
+      };
+      const proposalPda = await createProposal(program, electionPda, action);
+
+      try {
+        await configure(
+          electionPda,
+          proposalPda,
+          tallyPda,
+          [JOINT_PUBLIC_KEY],
+          [candidate1Pda]
+        );
+        expect.fail("Should have thrown VotingModeMismatch error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("VotingModeMismatch");
+        console.log("   ✅ Correctly rejected encrypted tally on plurality election");
+      }
+    });
+
+    it("Should reject key commitments that are not curve points", async () => {
+      const invalidKey = Array(32).fill(0xff);
+      const proposalPda = await createProposal(program, encryptedElectionPda, {
+        configureEncryptedTally: {
+          threshold: 1,
+          candidateIds: [1],
+          keyCommitments: [invalidKey],
+        },
+      });
+
+      try {
+        await configure(
+          encryptedElectionPda,
+          proposalPda,
+          encryptedTallyPda,
+          [invalidKey],
+          [encryptedCandidatePda]
+        );
+        expect.fail("Should have thrown InvalidCurvePoint error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidCurvePoint");
+        console.log("   ✅ Correctly rejected invalid key commitment");
+      }
+    });
+
+    it("Should configure the encrypted tally with zeroed ciphertexts", async () => {
+      const proposalPda = await createProposal(program, encryptedElectionPda, {
+        configureEncryptedTally: {
+          threshold: 1,
+          candidateIds: [1],
+          keyCommitments: [JOINT_PUBLIC_KEY],
+        },
+      });
+
+      await configure(
+        encryptedElectionPda,
+        proposalPda,
+        encryptedTallyPda,
+        [JOINT_PUBLIC_KEY],
+        [encryptedCandidatePda]
+      );
+
+      const tally = await program.account.encryptedTally.fetch(encryptedTallyPda);
+      expect(tally.threshold).to.equal(1);
+      expect(tally.candidateIds).to.deep.equal([1]);
+      expect(tally.tallies.length).to.equal(1);
+      expect(tally.tallies[0].c1).to.deep.equal(Array(32).fill(0));
+      expect(tally.tallies[0].c2).to.deep.equal(Array(32).fill(0));
+      expect(tally.isPublished).to.equal(false);
+      console.log("   ✅ Encrypted tally configured");
+    });
+
+    describe("Threshold decryption", () => {
+      const secondCommissioner = Keypair.generate();
+      const voters = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+      const choices = [0, 2, 2]; // Candidate indices, so counts 1, 0, 2
+      const nullifiers = voters.map(() => randomBytes(32)); // Kept by each voter
+      const candidateIds = [1, 2, 3];
+      let bank: Bank;
+      let setup: BankElection;
+      let tallyPda: PublicKey;
+      // x(t) = a0 + a1 t shared by two trustees; the joint key is a0 G
+      const coefficients = [randomScalar(), randomScalar()];
+      const jointKey = RistrettoPoint.BASE.multiply(coefficients[0]);
+      const secretShare = (index: number) =>
+        modOrder(coefficients[0] + coefficients[1] * BigInt(index));
+
+      const candidateAccounts = () =>
+        candidateIds.map((candidateId) => ({
+          pubkey: deriveCandidatePDA(setup.electionPda, candidateId, bank.program.programId)[0],
+          isSigner: false,
+          isWritable: true,
+        }));
+
+      // Shares x_i C1 for every candidate tally, each with its proof
+      const decryptionShares = async (index: number) => {
+        const tally = await bank.program.account.encryptedTally.fetch(tallyPda);
+        const x = secretShare(index);
+        const publicShare = RistrettoPoint.BASE.multiply(x);
+        const shares = tally.tallies.map((ciphertext) =>
+          RistrettoPoint.fromHex(Uint8Array.from(ciphertext.c1)).multiply(x)
+        );
+        const proofs = tally.tallies.map((ciphertext, k) =>
+          proveEquality(
+            x,
+            [RistrettoPoint.BASE, publicShare],
+            [RistrettoPoint.fromHex(Uint8Array.from(ciphertext.c1)), shares[k]],
+            "IDN-EVOTE-DECRYPT",
+            [setup.electionPda.toBuffer(), Buffer.from([index])]
+          )
+        );
+        return { shares, proofs };
+      };
+
+      const submitShares = (
+        commissioner: Keypair | null,
+        shares: (typeof RistrettoPoint.BASE)[],
+        proofs: { challenge: number[]; response: number[] }[]
+      ) => {
+        const builder = bank.program.methods
+          .submitDecryptionShare(
+            shares.map((share) => Array.from(share.toRawBytes())),
+            proofs
+          )
+          .accounts({
+            commissioner: commissioner
+              ? commissioner.publicKey
+              : bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            encryptedTally: tallyPda,
+          });
+        return (commissioner ? builder.signers([commissioner]) : builder).rpc();
+      };
+
+      before(async () => {
+        bank = await startBank(program.idl);
+        setup = await setupBankElection(bank, {
+          name: "Pemilu Terenkripsi",
+          votingMode: { encrypted: {} },
+          candidateIds,
+          registrar,
+          voters,
+          commissioners: [bank.provider.wallet.publicKey, secondCommissioner.publicKey],
+        });
+        [tallyPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("encrypted_tally"), setup.electionPda.toBuffer()],
+          bank.program.programId
+        );
+
+        const keyCommitments = coefficients.map((coefficient) =>
+          Array.from(RistrettoPoint.BASE.multiply(coefficient).toRawBytes())
+        );
+        const proposalPda = await createProposal(bank.program, setup.electionPda, {
+          configureEncryptedTally: { threshold: 2, candidateIds, keyCommitments },
+        });
+        await bank.program.methods
+          .configureEncryptedTally(2, candidateIds, keyCommitments)
+          .accounts({
+            commissioner: bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            proposal: proposalPda,
+            encryptedTally: tallyPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(candidateAccounts())
+          .rpc();
+
+        await warpTo(bank, setup.startTime);
+        await transitionElection(bank.program, setup.electionPda, { voting: {} });
+      });
+
+      it("Should add encrypted ballots to the tally without revealing them", async () => {
+        for (const [sequence, voter] of voters.entries()) {
+          const ballot = encryptBallot(jointKey, choices[sequence], candidateIds.length, [
+            setup.electionPda.toBuffer(),
+            voter.publicKey.toBuffer(),
+          ]);
+          await bank.program.methods
+            .castEncryptedVote(Array.from(nullifiers[sequence]), { candidate: {} }, ballot)
+            .accounts({
+              voter: voter.publicKey,
+              election: setup.electionPda,
+              voterCredential: deriveVoterCredentialPDA(
+                setup.electionPda,
+                voter.publicKey,
+                bank.program.programId
+              )[0],
+              pollingStation: setup.pollingStationPda,
+              encryptedTally: tallyPda,
+              nullifierRecord: PublicKey.findProgramAddressSync(
+                [Buffer.from("nullifier"), setup.electionPda.toBuffer(), nullifiers[sequence]],
+                bank.program.programId
+              )[0],
+              ballot: deriveBallotPDA(setup.electionPda, sequence, bank.program.programId)[0],
+              votingTokenMint: setup.votingTokenMintPda,
+              voterTokenAccount: await getAssociatedTokenAddress(
+                setup.votingTokenMintPda,
+                voter.publicKey
+              ),
+              tokenProgram: TOKEN_PROGRAM_ID,
+              systemProgram: SystemProgram.programId,
+            })
+            .remainingAccounts(
+              setup.regionChain.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+            )
+            .signers([voter])
+            .rpc();
+        }
+
+        const election = await bank.program.account.election.fetch(setup.electionPda);
+        expect(election.totalVotesCast.toNumber()).to.equal(voters.length);
+        const candidate = await bank.program.account.candidate.fetch(
+          deriveCandidatePDA(setup.electionPda, 3, bank.program.programId)[0]
+        );
+        expect(candidate.voteCount.toNumber()).to.equal(0);
+        console.log("   ✅ Three encrypted ballots added to the tally");
+      });
+
+      it("Should verify an encrypted ballot's receipt from its nullifier", async () => {
+        const [ballotPda] = deriveBallotPDA(setup.electionPda, 1, bank.program.programId);
+        const verification = await bank.program.methods
+          .verifyBallotReceipt(Array.from(nullifiers[1]))
+          .accounts({
+            election: setup.electionPda,
+            nullifierRecord: PublicKey.findProgramAddressSync(
+              [Buffer.from("nullifier"), setup.electionPda.toBuffer(), nullifiers[1]],
+              bank.program.programId
+            )[0],
+            ballot: ballotPda,
+          })
+          .view();
+
+        const ballot = await bank.program.account.ballot.fetch(ballotPda);
+        expect(verification.isValid).to.equal(true);
+        expect(verification.receipt).to.equal(ballot.verificationReceipt);
+        console.log("   ✅ Encrypted ballot receipt recomputed from the nullifier");
+      });
+
+      it("Should reject a decryption share that does not match its proof", async () => {
+        await warpTo(bank, setup.endTime + 1);
+        await transitionElection(bank.program, setup.electionPda, { closed: {} });
+
+        // Shifting a share by G would shift the published count by one
+        const { shares, proofs } = await decryptionShares(2);
+        const forged = [shares[0].add(RistrettoPoint.BASE), ...shares.slice(1)];
+
+        try {
+          await submitShares(secondCommissioner, forged, proofs);
+
+          expect.fail("Should have thrown InvalidDecryptionProof error");
+        } catch (error: any) {
+          expect(programErrorCode(bank.program, error)).to.equal("InvalidDecryptionProof");
+          console.log("   ✅ Correctly rejected a forged decryption share");
+        }
+      });
+
+      it("Should publish the counts opened by two trustees", async () => {
+        const first = await decryptionShares(1);
+        await submitShares(null, first.shares, first.proofs);
+        const second = await decryptionShares(2);
+        await submitShares(secondCommissioner, second.shares, second.proofs);
+
+        // Lagrange at zero over trustees {1, 2}: 2 for trustee 1, -1 for trustee 2
+        const lagrangeCoefficients = [scalarBytes(2n), scalarBytes(modOrder(-1n))];
+        const publish = (counts: number[]) =>
+          bank.program.methods
+            .publishTally(
+              counts.map((count) => new BN(count)),
+              lagrangeCoefficients
+            )
+            .accounts({
+              commissioner: bank.provider.wallet.publicKey,
+              election: setup.electionPda,
+              encryptedTally: tallyPda,
+            })
+            .remainingAccounts(candidateAccounts())
+            .rpc();
+
+        try {
+          await publish([0, 1, 2]);
+
+          expect.fail("Should have thrown TallyMismatch error");
+        } catch (error: any) {
+          expect(programErrorCode(bank.program, error)).to.equal("TallyMismatch");
+        }
+
+        await publish([1, 0, 2]);
+
+        const tally = await bank.program.account.encryptedTally.fetch(tallyPda);
+        expect(tally.isPublished).to.equal(true);
+        expect(tally.results.map((count) => count.toNumber())).to.deep.equal([1, 0, 2]);
+        for (const [k, candidateId] of candidateIds.entries()) {
+          const candidate = await bank.program.account.candidate.fetch(
+            deriveCandidatePDA(setup.electionPda, candidateId, bank.program.programId)[0]
+          );
+          expect(candidate.voteCount.toNumber()).to.equal([1, 0, 2][k]);
+        }
+        const election = await bank.program.account.election.fetch(setup.electionPda);
+        expect(election.status).to.deep.equal({ tallied: {} });
+        console.log("   ✅ Decrypted counts 1, 0, 2 published");
+      });
+    });
+  });
+
+  // ============================================================================
+  // 9. ZK Membership Tests
+  // ============================================================================
+
+  describe("9. ZK Membership", () => {
+    const ZK_ELECTION_NAME = "ZkMembershipElection";
+    // Placeholder key: the program stores it as-is and only uses it to verify proofs
+    const verifyingKey = {
+      alphaG1: Array(64).fill(1),
+      betaG2: Array(128).fill(2),
+      gammaG2: Array(128).fill(3),
+      deltaG2: Array(128).fill(4),
+      ic: Array.from({ length: 5 }, (_, i) => Array(64).fill(5 + i)),
+    };
+    const verifyingKeyHash = (key: typeof verifyingKey): number[] =>
+      Array.from(
+        createHash("sha256")
+          .update(
+            Buffer.from([
+              ...key.alphaG1,
+              ...key.betaG2,
+              ...key.gammaG2,
+              ...key.deltaG2,
+              ...key.ic.flat(),
+            ])
+          )
+          .digest()
+      );
+    let zkElectionPda: PublicKey;
+    let zkVerifyingKeyPda: PublicKey;
+    let membershipTreePda: PublicKey;
+
+    const configure = async (proposal: PublicKey, key: typeof verifyingKey) =>
+      program.methods
+        .configureMembershipProofs(key)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: zkElectionPda,
+          proposal,
+          zkVerifyingKey: zkVerifyingKeyPda,
+          membershipTree: membershipTreePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    before(async () => {
+      [zkElectionPda] = await deriveNextElectionPDA(program);
+      const [mintPda] = deriveVotingTokenMintPDA(
+        zkElectionPda,
+        program.programId
+      );
+      [zkVerifyingKeyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("zk_verifying_key"), zkElectionPda.toBuffer()],
+        program.programId
+      );
+      [membershipTreePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("membership_tree"), zkElectionPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeElection(
+          ZK_ELECTION_NAME,
+          new BN(registrationStartTime),
+          new BN(electionStartTime),
+          new BN(electionStartTime),
+          new BN(electionEndTime),
+          [provider.wallet.publicKey],
+          1,
+          { zkMembership: {} }
+        )
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: registryPda,
+          election: zkElectionPda,
+          votingTokenMint: mintPda,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+    });
+
+    it("Should reject a verifying key the proposal did not approve", async () => {
+      const proposalPda = await createProposal(program, zkElectionPda, {
+        configureMembershipProofs: {
+          verifyingKeyHash: verifyingKeyHash(verifyingKey),
+        },
+      });
+
+      try {
+        await configure(proposalPda, {
+          ...verifyingKey,
+          alphaG1: Array(64).fill(9),
+        });
+        expect.fail("Should have thrown ProposalActionMismatch error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("ProposalActionMismatch");
+        console.log("   ✅ Correctly rejected unapproved verifying key");
+      }
+    });
+
+    it("Should store the verifying key and create an empty identity tree", async () => {
+      const proposalPda = await createProposal(program, zkElectionPda, {
+        configureMembershipProofs: {
+          verifyingKeyHash: verifyingKeyHash(verifyingKey),
+        },
+      });
+
+      await configure(proposalPda, verifyingKey);
+
+      const stored = await program.account.zkVerifyingKey.fetch(zkVerifyingKeyPda);
+      expect(stored.verifyingKey.alphaG1).to.deep.equal(verifyingKey.alphaG1);
+      expect(stored.verifyingKey.ic).to.deep.equal(verifyingKey.ic);
+
+      const tree = await program.account.membershipTree.fetch(membershipTreePda);
+      expect(tree.nextIndex.toNumber()).to.equal(0);
+
+      // Empty-tree root is the depth-32 Poseidon zero hash, not zero
+      const election = await program.account.election.fetch(zkElectionPda);
+      expect(election.membershipRoot).to.not.deep.equal(Array(32).fill(0));
+      console.log("   ✅ Membership proofs configured");
+    });
+
+    describe("Membership proofs", () => {
+      const Fr = bn254.fields.Fr;
+      const G1 = bn254.G1.ProjectivePoint.BASE;
+      const G2 = bn254.G2.ProjectivePoint.BASE;
+      const voter = Keypair.generate();
+      let bank: Bank;
+      let setup: BankElection;
+      // Discrete logs of the verifying key: alpha, beta, gamma, delta, IC_0..IC_4.
+      // Knowing them stands in for the circuit; the program only sees points
+      const randomField = () => Fr.create(BigInt("0x" + randomBytes(32).toString("hex")) + 1n);
+      const [alpha, beta, gamma, delta, ...icLogs] = Array.from({ length: 9 }, randomField);
+
+      const fieldBytes = (value: bigint) =>
+        Buffer.from(value.toString(16).padStart(64, "0"), "hex");
+      const g1Bytes = (point: typeof G1) => {
+        const { x, y } = point.toAffine();
+        return Array.from(Buffer.concat([fieldBytes(x), fieldBytes(y)]));
+      };
+      // EIP-197 order: x.c1, x.c0, y.c1, y.c0
+      const g2Bytes = (point: typeof G2) => {
+        const { x, y } = point.toAffine();
+        return Array.from(
+          Buffer.concat([fieldBytes(x.c1), fieldBytes(x.c0), fieldBytes(y.c1), fieldBytes(y.c0)])
+        );
+      };
+      // hash_to_field: SHA-256 with the top byte cleared
+      const hashToField = (...parts: Buffer[]) => {
+        const hash = createHash("sha256").update(Buffer.concat(parts)).digest();
+        hash[0] = 0;
+        return hash;
+      };
+
+      const membershipKey = {
+        alphaG1: g1Bytes(G1.multiply(alpha)),
+        betaG2: g2Bytes(G2.multiply(beta)),
+        gammaG2: g2Bytes(G2.multiply(gamma)),
+        deltaG2: g2Bytes(G2.multiply(delta)),
+        ic: icLogs.map((log) => g1Bytes(G1.multiply(log))),
+      };
+
+      /**
+       * Proof for public inputs [root, nullifier, signal, external nullifier]:
+       * with B = G2, pick A and solve a = alpha beta + vk_x gamma + c delta for C
+       */
+      const prove = (publicInputs: Buffer[]) => {
+        const vkX = publicInputs.reduce((sum, input, i) => {
+          const value = Fr.create(BigInt("0x" + input.toString("hex")));
+          return Fr.add(sum, Fr.mul(value, icLogs[i + 1]));
+        }, icLogs[0]);
+        const a = randomField();
+        const c = Fr.div(Fr.sub(Fr.sub(a, Fr.mul(alpha, beta)), Fr.mul(vkX, gamma)), delta);
+        return { a: g1Bytes(G1.multiply(a)), b: g2Bytes(G2), c: g1Bytes(G1.multiply(c)) };
+      };
+
+      const castZkVote = (
+        nullifier: Buffer,
+        proof: { a: number[]; b: number[]; c: number[] },
+        candidateId: number
+      ) =>
+        bank.program.methods
+          .castVote(
+            Array.from(nullifier),
+            { membershipProof: { proof } },
+            { candidate: {} },
+            Array(32).fill(0)
+          )
+          .accounts({
+            relayer: bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            candidate: deriveCandidatePDA(setup.electionPda, candidateId, bank.program.programId)[0],
+            pollingStation: null,
+            nullifierRecord: PublicKey.findProgramAddressSync(
+              [Buffer.from("nullifier"), setup.electionPda.toBuffer(), nullifier],
+              bank.program.programId
+            )[0],
+            ballot: deriveBallotPDA(setup.electionPda, 0, bank.program.programId)[0],
+            zkVerifyingKey: PublicKey.findProgramAddressSync(
+              [Buffer.from("zk_verifying_key"), setup.electionPda.toBuffer()],
+              bank.program.programId
+            )[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+      before(async () => {
+        bank = await startBank(program.idl);
+        setup = await setupBankElection(bank, {
+          name: "Pemilu Anonim",
+          votingMode: { zkMembership: {} },
+          candidateIds: [1, 2],
+          registrar,
+          voters: [voter],
+        });
+
+        const proposalPda = await createProposal(bank.program, setup.electionPda, {
+          configureMembershipProofs: { verifyingKeyHash: verifyingKeyHash(membershipKey) },
+        });
+        await bank.program.methods
+          .configureMembershipProofs(membershipKey)
+          .accounts({
+            commissioner: bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            proposal: proposalPda,
+            zkVerifyingKey: PublicKey.findProgramAddressSync(
+              [Buffer.from("zk_verifying_key"), setup.electionPda.toBuffer()],
+              bank.program.programId
+            )[0],
+            membershipTree: PublicKey.findProgramAddressSync(
+              [Buffer.from("membership_tree"), setup.electionPda.toBuffer()],
+              bank.program.programId
+            )[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      });
+
+      it("Should add a verified voter's identity to the membership tree", async () => {
+        const [membershipTreePda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("membership_tree"), setup.electionPda.toBuffer()],
+          bank.program.programId
+        );
+        const [voterCredentialPda] = deriveVoterCredentialPDA(
+          setup.electionPda,
+          voter.publicKey,
+          bank.program.programId
+        );
+        const emptyRoot = (await bank.program.account.election.fetch(setup.electionPda))
+          .membershipRoot;
+        const register = (identityCommitment: Buffer) =>
+          bank.program.methods
+            .registerIdentity(Array.from(identityCommitment))
+            .accounts({
+              voter: voter.publicKey,
+              election: setup.electionPda,
+              voterCredential: voterCredentialPda,
+              membershipTree: membershipTreePda,
+            })
+            .signers([voter])
+            .rpc();
+
+        const identityCommitment = hashToField(Buffer.from("identity"), voter.publicKey.toBuffer());
+        await register(identityCommitment);
+
+        const tree = await bank.program.account.membershipTree.fetch(membershipTreePda);
+        expect(tree.nextIndex.toNumber()).to.equal(1);
+        const election = await bank.program.account.election.fetch(setup.electionPda);
+        expect(election.membershipRoot).to.not.deep.equal(emptyRoot);
+        const credential = await bank.program.account.voterCredential.fetch(voterCredentialPda);
+        expect(credential.identityCommitment).to.deep.equal(Array.from(identityCommitment));
+
+        try {
+          await register(hashToField(Buffer.from("second identity")));
+
+          expect.fail("Should have thrown IdentityAlreadyRegistered error");
+        } catch (error: any) {
+          expect(programErrorCode(bank.program, error)).to.equal("IdentityAlreadyRegistered");
+        }
+        console.log("   ✅ Identity commitment added to the tree");
+      });
+
+      it("Should count a membership-proof ballot only for the signal it proves", async () => {
+        // Proofs are taken while polls are open in every zone
+        await warpTo(bank, setup.startTime);
+        await transitionElection(bank.program, setup.electionPda, { voting: {} });
+        await warpTo(bank, setup.startTime + 2 * 3600);
+
+        const election = await bank.program.account.election.fetch(setup.electionPda);
+        const nullifier = hashToField(Buffer.from("nullifier"), voter.publicKey.toBuffer());
+        const signal = (candidateId: number) =>
+          hashToField(
+            deriveCandidatePDA(setup.electionPda, candidateId, bank.program.programId)[0].toBuffer()
+          );
+        const proof = prove([
+          Buffer.from(election.membershipRoot),
+          nullifier,
+          signal(1),
+          hashToField(setup.electionPda.toBuffer()),
+        ]);
+
+        // The same proof cannot carry another candidate or another nullifier
+        for (const [tamperedNullifier, candidateId] of [
+          [nullifier, 2],
+          [hashToField(Buffer.from("other nullifier")), 1],
+        ] as [Buffer, number][]) {
+          try {
+            await castZkVote(tamperedNullifier, proof, candidateId);
+
+            expect.fail("Should have thrown InvalidMembershipProof error");
+          } catch (error: any) {
+            expect(programErrorCode(bank.program, error)).to.equal("InvalidMembershipProof");
+          }
+        }
+
+        await castZkVote(nullifier, proof, 1);
+
+        const candidate = await bank.program.account.candidate.fetch(
+          deriveCandidatePDA(setup.electionPda, 1, bank.program.programId)[0]
+        );
+        expect(candidate.voteCount.toNumber()).to.equal(1);
+        const ballot = await bank.program.account.ballot.fetch(
+          deriveBallotPDA(setup.electionPda, 0, bank.program.programId)[0]
+        );
+        expect(ballot.region).to.equal(null);
+        console.log("   ✅ Membership proof verified; tampered signal and nullifier rejected");
+      });
+    });
+  });
+
+  // ============================================================================
+  // 10. Presidential Result Tests
+  // ============================================================================
+
+  describe("10. Presidential Result", () => {
+    const provinceCode = 31; // DKI Jakarta
+    let provincePda: PublicKey;
+    let electionResultPda: PublicKey;
+
+    before(() => {
+      [provincePda] = deriveRegionPDA(electionPda, provinceCode, program.programId);
+      [electionResultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("election_result"), electionPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    // Province followed by its tally for each ticket, in ticket order
+    const provinceAccounts = (candidateIds: number[]) => [
+      { pubkey: provincePda, isWritable: false, isSigner: false },
+      ...candidateIds.map((candidateId) => ({
+        pubkey: deriveRegionTallyPDA(provincePda, candidateId, program.programId)[0],
+        isWritable: false,
+        isSigner: false,
+      })),
+    ];
+
+    const tallyProvinces = (candidateIds: number[]) =>
+      program.methods
+        .tallyProvinces(candidateIds)
+        .accounts({
+          payer: provider.wallet.publicKey,
+          election: electionPda,
+          electionResult: electionResultPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(provinceAccounts(candidateIds))
+        .rpc();
+
+    it("Should reject a result with a single ticket", async () => {
+      try {
+        await tallyProvinces([1]);
+
+        expect.fail("Should have thrown NotEnoughCandidates error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NotEnoughCandidates");
+        console.log("   ✅ Correctly rejected single-ticket result");
+      }
+    });
+
+    it("Should reject a result listing a ticket twice", async () => {
+      try {
+        await tallyProvinces([1, 2, 1]);
+
+        expect.fail("Should have thrown InvalidTallyConfig error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidTallyConfig");
+        console.log("   ✅ Correctly rejected duplicate ticket");
+      }
+    });
+
+    it("Should not fold provinces before the election is tallied", async () => {
+      try {
+        await tallyProvinces([1, 2, 3]);
+
+        expect.fail("Should have thrown ResultNotReady error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("ResultNotReady");
+        console.log("   ✅ Correctly refused to recapitulate an untallied election");
+      }
+
+      const result = await program.account.electionResult.fetchNullable(
+        electionResultPda
+      );
+      expect(result).to.be.null;
+    });
+
+    it("Should refuse a presidential result without regional counts", async () => {
+      const [encryptedElectionPda] = await deriveNextElectionPDA(program);
+      await program.methods
+        .initializeElection(
+          "Pilpres Terenkripsi",
+          new BN(registrationStartTime),
+          new BN(electionStartTime),
+          new BN(electionStartTime),
+          new BN(electionEndTime),
+          [provider.wallet.publicKey],
+          1,
+          { encrypted: {} }
+        )
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: registryPda,
+          election: encryptedElectionPda,
+          votingTokenMint: deriveVotingTokenMintPDA(encryptedElectionPda, program.programId)[0],
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+
+      try {
+        await program.methods
+          .tallyProvinces([1, 2])
+          .accounts({
+            payer: provider.wallet.publicKey,
+            election: encryptedElectionPda,
+            electionResult: PublicKey.findProgramAddressSync(
+              [Buffer.from("election_result"), encryptedElectionPda.toBuffer()],
+              program.programId
+            )[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown NoRegionalCounts error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NoRegionalCounts");
+        console.log("   ✅ Encrypted ballots give no provincial spread");
+      }
+    });
+
+    describe("Runoff", () => {
+      const candidateIds = [1, 2, 3];
+      const voters = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+      const choices = [2, 3, 2]; // Ticket 1 gets no votes
+      let bank: Bank;
+      let setup: BankElection;
+      let resultPda: PublicKey;
+      let runoffPda: PublicKey;
+
+      const castForCandidate = async (voter: Keypair, sequence: number, candidateId: number) => {
+        const key = ballotKey();
+        const signature = await obtainBallotToken(
+          bank.program,
+          setup.electionPda,
+          setup.pollingStationPda,
+          voter,
+          key.nullifier
+        );
+        const candidatePda = deriveCandidatePDA(
+          setup.electionPda,
+          candidateId,
+          bank.program.programId
+        )[0];
+        const contentsSignature = signBallotContents(setup.electionPda, key, [
+          Buffer.from([0]), // BallotKind::Candidate
+          Buffer.alloc(32),
+          candidatePda.toBuffer(),
+        ]);
+        await bank.program.methods
+          .castVote(
+            Array.from(key.nullifier),
+            { blindToken: { signature, contentsSignature } },
+            { candidate: {} },
+            Array(32).fill(0)
+          )
+          .accounts({
+            relayer: bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            candidate: candidatePda,
+            pollingStation: setup.pollingStationPda,
+            nullifierRecord: PublicKey.findProgramAddressSync(
+              [Buffer.from("nullifier"), setup.electionPda.toBuffer(), key.nullifier],
+              bank.program.programId
+            )[0],
+            ballot: deriveBallotPDA(setup.electionPda, sequence, bank.program.programId)[0],
+            zkVerifyingKey: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            [
+              ...setup.regionChain,
+              ...setup.regionChain.map(
+                (region) => deriveRegionTallyPDA(region, candidateId, bank.program.programId)[0]
+              ),
+            ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+          )
+          .rpc();
+      };
+
+      before(async () => {
+        bank = await startBank(program.idl);
+        setup = await setupBankElection(bank, {
+          name: "Pilpres Putaran Pertama",
+          votingMode: { plurality: {} },
+          candidateIds,
+          registrar,
+          voters,
+        });
+        [resultPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("election_result"), setup.electionPda.toBuffer()],
+          bank.program.programId
+        );
+
+        await warpTo(bank, setup.startTime);
+        await transitionElection(bank.program, setup.electionPda, { voting: {} });
+        for (const [sequence, voter] of voters.entries()) {
+          await castForCandidate(voter, sequence, choices[sequence]);
+        }
+
+        await warpTo(bank, setup.endTime + 1);
+        await transitionElection(bank.program, setup.electionPda, { closed: {} });
+        await transitionElection(bank.program, setup.electionPda, { tallied: {} });
+      });
+
+      it("Should require a runoff when no ticket has the provincial spread", async () => {
+        // The only province, then its tally for each ticket; ticket 1 has
+        // none yet, so its PDA is passed uncreated
+        const province = setup.regionChain[setup.regionChain.length - 1];
+        await bank.program.methods
+          .tallyProvinces(candidateIds)
+          .accounts({
+            payer: bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            electionResult: resultPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            [
+              province,
+              ...candidateIds.map(
+                (candidateId) =>
+                  deriveRegionTallyPDA(province, candidateId, bank.program.programId)[0]
+              ),
+            ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+          )
+          .rpc();
+
+        await bank.program.methods
+          .computeResult()
+          .accounts({ election: setup.electionPda, electionResult: resultPda })
+          .remainingAccounts(
+            candidateIds.map((candidateId) => ({
+              pubkey: deriveCandidatePDA(setup.electionPda, candidateId, bank.program.programId)[0],
+              isSigner: false,
+              isWritable: false,
+            }))
+          )
+          .rpc();
+
+        // Ticket 2 has two thirds of the vote, but in one province of 38
+        const result = await bank.program.account.electionResult.fetch(resultPda);
+        expect(result.provincialVoteSums.map((votes) => votes.toNumber())).to.deep.equal([0, 2, 1]);
+        expect(result.outcome).to.deep.equal({
+          runoffRequired: { firstCandidateId: 2, secondCandidateId: 3 },
+        });
+        console.log("   ✅ Runoff required between tickets 2 and 3");
+      });
+
+      it("Should carry only the top two tickets into the runoff", async () => {
+        const now = await bankTime(bank);
+        const schedule = {
+          electionName: "Pilpres Putaran Kedua",
+          registrationStart: new BN(now - 60),
+          registrationEnd: new BN(now + 3600),
+          startTime: new BN(now + 3600),
+          endTime: new BN(now + 3600 + 86400),
+        };
+        const proposalPda = await createProposal(bank.program, setup.electionPda, {
+          createRunoff: schedule,
+        });
+        [runoffPda] = await deriveNextElectionPDA(bank.program);
+        await bank.program.methods
+          .createRunoff(
+            schedule.electionName,
+            schedule.registrationStart,
+            schedule.registrationEnd,
+            schedule.startTime,
+            schedule.endTime
+          )
+          .accounts({
+            commissioner: bank.provider.wallet.publicKey,
+            registry: deriveElectionRegistryPDA(bank.program.programId)[0],
+            election: setup.electionPda,
+            electionResult: resultPda,
+            proposal: proposalPda,
+            runoffElection: runoffPda,
+            runoffVotingTokenMint: deriveVotingTokenMintPDA(runoffPda, bank.program.programId)[0],
+            firstCandidate: deriveCandidatePDA(setup.electionPda, 2, bank.program.programId)[0],
+            secondCandidate: deriveCandidatePDA(setup.electionPda, 3, bank.program.programId)[0],
+            runoffFirstCandidate: deriveCandidatePDA(runoffPda, 2, bank.program.programId)[0],
+            runoffSecondCandidate: deriveCandidatePDA(runoffPda, 3, bank.program.programId)[0],
+            firstBallotNumberRecord: deriveBallotNumberPDA(runoffPda, 2, bank.program.programId)[0],
+            secondBallotNumberRecord: deriveBallotNumberPDA(runoffPda, 3, bank.program.programId)[0],
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc();
+
+        const parent = await bank.program.account.election.fetch(setup.electionPda);
+        const runoff = await bank.program.account.election.fetch(runoffPda);
+        expect(runoff.parentElection.toString()).to.equal(setup.electionPda.toString());
+        expect(runoff.status).to.deep.equal({ draft: {} });
+        expect(runoff.votingMode).to.deep.equal({ plurality: {} });
+        expect(runoff.nikSalt).to.deep.equal(parent.nikSalt);
+        expect(runoff.commissioners.map((key) => key.toString())).to.deep.equal(
+          parent.commissioners.map((key) => key.toString())
+        );
+        expect(runoff.registrars.map((key) => key.toString())).to.deep.equal(
+          parent.registrars.map((key) => key.toString())
+        );
+        // Voters re-register, and the ballot issuer is set anew
+        expect(runoff.totalRegisteredVoters.toNumber()).to.equal(0);
+        expect(runoff.ballotIssuer).to.deep.equal(Array(32).fill(0));
+
+        for (const candidateId of [2, 3]) {
+          const ticket = await bank.program.account.candidate.fetch(
+            deriveCandidatePDA(runoffPda, candidateId, bank.program.programId)[0]
+          );
+          expect(ticket.ballotNumber).to.equal(candidateId);
+          expect(ticket.voteCount.toNumber()).to.equal(0);
+        }
+        const dropped = await bank.program.account.candidate.fetchNullable(
+          deriveCandidatePDA(runoffPda, 1, bank.program.programId)[0]
+        );
+        expect(dropped).to.be.null;
+
+        const result = await bank.program.account.electionResult.fetch(resultPda);
+        expect(result.runoffElection.toString()).to.equal(runoffPda.toString());
+        console.log("   ✅ Runoff created with tickets 2 and 3");
+      });
+
+      it("Should carry a first-round voter over without re-verification", async () => {
+        const { pollingStationPda } = await createBankRegionChain(bank.program, runoffPda);
+        await transitionElection(bank.program, runoffPda, { registration: {} });
+
+        const voter = voters[0];
+        const parentCredential = await bank.program.account.voterCredential.fetch(
+          deriveVoterCredentialPDA(setup.electionPda, voter.publicKey, bank.program.programId)[0]
+        );
+        const [voterCredentialPda] = deriveVoterCredentialPDA(
+          runoffPda,
+          voter.publicKey,
+          bank.program.programId
+        );
+        const [mintPda] = deriveVotingTokenMintPDA(runoffPda, bank.program.programId);
+
+        await bank.program.methods
+          .carryOverVoter()
+          .accounts({
+            voter: voter.publicKey,
+            election: runoffPda,
+            parentElection: setup.electionPda,
+            parentVoterCredential: deriveVoterCredentialPDA(
+              setup.electionPda,
+              voter.publicKey,
+              bank.program.programId
+            )[0],
+            parentPollingStation: setup.pollingStationPda,
+            pollingStation: pollingStationPda,
+            voterCredential: voterCredentialPda,
+            nikRecord: deriveNikRecordPDA(
+              runoffPda,
+              parentCredential.voterNikCommitment,
+              bank.program.programId
+            )[0],
+            votingTokenMint: mintPda,
+            voterTokenAccount: await getAssociatedTokenAddress(mintPda, voter.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([voter])
+          .rpc();
+
+        const credential = await bank.program.account.voterCredential.fetch(voterCredentialPda);
+        expect(credential.isVerified).to.equal(true);
+        expect(credential.hasVoted).to.equal(false);
+        expect(credential.voterNikCommitment).to.deep.equal(parentCredential.voterNikCommitment);
+        expect(credential.pollingStation.toString()).to.equal(pollingStationPda.toString());
+        const runoff = await bank.program.account.election.fetch(runoffPda);
+        expect(runoff.totalRegisteredVoters.toNumber()).to.equal(1);
+        console.log("   ✅ First-round credential carried over");
+      });
+    });
+  });
+
+  // ============================================================================
+  // 11. Legislative Election Tests
+  // ============================================================================
+
+  describe("11. Legislative Election", () => {
+    const LEGISLATIVE_ELECTION_NAME = "Pemilu DPR";
+    const districtNumber = 1;
+    const partyId = 1;
+    let legislativeElectionPda: PublicKey;
+    let districtPda: PublicKey;
+    let partyPda: PublicKey;
+
+    const u16Buffer = (value: number) => {
+      const buffer = Buffer.alloc(2);
+      buffer.writeUInt16LE(value);
+      return buffer;
+    };
+
+    // Each dapil region's DistrictRegion PDA, then those of its parents up
+    // to the province (regency 3171 -> province 31)
+    const districtRegionAccounts = (regionCodes: BN[]) =>
+      regionCodes.flatMap((regionCode) => {
+        const chain: number[] = [];
+        let code = regionCode.toNumber();
+        for (; code >= 100; code = Math.floor(code / 100)) {
+          chain.push(code);
+        }
+        chain.push(code);
+        return chain.map((chainCode) => ({
+          pubkey: PublicKey.findProgramAddressSync(
+            [
+              Buffer.from("district_region"),
+              legislativeElectionPda.toBuffer(),
+              new BN(chainCode).toArrayLike(Buffer, "le", 8),
+            ],
+            program.programId
+          )[0],
+          isSigner: false,
+          isWritable: true,
+        }));
+      });
+
+    before(async () => {
+      [legislativeElectionPda] = await deriveNextElectionPDA(program);
+      const [mintPda] = deriveVotingTokenMintPDA(
+        legislativeElectionPda,
+        program.programId
+      );
+      [districtPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("electoral_district"),
+          legislativeElectionPda.toBuffer(),
+          u16Buffer(districtNumber),
+        ],
+        program.programId
+      );
+      [partyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("party"), legislativeElectionPda.toBuffer(), u16Buffer(partyId)],
+        program.programId
+      );
+
+      await program.methods
+        .initializeElection(
+          LEGISLATIVE_ELECTION_NAME,
+          new BN(registrationStartTime),
+          new BN(electionStartTime),
+          new BN(electionStartTime),
+          new BN(electionEndTime),
+          [provider.wallet.publicKey],
+          1,
+          { legislative: {} }
+        )
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: registryPda,
+          election: legislativeElectionPda,
+          votingTokenMint: mintPda,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+    });
+
+    it("Should reject a dapil drawn from villages", async () => {
+      try {
+        await createProposal(program, legislativeElectionPda, {
+          createElectoralDistrict: {
+            districtNumber,
+            name: "DKI Jakarta II",
+            seats: 7,
+            regionCodes: [new BN(VILLAGE_CODE)],
+          },
+        });
+
+        expect.fail("Should have thrown InvalidElectoralDistrict error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidElectoralDistrict");
+        console.log("   ✅ Correctly rejected a village-level dapil");
+      }
+    });
+
+    it("Should reject party number zero", async () => {
+      try {
+        await createProposal(program, legislativeElectionPda, {
+          registerParty: { partyId: 0, name: "Partai Nol" },
+        });
+
+        expect.fail("Should have thrown InvalidBallotNumber error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidBallotNumber");
+        console.log("   ✅ Correctly rejected party number zero");
+      }
+    });
+
+    it("Should not register parties in a presidential election", async () => {
+      const proposalPda = await createProposal(program, electionPda, {
+        registerParty: { partyId, name: "Partai Satu" },
+      });
+      const [presidentialPartyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("party"), electionPda.toBuffer(), u16Buffer(partyId)],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .registerParty(partyId, "Partai Satu")
+          .accounts({
+            commissioner: provider.wallet.publicKey,
+            election: electionPda,
+            proposal: proposalPda,
+            party: presidentialPartyPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown VotingModeMismatch error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("VotingModeMismatch");
+        console.log("   ✅ Correctly rejected a party outside a legislative election");
+      }
+    });
+
+    it("Should register a dapil, a party and its list candidate", async () => {
+      const regionCodes = [new BN(3171), new BN(3174)];
+      const districtProposal = await createProposal(program, legislativeElectionPda, {
+        createElectoralDistrict: {
+          districtNumber,
+          name: "DKI Jakarta II",
+          seats: 7,
+          regionCodes,
+        },
+      });
+      await program.methods
+        .createElectoralDistrict(districtNumber, "DKI Jakarta II", 7, regionCodes)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: legislativeElectionPda,
+          proposal: districtProposal,
+          electoralDistrict: districtPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(districtRegionAccounts(regionCodes))
+        .rpc();
+
+      const partyProposal = await createProposal(program, legislativeElectionPda, {
+        registerParty: { partyId, name: "Partai Satu" },
+      });
+      await program.methods
+        .registerParty(partyId, "Partai Satu")
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: legislativeElectionPda,
+          proposal: partyProposal,
+          party: partyPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const listNumber = 1;
+      const [listCandidatePda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("list_candidate"),
+          legislativeElectionPda.toBuffer(),
+          u16Buffer(partyId),
+          u16Buffer(districtNumber),
+          Buffer.from([listNumber]),
+        ],
+        program.programId
+      );
+      const candidateProposal = await createProposal(program, legislativeElectionPda, {
+        registerListCandidate: {
+          partyId,
+          districtNumber,
+          listNumber,
+          name: "Caleg Satu",
+        },
+      });
+      await program.methods
+        .registerListCandidate(partyId, districtNumber, listNumber, "Caleg Satu")
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: legislativeElectionPda,
+          proposal: candidateProposal,
+          party: partyPda,
+          electoralDistrict: districtPda,
+          listCandidate: listCandidatePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const district = await program.account.electoralDistrict.fetch(districtPda);
+      expect(district.seats).to.equal(7);
+      expect(district.regionCodes.map((code) => code.toNumber())).to.deep.equal([
+        3171, 3174,
+      ]);
+
+      const party = await program.account.party.fetch(partyPda);
+      expect(party.name).to.equal("Partai Satu");
+      expect(party.voteCount.toNumber()).to.equal(0);
+      expect(party.districtVotes.length).to.equal(0);
+
+      const listCandidate = await program.account.partyListCandidate.fetch(
+        listCandidatePda
+      );
+      expect(listCandidate.party.toString()).to.equal(partyPda.toString());
+      expect(listCandidate.districtNumber).to.equal(districtNumber);
+      expect(listCandidate.listNumber).to.equal(listNumber);
+
+      const election = await program.account.election.fetch(legislativeElectionPda);
+      expect(election.partyCount).to.equal(1);
+      expect(
+        (await program.account.electoralDistrict.fetch(districtPda)).listCandidateCount
+      ).to.equal(1);
+      console.log("   ✅ Dapil, party and list candidate registered");
+    });
+
+    it("Should reject a dapil with more than 12 seats", async () => {
+      try {
+        await createProposal(program, legislativeElectionPda, {
+          createElectoralDistrict: {
+            districtNumber: 2,
+            name: "Jawa Barat III",
+            seats: 13,
+            regionCodes: [new BN(3201)],
+          },
+        });
+
+        expect.fail("Should have thrown InvalidElectoralDistrict error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidElectoralDistrict");
+        console.log("   ✅ Correctly rejected an oversized dapil");
+      }
+    });
+
+    it("Should reject a dapil overlapping another", async () => {
+      const [secondDistrictPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("electoral_district"),
+          legislativeElectionPda.toBuffer(),
+          u16Buffer(2),
+        ],
+        program.programId
+      );
+
+      // Dapil 1 is regencies 3171 and 3174: the same regency, the province
+      // above them and a district inside 3171 all overlap it
+      for (const regionCodes of [[3172, 3174], [31], [317101]]) {
+        const codes = regionCodes.map((code) => new BN(code));
+        const proposalPda = await createProposal(program, legislativeElectionPda, {
+          createElectoralDistrict: {
+            districtNumber: 2,
+            name: "DKI Jakarta III",
+            seats: 8,
+            regionCodes: codes,
+          },
+        });
+
+        try {
+          await program.methods
+            .createElectoralDistrict(2, "DKI Jakarta III", 8, codes)
+            .accounts({
+              commissioner: provider.wallet.publicKey,
+              election: legislativeElectionPda,
+              proposal: proposalPda,
+              electoralDistrict: secondDistrictPda,
+              systemProgram: SystemProgram.programId,
+            })
+            .remainingAccounts(districtRegionAccounts(codes))
+            .rpc();
+
+          expect.fail("Should have thrown DistrictOverlap error");
+        } catch (error: any) {
+          expect(error.error.errorCode.code).to.equal("DistrictOverlap");
+        }
+      }
+
+      const secondDistrict = await program.account.electoralDistrict.fetchNullable(
+        secondDistrictPda
+      );
+      expect(secondDistrict).to.be.null;
+      console.log("   ✅ Correctly rejected dapils sharing, containing or inside a region");
+    });
+
+    it("Should default to a 4% threshold and reject one above 100%", async () => {
+      const election = await program.account.election.fetch(legislativeElectionPda);
+      expect(election.parliamentaryThresholdBps).to.equal(400);
+
+      try {
+        await createProposal(program, legislativeElectionPda, {
+          setParliamentaryThreshold: { thresholdBps: 10_001 },
+        });
+
+        expect.fail("Should have thrown InvalidParliamentaryThreshold error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidParliamentaryThreshold");
+        console.log("   ✅ Correctly rejected a threshold above 100%");
+      }
+    });
+
+    it("Should set the parliamentary threshold by proposal", async () => {
+      const proposalPda = await createProposal(program, legislativeElectionPda, {
+        setParliamentaryThreshold: { thresholdBps: 500 },
+      });
+      await program.methods
+        .setParliamentaryThreshold(500)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: legislativeElectionPda,
+          proposal: proposalPda,
+        })
+        .rpc();
+
+      const election = await program.account.election.fetch(legislativeElectionPda);
+      expect(election.parliamentaryThresholdBps).to.equal(500);
+      console.log("   ✅ Threshold set to 5%");
+    });
+
+    it("Should not allocate seats before the election is tallied", async () => {
+      const [seatAllocationPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("seat_allocation"),
+          legislativeElectionPda.toBuffer(),
+          u16Buffer(districtNumber),
+        ],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .allocateSeats(districtNumber)
+          .accounts({
+            payer: provider.wallet.publicKey,
+            election: legislativeElectionPda,
+            electoralDistrict: districtPda,
+            seatAllocation: seatAllocationPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([
+            { pubkey: partyPda, isSigner: false, isWritable: false },
+          ])
+          .rpc();
+
+        expect.fail("Should have thrown ResultNotReady error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("ResultNotReady");
+        console.log("   ✅ Correctly refused to allocate seats early");
+      }
+    });
+  });
+
+  // ============================================================================
+  // 12. Election Event Tests
+  // ============================================================================
+
+  describe("12. Election Event", () => {
+    let hostElectionPda: PublicKey;
+    let contestElectionPda: PublicKey;
+    let electionEventPda: PublicKey;
+
+    const initElection = async (name: string, endTime: number, votingMode: any) => {
+      const [pda] = await deriveNextElectionPDA(program);
+      const [mintPda] = deriveVotingTokenMintPDA(pda, program.programId);
+      await program.methods
+        .initializeElection(
+          name,
+          new BN(registrationStartTime),
+          new BN(electionStartTime),
+          new BN(electionStartTime),
+          new BN(endTime),
+          [provider.wallet.publicKey],
+          1,
+          votingMode
+        )
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: registryPda,
+          election: pda,
+          votingTokenMint: mintPda,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+      return pda;
+    };
+
+    const addContest = async (contest: PublicKey) => {
+      const proposalPda = await createProposal(program, hostElectionPda, {
+        addEventContest: { contest },
+      });
+      await program.methods
+        .addEventContest()
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: hostElectionPda,
+          proposal: proposalPda,
+          electionEvent: electionEventPda,
+          contest,
+        })
+        .rpc();
+    };
+
+    before(async () => {
+      hostElectionPda = await initElection("Pilpres 2029", electionEndTime, {
+        plurality: {},
+      });
+      contestElectionPda = await initElection("Pileg DPR 2029", electionEndTime, {
+        legislative: {},
+      });
+      [electionEventPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("election_event"), hostElectionPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("Should create an event hosted by the presidential election", async () => {
+      const name = "Pemilu Serentak 2029";
+      const proposalPda = await createProposal(program, hostElectionPda, {
+        createElectionEvent: { name },
+      });
+      await program.methods
+        .createElectionEvent(name)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: hostElectionPda,
+          proposal: proposalPda,
+          electionEvent: electionEventPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const event = await program.account.electionEvent.fetch(electionEventPda);
+      expect(event.host.toString()).to.equal(hostElectionPda.toString());
+      expect(event.contests.map((key) => key.toString())).to.deep.equal([
+        hostElectionPda.toString(),
+      ]);
+      const host = await program.account.election.fetch(hostElectionPda);
+      expect(host.event.toString()).to.equal(electionEventPda.toString());
+      console.log("   ✅ Event created with the host as its first contest");
+    });
+
+    it("Should reject a contest with a different polling window", async () => {
+      const lateContestPda = await initElection("Pemilu DPD 2029", electionEndTime + 3600, {
+        plurality: {},
+      });
+
+      try {
+        await addContest(lateContestPda);
+
+        expect.fail("Should have thrown EventContestMismatch error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("EventContestMismatch");
+        console.log("   ✅ Correctly rejected a contest outside the shared window");
+      }
+    });
+
+    it("Should add a legislative contest to the event", async () => {
+      await addContest(contestElectionPda);
+
+      const event = await program.account.electionEvent.fetch(electionEventPda);
+      expect(event.contests.map((key) => key.toString())).to.deep.equal([
+        hostElectionPda.toString(),
+        contestElectionPda.toString(),
+      ]);
+      const contest = await program.account.election.fetch(contestElectionPda);
+      expect(contest.event.toString()).to.equal(electionEventPda.toString());
+      console.log("   ✅ DPR contest joined the event");
+    });
+
+    it("Should not add the same contest twice", async () => {
+      try {
+        await addContest(contestElectionPda);
+
+        expect.fail("Should have thrown ElectionInEvent error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("ElectionInEvent");
+        console.log("   ✅ Correctly rejected a duplicate contest");
+      }
+    });
+  });
+
+  // ============================================================================
+  // 13. Ranked Choice Tests
+  // ============================================================================
+
+  describe("13. Ranked Choice", () => {
+    let rankedElectionPda: PublicKey;
+    let irvTallyPda: PublicKey;
+
+    const registerCandidate = async (candidateId: number) => {
+      const ticket = candidateTicket(`Ticket Ranked ${candidateId}`, candidateId);
+      const proposalPda = await createProposal(program, rankedElectionPda, {
+        registerCandidate: { candidateId, ticket },
+      });
+      await program.methods
+        .registerCandidate(ticket, candidateId)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: rankedElectionPda,
+          proposal: proposalPda,
+          candidate: deriveCandidatePDA(rankedElectionPda, candidateId, program.programId)[0],
+          ballotNumberRecord: deriveBallotNumberPDA(
+            rankedElectionPda,
+            candidateId,
+            program.programId
+          )[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    };
+
+    before(async () => {
+      [rankedElectionPda] = await deriveNextElectionPDA(program);
+      const [mintPda] = deriveVotingTokenMintPDA(rankedElectionPda, program.programId);
+      [irvTallyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("irv_tally"), rankedElectionPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeElection(
+          "RankedElection",
+          new BN(registrationStartTime),
+          new BN(electionStartTime),
+          new BN(electionStartTime),
+          new BN(electionEndTime),
+          [provider.wallet.publicKey],
+          1,
+          { rankedChoice: {} }
+        )
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: registryPda,
+          election: rankedElectionPda,
+          votingTokenMint: mintPda,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+
+      await registerCandidate(1);
+      await registerCandidate(2);
+    });
+
+    it("Should reject a ranked-choice proposal with one candidate", async () => {
+      try {
+        await createProposal(program, rankedElectionPda, {
+          configureRankedChoice: { candidateIds: [1] },
+        });
+
+        expect.fail("Should have thrown NotEnoughCandidates error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NotEnoughCandidates");
+        console.log("   ✅ Correctly rejected a single-candidate runoff");
+      }
+    });
+
+    it("Should configure the instant-runoff tally", async () => {
+      const candidateIds = [1, 2];
+      const proposalPda = await createProposal(program, rankedElectionPda, {
+        configureRankedChoice: { candidateIds },
+      });
+      await program.methods
+        .configureRankedChoice(candidateIds)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: rankedElectionPda,
+          proposal: proposalPda,
+          irvTally: irvTallyPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          candidateIds.map((candidateId) => ({
+            pubkey: deriveCandidatePDA(rankedElectionPda, candidateId, program.programId)[0],
+            isSigner: false,
+            isWritable: false,
+          }))
+        )
+        .rpc();
+
+      const tally = await program.account.irvTally.fetch(irvTallyPda);
+      expect(tally.candidateIds).to.deep.equal(candidateIds);
+      expect(tally.continuing).to.deep.equal([true, true]);
+      expect(tally.rounds).to.have.length(0);
+      expect(tally.winner).to.be.null;
+      console.log("   ✅ IRV tally created with both candidates continuing");
+    });
+
+    it("Should not count an election still in registration", async () => {
+      try {
+        await program.methods
+          .tallyIrv()
+          .accounts({
+            election: rankedElectionPda,
+            irvTally: irvTallyPda,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown RecapitulationClosed error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("RecapitulationClosed");
+        console.log("   ✅ Correctly refused to count before polls close");
+      }
+    });
+
+    describe("All blank", () => {
+      const candidateIds = [1, 2];
+      const voters = [Keypair.generate(), Keypair.generate()];
+      let bank: Bank;
+      let setup: BankElection;
+      let bankIrvTallyPda: PublicKey;
+
+      const castBlank = async (voter: Keypair, sequence: number) => {
+        const key = ballotKey();
+        const signature = await obtainBallotToken(
+          bank.program,
+          setup.electionPda,
+          setup.pollingStationPda,
+          voter,
+          key.nullifier
+        );
+        const contentsSignature = signBallotContents(setup.electionPda, key, [
+          Buffer.from([1]), // BallotKind::Blank, with nothing ranked
+        ]);
+        await bank.program.methods
+          .castRankedVote(
+            Array.from(key.nullifier),
+            signature,
+            contentsSignature,
+            { blank: {} },
+            []
+          )
+          .accounts({
+            relayer: bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            pollingStation: setup.pollingStationPda,
+            irvTally: bankIrvTallyPda,
+            nullifierRecord: PublicKey.findProgramAddressSync(
+              [Buffer.from("nullifier"), setup.electionPda.toBuffer(), key.nullifier],
+              bank.program.programId
+            )[0],
+            ballot: deriveBallotPDA(setup.electionPda, sequence, bank.program.programId)[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            setup.regionChain.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+          )
+          .rpc();
+      };
+
+      before(async () => {
+        bank = await startBank(program.idl);
+        setup = await setupBankElection(bank, {
+          name: "Pilkada Jayapura",
+          votingMode: { rankedChoice: {} },
+          candidateIds,
+          registrar,
+          voters,
+        });
+        [bankIrvTallyPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("irv_tally"), setup.electionPda.toBuffer()],
+          bank.program.programId
+        );
+
+        const proposalPda = await createProposal(bank.program, setup.electionPda, {
+          configureRankedChoice: { candidateIds },
+        });
+        await bank.program.methods
+          .configureRankedChoice(candidateIds)
+          .accounts({
+            commissioner: bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            proposal: proposalPda,
+            irvTally: bankIrvTallyPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            candidateIds.map((candidateId) => ({
+              pubkey: deriveCandidatePDA(setup.electionPda, candidateId, bank.program.programId)[0],
+              isSigner: false,
+              isWritable: false,
+            }))
+          )
+          .rpc();
+
+        await warpTo(bank, setup.startTime);
+        await transitionElection(bank.program, setup.electionPda, { voting: {} });
+        await castBlank(voters[0], 0);
+        await castBlank(voters[1], 1);
+
+        await warpTo(bank, setup.endTime + 1);
+        await transitionElection(bank.program, setup.electionPda, { closed: {} });
+      });
+
+      it("Should declare no winner when every ballot is blank", async () => {
+        try {
+          await bank.program.methods
+            .tallyIrv()
+            .accounts({
+              election: setup.electionPda,
+              irvTally: bankIrvTallyPda,
+            })
+            .remainingAccounts(
+              [0, 1].map((sequence) => ({
+                pubkey: deriveBallotPDA(setup.electionPda, sequence, bank.program.programId)[0],
+                isSigner: false,
+                isWritable: false,
+              }))
+            )
+            .rpc();
+
+          expect.fail("Should have thrown NoContinuingVotes error");
+        } catch (error: any) {
+          expect(programErrorCode(bank.program, error)).to.equal("NoContinuingVotes");
+        }
+
+        const tally = await bank.program.account.irvTally.fetch(bankIrvTallyPda);
+        expect(tally.winner).to.be.null;
+        expect(tally.rounds).to.have.length(0);
+        expect(tally.continuing).to.deep.equal([true, true]);
+        console.log("   ✅ All-blank election elects no one");
+      });
+    });
+  });
+
+  // ============================================================================
+  // 14. Multi-Seat Tests
+  // ============================================================================
+
+  describe("14. Multi-Seat", () => {
+    let multiSeatElectionPda: PublicKey;
+
+    const initElection = async (name: string, votingMode: any) => {
+      const [pda] = await deriveNextElectionPDA(program);
+      const [mintPda] = deriveVotingTokenMintPDA(pda, program.programId);
+      await program.methods
+        .initializeElection(
+          name,
+          new BN(registrationStartTime),
+          new BN(electionStartTime),
+          new BN(electionStartTime),
+          new BN(electionEndTime),
+          [provider.wallet.publicKey],
+          1,
+          votingMode
+        )
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: registryPda,
+          election: pda,
+          votingTokenMint: mintPda,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+      return pda;
+    };
+
+    const setMaxSelections = async (election: PublicKey, maxSelections: number) => {
+      const proposalPda = await createProposal(program, election, {
+        setMaxSelections: { maxSelections },
+      });
+      await program.methods
+        .setMaxSelections(maxSelections)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election,
+          proposal: proposalPda,
+        })
+        .rpc();
+    };
+
+    before(async () => {
+      multiSeatElectionPda = await initElection("Pemilu DPD Jawa Barat", { plurality: {} });
+    });
+
+    it("Should default to one selection and reject more than eight", async () => {
+      const election = await program.account.election.fetch(multiSeatElectionPda);
+      expect(election.maxSelections).to.equal(1);
+
+      try {
+        await createProposal(program, multiSeatElectionPda, {
+          setMaxSelections: { maxSelections: 9 },
+        });
+
+        expect.fail("Should have thrown InvalidMaxSelections error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidMaxSelections");
+        console.log("   ✅ Correctly rejected nine selections");
+      }
+    });
+
+    it("Should not allow several selections in commit-reveal mode", async () => {
+      const commitRevealElectionPda = await initElection("Pemilihan Pengurus", {
+        commitReveal: {},
+      });
+
+      try {
+        await setMaxSelections(commitRevealElectionPda, 4);
+
+        expect.fail("Should have thrown InvalidMaxSelections error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidMaxSelections");
+        console.log("   ✅ Correctly kept commitment ballots to one choice");
+      }
+    });
+
+    it("Should elect four DPD members per province", async () => {
+      await setMaxSelections(multiSeatElectionPda, 4);
+
+      const election = await program.account.election.fetch(multiSeatElectionPda);
+      expect(election.maxSelections).to.equal(4);
+      expect(election.totalSelections.toNumber()).to.equal(0);
+      console.log("   ✅ Ballots may mark up to four candidates");
+    });
+
+    it("Should not tally seats before the election is tallied", async () => {
+      const [multiSeatResultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("multi_seat_result"), multiSeatElectionPda.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .tallyMultiSeat()
+          .accounts({
+            payer: provider.wallet.publicKey,
+            election: multiSeatElectionPda,
+            multiSeatResult: multiSeatResultPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown ResultNotReady error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("ResultNotReady");
+        console.log("   ✅ Correctly refused to elect before the tally");
+      }
+    });
+
+    describe("Certification", () => {
+      const candidateIds = [1, 2, 3];
+      const voters = [Keypair.generate(), Keypair.generate()];
+      let bank: Bank;
+      let setup: BankElection;
+      let resultPda: PublicKey;
+
+      const castForCandidates = async (voter: Keypair, sequence: number, choices: number[]) => {
+        const key = ballotKey();
+        const signature = await obtainBallotToken(
+          bank.program,
+          setup.electionPda,
+          setup.pollingStationPda,
+          voter,
+          key.nullifier
+        );
+        const candidatePdas = choices.map(
+          (candidateId) =>
+            deriveCandidatePDA(setup.electionPda, candidateId, bank.program.programId)[0]
+        );
+        const contentsSignature = signBallotContents(setup.electionPda, key, [
+          Buffer.from([0]), // BallotKind::Candidate
+          Buffer.alloc(32),
+          ...candidatePdas.map((pda) => pda.toBuffer()),
+        ]);
+        // Further choices lead the remaining accounts, then the region
+        // chain, then each choice's tally in every region
+        await bank.program.methods
+          .castVote(
+            Array.from(key.nullifier),
+            { blindToken: { signature, contentsSignature } },
+            { candidate: {} },
+            Array(32).fill(0)
+          )
+          .accounts({
+            relayer: bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            candidate: candidatePdas[0],
+            pollingStation: setup.pollingStationPda,
+            nullifierRecord: PublicKey.findProgramAddressSync(
+              [Buffer.from("nullifier"), setup.electionPda.toBuffer(), key.nullifier],
+              bank.program.programId
+            )[0],
+            ballot: deriveBallotPDA(setup.electionPda, sequence, bank.program.programId)[0],
+            zkVerifyingKey: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            [
+              ...candidatePdas.slice(1),
+              ...setup.regionChain,
+              ...choices.flatMap((candidateId) =>
+                setup.regionChain.map(
+                  (region) => deriveRegionTallyPDA(region, candidateId, bank.program.programId)[0]
+                )
+              ),
+            ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+          )
+          .rpc();
+      };
+
+      const tallyMultiSeat = (candidates: number[]) =>
+        bank.program.methods
+          .tallyMultiSeat()
+          .accounts({
+            payer: bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            multiSeatResult: resultPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            candidates.map((candidateId) => ({
+              pubkey: deriveCandidatePDA(setup.electionPda, candidateId, bank.program.programId)[0],
+              isSigner: false,
+              isWritable: false,
+            }))
+          )
+          .rpc();
+
+      const expectCertificationPending = async () => {
+        try {
+          await transitionElection(bank.program, setup.electionPda, { certified: {} });
+
+          expect.fail("Should have thrown MultiSeatResultPending error");
+        } catch (error: any) {
+          expect(programErrorCode(bank.program, error)).to.equal("MultiSeatResultPending");
+        }
+      };
+
+      before(async () => {
+        bank = await startBank(program.idl);
+        setup = await setupBankElection(bank, {
+          name: "Pemilu DPD Papua",
+          votingMode: { plurality: {} },
+          candidateIds,
+          registrar,
+          voters,
+        });
+        [resultPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("multi_seat_result"), setup.electionPda.toBuffer()],
+          bank.program.programId
+        );
+
+        const proposalPda = await createProposal(bank.program, setup.electionPda, {
+          setMaxSelections: { maxSelections: 2 },
+        });
+        await bank.program.methods
+          .setMaxSelections(2)
+          .accounts({
+            commissioner: bank.provider.wallet.publicKey,
+            election: setup.electionPda,
+            proposal: proposalPda,
+          })
+          .rpc();
+
+        await warpTo(bank, setup.startTime);
+        await transitionElection(bank.program, setup.electionPda, { voting: {} });
+        await castForCandidates(voters[0], 0, [1, 3]);
+        await castForCandidates(voters[1], 1, [3]);
+
+        await warpTo(bank, setup.endTime + 1);
+        await transitionElection(bank.program, setup.electionPda, { closed: {} });
+        await transitionElection(bank.program, setup.electionPda, { tallied: {} });
+      });
+
+      it("Should not certify before the seats are tallied", async () => {
+        await expectCertificationPending();
+        console.log("   ✅ Correctly refused to certify an untallied multi-seat race");
+      });
+
+      it("Should not certify while candidates are left out of the tally", async () => {
+        await tallyMultiSeat([1]);
+
+        const result = await bank.program.account.multiSeatResult.fetch(resultPda);
+        expect(result.complete).to.equal(false);
+        await expectCertificationPending();
+        console.log("   ✅ Correctly refused to certify with ticket 3 uncounted");
+      });
+
+      it("Should certify once every candidate is tallied", async () => {
+        await tallyMultiSeat([2, 3]);
+
+        const result = await bank.program.account.multiSeatResult.fetch(resultPda);
+        expect(result.complete).to.equal(true);
+        expect(result.votesCounted.toNumber()).to.equal(3);
+        expect(
+          result.elected.map((entry) => [entry.candidateId, entry.voteCount.toNumber()])
+        ).to.deep.equal([
+          [3, 2],
+          [1, 1],
+        ]);
+
+        await transitionElection(bank.program, setup.electionPda, { certified: {} });
+        const election = await bank.program.account.election.fetch(setup.electionPda);
+        expect(election.multiSeatTallied).to.equal(true);
+        expect(election.status).to.deep.equal({ certified: {} });
+        console.log("   ✅ Tickets 3 and 1 elected and certified");
+      });
+    });
+  });
+
+
+  // ============================================================================
+  // 15. Region Tally Tests
+  // ============================================================================
+
+  describe("15. Region Tallies", () => {
+    const CANDIDATE_COUNT = 9;
+    const TALLY_VILLAGE_CODE = 9171011001; // Gurabesi, Jayapura Utara, Kota Jayapura
+    const tallyVoter = Keypair.generate();
+    let tallyElectionPda: PublicKey;
+    let tallyStationPda: PublicKey;
+    let tallyRegionChain: PublicKey[];
+    let tallyStartTime: number;
+
+    before(async () => {
+      [tallyElectionPda] = await deriveNextElectionPDA(program);
+      const [mintPda] = deriveVotingTokenMintPDA(tallyElectionPda, program.programId);
+      // Polls open soon after setup; Papua keeps WIT hours, so they open
+      // there at start_time
+      tallyStartTime = getCurrentTimestamp() + 45;
+
+      await program.methods
+        .initializeElection(
+          "Pemilu Kota Jayapura",
+          new BN(getCurrentTimestamp() - 60),
+          new BN(tallyStartTime),
+          new BN(tallyStartTime),
+          new BN(tallyStartTime + ELECTION_DURATION),
+          [provider.wallet.publicKey],
+          1,
+          { plurality: {} }
+        )
+        .accounts({
+          authority: provider.wallet.publicKey,
+          registry: registryPda,
+          election: tallyElectionPda,
+          votingTokenMint: mintPda,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+
+      for (let candidateId = 1; candidateId <= CANDIDATE_COUNT; candidateId++) {
+        const ticket = candidateTicket(`Ticket Jayapura ${candidateId}`, candidateId);
+        const proposalPda = await createProposal(program, tallyElectionPda, {
+          registerCandidate: { candidateId, ticket },
+        });
+        await program.methods
+          .registerCandidate(ticket, candidateId)
+          .accounts({
+            commissioner: provider.wallet.publicKey,
+            election: tallyElectionPda,
+            proposal: proposalPda,
+            candidate: deriveCandidatePDA(tallyElectionPda, candidateId, program.programId)[0],
+            ballotNumberRecord: deriveBallotNumberPDA(
+              tallyElectionPda,
+              candidateId,
+              program.programId
+            )[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      const province = await createRegion(program, tallyElectionPda, 91, "Papua", null);
+      const regency = await createRegion(program, tallyElectionPda, 9171, "Kota Jayapura", 91);
+      const district = await createRegion(program, tallyElectionPda, 917101, "Jayapura Utara", 9171);
+      const village = await createRegion(
+        program,
+        tallyElectionPda,
+        TALLY_VILLAGE_CODE,
+        "Gurabesi",
+        917101
+      );
+      tallyRegionChain = [village, district, regency, province];
+      tallyStationPda = await createPollingStation(
+        program,
+        tallyElectionPda,
+        TALLY_VILLAGE_CODE,
+        1,
+        300
+      );
+
+      const registrarProposalPda = await createProposal(program, tallyElectionPda, {
+        addRegistrar: { registrar: registrar.publicKey },
+      });
+      await program.methods
+        .addRegistrar(registrar.publicKey)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: tallyElectionPda,
+          proposal: registrarProposalPda,
+        })
+        .rpc();
+
+      const issuerProposalPda = await createProposal(program, tallyElectionPda, {
+        setBallotIssuer: {
+          issuerKey: RISTRETTO_BASEPOINT,
+          issuerAuthority: provider.wallet.publicKey,
+        },
+      });
+      await program.methods
+        .setBallotIssuer(RISTRETTO_BASEPOINT, provider.wallet.publicKey)
+        .accounts({
+          commissioner: provider.wallet.publicKey,
+          election: tallyElectionPda,
+          proposal: issuerProposalPda,
+        })
+        .rpc();
+
+      await transitionElection(program, tallyElectionPda, { registration: {} });
+
+      await airdrop(provider.connection, tallyVoter.publicKey);
+      const election = await program.account.election.fetch(tallyElectionPda);
+      const tallyNikSalt = Buffer.from(election.nikSalt);
+      const voterData = createTestVoterData(tallyVoter.publicKey);
+      const commitment = nikCommitment(tallyNikSalt, voterData.nik);
+      await program.methods
+        .verifyVoter(
+          commitment,
+          voterData.biometricHash,
+          voterData.photoIpfsHash,
+          new BN(getCurrentTimestamp()),
+          voterData.aiScore
+        )
+        .accounts({
+          voter: tallyVoter.publicKey,
+          election: tallyElectionPda,
+          voterCredential: deriveVoterCredentialPDA(
+            tallyElectionPda,
+            tallyVoter.publicKey,
+            program.programId
+          )[0],
+          nikRecord: deriveNikRecordPDA(tallyElectionPda, commitment, program.programId)[0],
+          pollingStation: tallyStationPda,
+          votingTokenMint: mintPda,
+          voterTokenAccount: await getAssociatedTokenAddress(mintPda, tallyVoter.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          registrar: registrar.publicKey,
+          instructionsSysvar: null,
+        })
+        .signers([tallyVoter, registrar])
+        .rpc();
+
+      // Wait for polls to open
+      const wait = (tallyStartTime + 1 - getCurrentTimestamp()) * 1000;
+      if (wait > 0) {
+        await new Promise((resolve) => setTimeout(resolve, wait));
+      }
+      await transitionElection(program, tallyElectionPda, { voting: {} });
+    });
+
+    const castForCandidate = (
+      key: { secret: bigint; nullifier: Buffer },
+      signature: { r: number[]; s: number[] },
+      contentsSignature: { r: number[]; s: number[] },
+      candidateId: number
+    ) =>
+      program.methods
+        .castVote(
+          Array.from(key.nullifier),
+          { blindToken: { signature, contentsSignature } },
+          { candidate: {} },
+          Array(32).fill(0)
+        )
+        .accounts({
+          relayer: provider.wallet.publicKey,
+          election: tallyElectionPda,
+          candidate: deriveCandidatePDA(tallyElectionPda, candidateId, program.programId)[0],
+          pollingStation: tallyStationPda,
+          nullifierRecord: PublicKey.findProgramAddressSync(
+            [Buffer.from("nullifier"), tallyElectionPda.toBuffer(), key.nullifier],
+            program.programId
+          )[0],
+          ballot: deriveBallotPDA(tallyElectionPda, 0, program.programId)[0],
+          zkVerifyingKey: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          [
+            ...tallyRegionChain,
+            ...tallyRegionChain.map(
+              (region) => deriveRegionTallyPDA(region, candidateId, program.programId)[0]
+            ),
+          ].map((pubkey) => ({
+            pubkey,
+            isSigner: false,
+            isWritable: true,
+          }))
+        )
+        .rpc();
+
+    const candidateContents = (candidateId: number) => [
+      Buffer.from([0]), // BallotKind::Candidate
+      Buffer.alloc(32),
+      deriveCandidatePDA(tallyElectionPda, candidateId, program.programId)[0].toBuffer(),
+    ];
+
+    const castKey = ballotKey();
+
+    it("Should count a vote for the ninth candidate in every region", async () => {
+      const key = castKey;
+      const signature = await obtainBallotToken(
+        program,
+        tallyElectionPda,
+        tallyStationPda,
+        tallyVoter,
+        key.nullifier
+      );
+      const contentsSignature = signBallotContents(
+        tallyElectionPda,
+        key,
+        candidateContents(CANDIDATE_COUNT)
+      );
+
+      // A relayer holding the token cannot point it at another candidate
+      try {
+        await castForCandidate(key, signature, contentsSignature, 1);
+
+        expect.fail("Should have thrown InvalidBallotSignature error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("InvalidBallotSignature");
+      }
+
+      await castForCandidate(key, signature, contentsSignature, CANDIDATE_COUNT);
+      const tallies = tallyRegionChain.map(
+        (region) => deriveRegionTallyPDA(region, CANDIDATE_COUNT, program.programId)[0]
+      );
+
+      for (const [i, tallyPda] of tallies.entries()) {
+        // RegionTally is not named by any instruction, so read it raw:
+        // discriminator, election, region, candidate_id, then vote_count
+        const info = (await provider.connection.getAccountInfo(tallyPda))!;
+        expect(info.owner.toString()).to.equal(program.programId.toString());
+        expect(new PublicKey(info.data.subarray(40, 72)).toString()).to.equal(
+          tallyRegionChain[i].toString()
+        );
+        expect(info.data.readUInt32LE(72)).to.equal(CANDIDATE_COUNT);
+        expect(Number(info.data.readBigUInt64LE(76))).to.equal(1);
+      }
+
+      const candidate = await program.account.candidate.fetch(
+        deriveCandidatePDA(tallyElectionPda, CANDIDATE_COUNT, program.programId)[0]
+      );
+      expect(candidate.voteCount.toNumber()).to.equal(1);
+      const village = await program.account.region.fetch(tallyRegionChain[0]);
+      expect(village.votesCast.toNumber()).to.equal(1);
+      console.log("   ✅ Ninth candidate tallied from village to province");
+    });
+
+    it("Should verify the ballot receipt from the nullifier alone", async () => {
+      const [nullifierRecordPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("nullifier"), tallyElectionPda.toBuffer(), castKey.nullifier],
+        program.programId
+      );
+      const [ballotPda] = deriveBallotPDA(tallyElectionPda, 0, program.programId);
+
+      const verification = await program.methods
+        .verifyBallotReceipt(Array.from(castKey.nullifier))
+        .accounts({
+          election: tallyElectionPda,
+          nullifierRecord: nullifierRecordPda,
+          ballot: ballotPda,
+        })
+        .view();
+
+      const ballot = await program.account.ballot.fetch(ballotPda);
+      expect(verification.isValid).to.equal(true);
+      expect(verification.receipt).to.equal(ballot.verificationReceipt);
+      expect(verification.ballotSequence.toNumber()).to.equal(0);
+      console.log("   ✅ Receipt recomputed from the nullifier");
+    });
+
+    it("Should only take blind responses from the issuer wallet", async () => {
+      const [voterCredentialPda] = deriveVoterCredentialPDA(
+        tallyElectionPda,
+        tallyVoter.publicKey,
+        program.programId
+      );
+      const [ballotTokenRequestPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ballot_token"), tallyElectionPda.toBuffer(), voterCredentialPda.toBuffer()],
+        program.programId
+      );
+
+      try {
+        // Whoever posts the response picks the clause
+        await program.methods
+          .issueBallotToken(1, scalarBytes(1n))
+          .accounts({
+            issuer: unauthorizedUser.publicKey,
+            election: tallyElectionPda,
+            ballotTokenRequest: ballotTokenRequestPda,
+          })
+          .signers([unauthorizedUser])
+          .rpc();
+
+        expect.fail("Should have thrown NotBallotIssuer error");
+      } catch (error: any) {
+        expect(error.error.errorCode.code).to.equal("NotBallotIssuer");
+        console.log("   ✅ Correctly rejected a response from another wallet");
+      }
+    });
+  });
+
+  // ============================================================================
+  // 16. Commit-Reveal Tests
+  // ============================================================================
+
+  describe("16. Commit-Reveal", () => {
+    const CANDIDATE_ID = 2;
+    const voter = Keypair.generate();
+    const relayer = Keypair.generate();
+    const key = ballotKey();
+    const salt = randomBytes(32);
+    let bank: Bank;
+    let setup: BankElection;
+    let candidatePda: PublicKey;
+    let ballotPda: PublicKey;
+
+    // sha256(election || nullifier || candidate_id LE || salt)
+    const voteCommitment = (candidateId: number, voteSalt: Buffer) => {
+      const candidateIdBuffer = Buffer.alloc(4);
+      candidateIdBuffer.writeUInt32LE(candidateId);
+      return createHash("sha256")
+        .update(
+          Buffer.concat([setup.electionPda.toBuffer(), key.nullifier, candidateIdBuffer, voteSalt])
+        )
+        .digest();
+    };
+
+    const revealVote = (voteSalt: Buffer, revealer?: Keypair) => {
+      const builder = bank.program.methods
+        .revealVote(CANDIDATE_ID, Array.from(key.nullifier), Array.from(voteSalt))
+        .accounts({
+          revealer: revealer ? revealer.publicKey : bank.provider.wallet.publicKey,
+          election: setup.electionPda,
+          ballot: ballotPda,
+          nullifierRecord: PublicKey.findProgramAddressSync(
+            [Buffer.from("nullifier"), setup.electionPda.toBuffer(), key.nullifier],
+            bank.program.programId
+          )[0],
+          candidate: candidatePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          [
+            ...setup.regionChain,
+            ...setup.regionChain.map(
+              (region) => deriveRegionTallyPDA(region, CANDIDATE_ID, bank.program.programId)[0]
+            ),
+          ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+        );
+      return (revealer ? builder.signers([revealer]) : builder).rpc();
+    };
+
+    before(async () => {
+      bank = await startBank(program.idl);
+      setup = await setupBankElection(bank, {
+        name: "Pemilu Tertutup",
+        votingMode: { commitReveal: {} },
+        candidateIds: [1, 2],
+        registrar,
+        voters: [voter],
+      });
+      fundOnBank(bank, relayer.publicKey);
+      [candidatePda] = deriveCandidatePDA(setup.electionPda, CANDIDATE_ID, bank.program.programId);
+      [ballotPda] = deriveBallotPDA(setup.electionPda, 0, bank.program.programId);
+
+      await warpTo(bank, setup.startTime);
+      await transitionElection(bank.program, setup.electionPda, { voting: {} });
+
+      // The ballot carries only the commitment; no candidate is passed
+      const commitment = voteCommitment(CANDIDATE_ID, salt);
+      const signature = await obtainBallotToken(
+        bank.program,
+        setup.electionPda,
+        setup.pollingStationPda,
+        voter,
+        key.nullifier
+      );
+      const contentsSignature = signBallotContents(setup.electionPda, key, [
+        Buffer.from([0]), // BallotKind::Candidate
+        commitment,
+        Buffer.alloc(32),
+      ]);
+      await bank.program.methods
+        .castVote(
+          Array.from(key.nullifier),
+          { blindToken: { signature, contentsSignature } },
+          { candidate: {} },
+          Array.from(commitment)
+        )
+        .accounts({
+          relayer: bank.provider.wallet.publicKey,
+          election: setup.electionPda,
+          candidate: null,
+          pollingStation: setup.pollingStationPda,
+          nullifierRecord: PublicKey.findProgramAddressSync(
+            [Buffer.from("nullifier"), setup.electionPda.toBuffer(), key.nullifier],
+            bank.program.programId
+          )[0],
+          ballot: ballotPda,
+          zkVerifyingKey: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          setup.regionChain.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+        )
+        .rpc();
+    });
+
+    it("Should not reveal while polls are open", async () => {
+      try {
+        await revealVote(salt);
+
+        expect.fail("Should have thrown RevealWindowClosed error");
+      } catch (error: any) {
+        expect(programErrorCode(bank.program, error)).to.equal("RevealWindowClosed");
+        console.log("   ✅ Correctly refused a reveal during voting");
+      }
+    });
+
+    it("Should reject a reveal with the wrong salt", async () => {
+      await warpTo(bank, setup.endTime + 1);
+      await transitionElection(bank.program, setup.electionPda, { closed: {} });
+
+      try {
+        await revealVote(randomBytes(32));
+
+        expect.fail("Should have thrown InvalidReveal error");
+      } catch (error: any) {
+        expect(programErrorCode(bank.program, error)).to.equal("InvalidReveal");
+        console.log("   ✅ Correctly rejected a wrong salt");
+      }
+    });
+
+    it("Should count the vote once the commitment is opened", async () => {
+      await revealVote(salt);
+
+      const ballot = await bank.program.account.ballot.fetch(ballotPda);
+      expect(ballot.isRevealed).to.equal(true);
+      expect(ballot.candidate.toString()).to.equal(candidatePda.toString());
+      const candidate = await bank.program.account.candidate.fetch(candidatePda);
+      expect(candidate.voteCount.toNumber()).to.equal(1);
+      const election = await bank.program.account.election.fetch(setup.electionPda);
+      expect(election.totalVotesRevealed.toNumber()).to.equal(1);
+      console.log("   ✅ Commitment opened and counted");
+    });
+
+    it("Should not count a ballot revealed twice", async () => {
+      try {
+        // Anyone may submit a reveal, so a relayer can replay the preimage
+        await revealVote(salt, relayer);
+
+        expect.fail("Should have thrown BallotAlreadyRevealed error");
+      } catch (error: any) {
+        expect(programErrorCode(bank.program, error)).to.equal("BallotAlreadyRevealed");
+      }
+
+      const candidate = await bank.program.account.candidate.fetch(candidatePda);
+      expect(candidate.voteCount.toNumber()).to.equal(1);
+      console.log("   ✅ Correctly rejected a second reveal");
+    });
+  });
+
   after(async () => {
     console.log("\n" + "=".repeat(60));
     console.log("📊 TEST SUMMARY");
@@ -816,7 +5527,7 @@ describe("E-Voting Smart Contract Tests", () => {
     const credentials = await program.account.voterCredential.all();
 
     console.log(`   Election: ${election.electionName}`);
-    console.log(`   Active: ${election.isActive}`);
+    console.log(`   Status: ${Object.keys(election.status)[0]}`);
     console.log(`   Registered Voters: ${election.totalRegisteredVoters}`);
     console.log(`   Votes Cast: ${election.totalVotesCast}`);
     console.log(`   Candidates: ${candidates.length}`);